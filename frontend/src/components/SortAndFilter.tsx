@@ -5,7 +5,7 @@
 import { createSignal } from "solid-js";
 import { createStore } from "solid-js/store";
 import { t } from "../translations";
-import { ITask } from "../types";
+import { daysUntilDue, ITask } from "../types";
 
 export type SortFilter = (tasks: ITask[]) => ITask[];
 
@@ -19,8 +19,7 @@ type FilterSettings = {
 };
 
 function timeLeft(task: ITask): number {
-  if ("Overdue" in task.deadline) return -task.deadline.Overdue;
-  return task.deadline.Upcoming;
+  return daysUntilDue(task.deadline) ?? Infinity;
 }
 
 function generateSortFilter(
@@ -35,7 +34,11 @@ function generateSortFilter(
 
         return true;
       })
-      .sort((task1, task2) => timeLeft(task1) - timeLeft(task2));
+      .sort((task1, task2) => {
+        const left1 = timeLeft(task1);
+        const left2 = timeLeft(task2);
+        return left1 === left2 ? 0 : left1 < left2 ? -1 : 1;
+      });
   };
 }
 
@@ -2,14 +2,13 @@
 //
 // SPDX-License-Identifier: MPL-2.0
 
-import clsx from "clsx";
 import { add } from "date-fns";
 import { createSignal } from "solid-js";
 import { Button } from "../design/Button";
 import { FlexGap } from "../design/FlexGap";
 import { formatDate, formatRelativeDate } from "../stores/formatting";
 import { t } from "../translations";
-import { ITask } from "../types";
+import { daysUntilDue, ITask } from "../types";
 import styles from "./Task.module.css";
 import { TaskDoneModal } from "./TaskDoneModal";
 
@@ -23,6 +22,18 @@ export function Task(props: { task: ITask; onUpdate: (task: ITask) => void }) {
         add(lastCompleted, { days: props.task.length_days })
       );
       return t({ en: `due ${due}`, de: `${due} fällig` });
+    }
+
+    const days = daysUntilDue(props.task.deadline);
+    if (days === null) {
+      return "";
+    } else if (
+      props.task.kind === "Calendar" ||
+      props.task.kind === "OneOff" ||
+      props.task.kind === "Triggered"
+    ) {
+      const due = formatRelativeDate(add(new Date(), { days }));
+      return t({ en: `due ${due}`, de: `${due} fällig` });
     } else {
       const start = add(lastCompleted, { days: 1 });
       const end = add(lastCompleted, { days: props.task.length_days });
@@ -30,6 +41,15 @@ export function Task(props: { task: ITask; onUpdate: (task: ITask) => void }) {
     }
   };
 
+  const urgencyClass = () => {
+    const deadline = props.task.deadline;
+    if (deadline === "Unscheduled") return undefined;
+    if (deadline === "DueToday" || "DueSoon" in deadline) return styles.dueSoon;
+    if ("Overdue" in deadline) return styles.overdue;
+    if ("BadlyOverdue" in deadline) return styles.badlyOverdue;
+    return undefined;
+  };
+
   return (
     <div ref={setElement} class={styles.taskbox}>
       <div class={styles.taskboxHeader}>
@@ -38,7 +58,7 @@ export function Task(props: { task: ITask; onUpdate: (task: ITask) => void }) {
       </div>
       <div class={styles.taskboxItem}>
         <span
-          class={clsx({ [styles.overdue]: "Overdue" in props.task.deadline })}
+          class={urgencyClass()}
           textContent={dueDate()}
         />
         <FlexGap />
@@ -20,14 +20,16 @@ export function TaskDoneModal(props: {
   onConfirm: (doneBy: ITask) => void;
 }) {
   const [, authActions] = useAuth();
-  const [doneBy, setDoneBy] = createSignal(props.task.assigned_to);
+  const [doneBy, setDoneBy] = createSignal(
+    props.task.assigned_to ?? props.task.participants[0]
+  );
   const [error, setError] = createSignal<string | null>(null);
 
   createEffect(
     on(
       () => [props.open, props.task.assigned_to],
       () => {
-        setDoneBy(props.task.assigned_to);
+        setDoneBy(props.task.assigned_to ?? props.task.participants[0]);
       }
     )
   );
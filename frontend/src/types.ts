@@ -4,13 +4,121 @@
 
 export type TaskId = number & { __task_id_brand: "TASK_ID_BRAND" };
 
+export type TagId = number & { __tag_id_brand: "TAG_ID_BRAND" };
+
+export type ITag = {
+  id: TagId;
+  name: string;
+};
+
+export type StepId = number & { __step_id_brand: "STEP_ID_BRAND" };
+
+export type IChecklistStep = {
+  id: StepId;
+  name: string;
+};
+
+export type Rotation =
+  | "RoundRobin"
+  | "LeastRecentlyDone"
+  | { FewestCompletions: { days: number } }
+  | { FixedOwner: string }
+  | { SeededRandom: { seed: number } };
+
+export type CatchUp = "Accumulate" | "Collapse" | "Skip";
+
+export type Deadline =
+  | { Upcoming: number }
+  | { DueSoon: number }
+  | "DueToday"
+  | { Overdue: number }
+  | { BadlyOverdue: number }
+  | "Unscheduled";
+
+export type Urgency = {
+  due_soon_days: number;
+  grace_days: number | null;
+};
+
+/** Days until a task is due (negative if overdue), or null if it has no due date */
+export function daysUntilDue(deadline: Deadline): number | null {
+  if (deadline === "Unscheduled") return null;
+  if (deadline === "DueToday") return 0;
+  if ("Overdue" in deadline) return -deadline.Overdue;
+  if ("BadlyOverdue" in deadline) return -deadline.BadlyOverdue;
+  if ("DueSoon" in deadline) return deadline.DueSoon;
+  return deadline.Upcoming;
+}
+
 export type ITask = {
   id: TaskId;
   name: string;
-  kind: "Schedule" | "Interval";
-  assigned_to: string;
-  deadline: { Overdue: number } | { Upcoming: number };
+  description: string | null;
+  kind: "Schedule" | "Interval" | "Calendar" | "OneOff" | "Triggered";
+  rotation: Rotation;
+  catch_up: CatchUp;
+  recurrence: string | null;
+  active_window: string | null;
+  active: boolean;
+  assigned_to: string | null;
+  deadline: Deadline;
+  urgency: Urgency;
+  points: number;
   length_days: number;
   last_completed: string;
   participants: string[];
+  tags: ITag[];
+  checklist: IChecklistStep[];
+  last_steps_done: StepId[] | null;
+};
+
+export type AttachmentId = number & {
+  __attachment_id_brand: "ATTACHMENT_ID_BRAND";
+};
+
+export type IAttachment = {
+  id: AttachmentId;
+  content_type: string;
+  size: number;
+};
+
+export type CompletionId = number & {
+  __completion_id_brand: "COMPLETION_ID_BRAND";
+};
+
+export type IHistoryEntry = {
+  id: CompletionId;
+  completed_by: string;
+  helpers: string[];
+  completed_on: string;
+  note: string | null;
+  attachments: IAttachment[];
+};
+
+export type LedgerEntryId = number & {
+  __ledger_entry_id_brand: "LEDGER_ENTRY_ID_BRAND";
+};
+
+export type LedgerKind = "Completion" | "Adjustment" | "Redemption";
+
+export type ILedgerEntry = {
+  id: LedgerEntryId;
+  person: string;
+  entry_on: string;
+  kind: LedgerKind;
+  points: number;
+  task_id: TaskId | null;
+  description: string | null;
+};
+
+export type IBalance = {
+  person: string;
+  points: number;
+};
+
+export type ITemplate = {
+  id: string;
+  names: Record<string, string>;
+  routine: "Schedule" | "Interval" | "Calendar" | "OneOff" | "Triggered";
+  duration: number;
 };
@@ -0,0 +1,12 @@
+-- SPDX-FileCopyrightText: 2023 Jonathan Frere
+--
+-- SPDX-License-Identifier: MPL-2.0
+-- next_due and assigned_to are worked out by the task store whenever a task
+-- changes, so that tasks can be filtered and sorted by them in queries
+ALTER TABLE tasks
+ADD COLUMN next_due text;
+
+ALTER TABLE tasks
+ADD COLUMN assigned_to integer REFERENCES users (id);
+
+CREATE INDEX tasks_next_due ON tasks (next_due);
\ No newline at end of file
@@ -0,0 +1,22 @@
+-- SPDX-FileCopyrightText: 2023 Jonathan Frere
+--
+-- SPDX-License-Identifier: MPL-2.0
+CREATE TABLE
+  tags (id integer primary key autoincrement);
+
+CREATE TABLE
+  tag_translations (
+    tag_id integer NOT NULL REFERENCES tags (id),
+    lang text NOT NULL,
+    tag_name text NOT NULL,
+    UNIQUE (tag_id, lang)
+  );
+
+CREATE TABLE
+  task_tag_link (
+    task_id integer NOT NULL REFERENCES tasks (id),
+    tag_id integer NOT NULL REFERENCES tags (id),
+    UNIQUE (task_id, tag_id)
+  );
+
+CREATE INDEX task_tag_link_tag_id ON task_tag_link (tag_id);
\ No newline at end of file
@@ -0,0 +1,26 @@
+-- SPDX-FileCopyrightText: 2023 Jonathan Frere
+--
+-- SPDX-License-Identifier: MPL-2.0
+ALTER TABLE task_translations
+ADD COLUMN description text;
+
+CREATE TABLE
+  checklist_steps (
+    id integer primary key autoincrement,
+    task_id integer NOT NULL REFERENCES tasks (id),
+    position integer NOT NULL
+  );
+
+CREATE TABLE
+  checklist_step_translations (
+    step_id integer NOT NULL REFERENCES checklist_steps (id),
+    lang text NOT NULL,
+    step_name text NOT NULL,
+    UNIQUE (step_id, lang)
+  );
+
+CREATE INDEX checklist_steps_task_id ON checklist_steps (task_id, position);
+
+-- a JSON array of the checklist steps that were done, if these were recorded
+ALTER TABLE completions
+ADD COLUMN steps_done text;
\ No newline at end of file
@@ -0,0 +1,13 @@
+-- SPDX-FileCopyrightText: 2023 Jonathan Frere
+--
+-- SPDX-License-Identifier: MPL-2.0
+CREATE TABLE
+  absences (
+    id integer primary key autoincrement,
+    user_id integer NOT NULL REFERENCES users (id),
+    -- both dates are inclusive
+    starts_on text NOT NULL,
+    ends_on text NOT NULL
+  );
+
+CREATE INDEX absences_user_id ON absences (user_id, starts_on);
\ No newline at end of file
@@ -0,0 +1,5 @@
+-- SPDX-FileCopyrightText: 2023 Jonathan Frere
+--
+-- SPDX-License-Identifier: MPL-2.0
+ALTER TABLE tasks
+ADD COLUMN active_window text;
\ No newline at end of file
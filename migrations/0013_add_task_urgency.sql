@@ -0,0 +1,8 @@
+-- SPDX-FileCopyrightText: 2023 Jonathan Frere
+--
+-- SPDX-License-Identifier: MPL-2.0
+ALTER TABLE tasks
+ADD COLUMN due_soon_days integer NOT NULL DEFAULT 0;
+
+ALTER TABLE tasks
+ADD COLUMN grace_days integer;
\ No newline at end of file
@@ -0,0 +1,16 @@
+-- SPDX-FileCopyrightText: 2023 Jonathan Frere
+--
+-- SPDX-License-Identifier: MPL-2.0
+CREATE TABLE
+  household (
+    id integer primary key CHECK (id = 1),
+    timezone text
+  );
+
+INSERT INTO
+  household (id, timezone)
+VALUES
+  (1, NULL);
+
+ALTER TABLE users
+ADD COLUMN timezone text;
\ No newline at end of file
@@ -0,0 +1,10 @@
+-- SPDX-FileCopyrightText: 2023 Jonathan Frere
+--
+-- SPDX-License-Identifier: MPL-2.0
+CREATE TABLE
+  calendar_feeds (
+    id integer primary key autoincrement,
+    secret text NOT NULL UNIQUE,
+    user_id integer REFERENCES users (id),
+    lang text NOT NULL
+  );
\ No newline at end of file
@@ -0,0 +1,56 @@
+-- SPDX-FileCopyrightText: 2023 Jonathan Frere
+--
+-- SPDX-License-Identifier: MPL-2.0
+CREATE TABLE
+  households (
+    id integer primary key autoincrement,
+    name text NOT NULL UNIQUE COLLATE NOCASE,
+    timezone text
+  );
+
+-- everything that already exists belongs to the first household
+INSERT INTO
+  households (id, name, timezone)
+SELECT
+  id,
+  'Home',
+  timezone
+FROM
+  household;
+
+DROP TABLE household;
+
+CREATE TABLE
+  household_members (
+    household_id integer NOT NULL REFERENCES households (id),
+    user_id integer NOT NULL REFERENCES users (id),
+    UNIQUE (household_id, user_id)
+  );
+
+INSERT INTO
+  household_members (household_id, user_id)
+SELECT
+  1,
+  id
+FROM
+  users;
+
+CREATE INDEX household_members_user_id ON household_members (user_id);
+
+-- columns added to existing tables can't have a REFERENCES constraint with a
+-- non-NULL default, so these aren't declared as foreign keys
+ALTER TABLE tasks
+ADD COLUMN household_id integer NOT NULL DEFAULT 1;
+
+ALTER TABLE tags
+ADD COLUMN household_id integer NOT NULL DEFAULT 1;
+
+ALTER TABLE calendar_feeds
+ADD COLUMN household_id integer NOT NULL DEFAULT 1;
+
+-- the household chosen when logging in, or NULL for tokens from before there
+-- were households
+ALTER TABLE tokens
+ADD COLUMN household_id integer REFERENCES households (id);
+
+CREATE INDEX tasks_household_id ON tasks (household_id);
\ No newline at end of file
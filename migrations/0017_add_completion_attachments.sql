@@ -0,0 +1,124 @@
+-- SPDX-FileCopyrightText: 2023 Jonathan Frere
+--
+-- SPDX-License-Identifier: MPL-2.0
+-- completions are rebuilt with an id column, so that attachments can refer to
+-- them (a rowid may change when the database is vacuumed).  The view is
+-- dropped first, so that it doesn't stop the old table from being replaced.
+DROP VIEW grouped_tasks;
+
+CREATE TABLE
+  new_completions (
+    id integer primary key autoincrement,
+    task_id integer NOT NULL REFERENCES tasks (id),
+    completed_by integer NOT NULL REFERENCES users (id),
+    completed_on text NOT NULL,
+    initial integer NOT NULL DEFAULT FALSE,
+    steps_done text,
+    note text
+  );
+
+INSERT INTO
+  new_completions (
+    id,
+    task_id,
+    completed_by,
+    completed_on,
+    initial,
+    steps_done
+  )
+SELECT
+  rowid,
+  task_id,
+  completed_by,
+  completed_on,
+  initial,
+  steps_done
+FROM
+  completions;
+
+DROP TABLE completions;
+
+ALTER TABLE new_completions
+RENAME TO completions;
+
+CREATE INDEX completions_completed_on_task_id ON completions (completed_on, task_id);
+
+CREATE TABLE
+  completion_attachments (
+    id integer primary key autoincrement,
+    completion_id integer NOT NULL REFERENCES completions (id),
+    file_name text NOT NULL UNIQUE,
+    content_type text NOT NULL,
+    size integer NOT NULL
+  );
+
+CREATE INDEX completion_attachments_completion_id ON completion_attachments (completion_id);
+
+CREATE VIEW
+  grouped_tasks AS
+WITH
+  participants AS (
+    SELECT
+      _p.position as ordering,
+      task_id,
+      _u.username
+    FROM
+      task_participant_link _p
+      INNER JOIN users _u ON _u.id = _p.user_id
+    ORDER BY
+      _p.position
+  )
+SELECT
+  tasks.id as id,
+  tasks.kind as kind,
+  tasks.duration as duration,
+  json_group_array (participants.username) as participants,
+  CASE tasks.kind
+    WHEN "Schedule" THEN date (
+      first_completion.completed_on,
+      '+' || (tasks.duration * coalesce(completion_count, 0)) || ' days'
+    )
+    ELSE last_completion.completed_on
+  END as last_completed,
+  u_completed.username as last_completed_by,
+  first_completion.completed_on as started_on,
+  count(participants.ordering) as _ignore_me
+FROM
+  tasks
+  INNER JOIN participants ON participants.task_id = tasks.id
+  INNER JOIN completions last_completion ON tasks.id = last_completion.task_id
+  AND last_completion.rowid = (
+    SELECT
+      c2.rowid
+    FROM
+      completions AS c2
+    WHERE
+      c2.task_id = tasks.id
+    ORDER BY
+      c2.completed_on DESC,
+      c2.rowid DESC
+    LIMIT
+      1
+  )
+  INNER JOIN users u_completed ON u_completed.id = last_completion.completed_by
+  INNER JOIN completions first_completion ON tasks.id = first_completion.task_id
+  AND first_completion.completed_on = (
+    Select
+      max(completed_on)
+    from
+      completions as c3
+    where
+      c3.task_id = tasks.id
+      AND c3.initial = TRUE
+  )
+  LEFT JOIN (
+    select
+      task_id,
+      count(*) as completion_count
+    FROM
+      completions _ccount
+    WHERE
+      _ccount.initial = FALSE
+  ) c4 ON c4.task_id = tasks.id
+GROUP BY
+  tasks.id;
\ No newline at end of file
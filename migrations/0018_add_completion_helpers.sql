@@ -0,0 +1,10 @@
+-- SPDX-FileCopyrightText: 2023 Jonathan Frere
+--
+-- SPDX-License-Identifier: MPL-2.0
+-- everyone who did a task together with the person it was recorded against
+CREATE TABLE
+  completion_helpers (
+    completion_id integer NOT NULL REFERENCES completions (id),
+    user_id integer NOT NULL REFERENCES users (id),
+    UNIQUE (completion_id, user_id)
+  );
\ No newline at end of file
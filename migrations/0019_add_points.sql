@@ -0,0 +1,22 @@
+-- SPDX-FileCopyrightText: 2023 Jonathan Frere
+--
+-- SPDX-License-Identifier: MPL-2.0
+ALTER TABLE tasks
+ADD COLUMN points integer NOT NULL DEFAULT 0;
+
+-- every change to someone's points: earned by doing a task, given or taken
+-- away by hand, or spent on a reward.
+CREATE TABLE
+  points_ledger (
+    id integer primary key autoincrement,
+    household_id integer NOT NULL REFERENCES households (id),
+    user_id integer NOT NULL REFERENCES users (id),
+    entry_on text NOT NULL,
+    kind text NOT NULL,
+    points integer NOT NULL,
+    task_id integer REFERENCES tasks (id),
+    completion_id integer REFERENCES completions (id),
+    description text
+  );
+
+CREATE INDEX points_ledger_household_id_user_id ON points_ledger (household_id, user_id);
\ No newline at end of file
@@ -0,0 +1,14 @@
+-- SPDX-FileCopyrightText: 2023 Jonathan Frere
+--
+-- SPDX-License-Identifier: MPL-2.0
+-- tasks that come from a household config file are matched up with it by
+-- their key
+ALTER TABLE tasks
+ADD COLUMN config_key text;
+
+-- tasks that have been taken out of the config file are archived rather than
+-- deleted, so that their history is kept
+ALTER TABLE tasks
+ADD COLUMN archived_on text;
+
+CREATE UNIQUE INDEX tasks_household_id_config_key ON tasks (household_id, config_key);
\ No newline at end of file
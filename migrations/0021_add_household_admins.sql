@@ -0,0 +1,6 @@
+-- SPDX-FileCopyrightText: 2023 Jonathan Frere
+--
+-- SPDX-License-Identifier: MPL-2.0
+-- admins can export and import the whole household
+ALTER TABLE household_members
+ADD COLUMN admin boolean NOT NULL DEFAULT FALSE;
\ No newline at end of file
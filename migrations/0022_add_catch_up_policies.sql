@@ -0,0 +1,75 @@
+-- SPDX-FileCopyrightText: 2023 Jonathan Frere
+--
+-- SPDX-License-Identifier: MPL-2.0
+ALTER TABLE tasks
+ADD COLUMN catch_up text NOT NULL DEFAULT 'Accumulate';
+
+-- a schedule task is done up to the start of the cycle it is next due in,
+-- which depends on its catch-up policy, so this now comes from the stored
+-- due date rather than from counting completions
+DROP VIEW grouped_tasks;
+
+CREATE VIEW
+  grouped_tasks AS
+WITH
+  participants AS (
+    SELECT
+      _p.position as ordering,
+      task_id,
+      _u.username
+    FROM
+      task_participant_link _p
+      INNER JOIN users _u ON _u.id = _p.user_id
+    ORDER BY
+      _p.position
+  )
+SELECT
+  tasks.id as id,
+  tasks.kind as kind,
+  tasks.duration as duration,
+  json_group_array (participants.username) as participants,
+  CASE tasks.kind
+    WHEN "Schedule" THEN coalesce(
+      date (tasks.next_due, '-' || tasks.duration || ' days'),
+      last_completion.completed_on
+    )
+    ELSE last_completion.completed_on
+  END as last_completed,
+  u_completed.username as last_completed_by,
+  first_completion.completed_on as started_on,
+  count(participants.ordering) as _ignore_me
+FROM
+  tasks
+  INNER JOIN participants ON participants.task_id = tasks.id
+  INNER JOIN completions last_completion ON tasks.id = last_completion.task_id
+  AND last_completion.rowid = (
+    SELECT
+      c2.rowid
+    FROM
+      completions AS c2
+    WHERE
+      c2.task_id = tasks.id
+    ORDER BY
+      c2.completed_on DESC,
+      c2.rowid DESC
+    LIMIT
+      1
+  )
+  INNER JOIN users u_completed ON u_completed.id = last_completion.completed_by
+  INNER JOIN completions first_completion ON tasks.id = first_completion.task_id
+  AND first_completion.rowid = (
+    SELECT
+      c3.rowid
+    FROM
+      completions AS c3
+    WHERE
+      c3.task_id = tasks.id
+      AND c3.initial = TRUE
+    ORDER BY
+      c3.completed_on DESC,
+      c3.rowid DESC
+    LIMIT
+      1
+  )
+GROUP BY
+  tasks.id;
\ No newline at end of file
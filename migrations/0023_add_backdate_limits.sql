@@ -0,0 +1,7 @@
+-- SPDX-FileCopyrightText: 2023 Jonathan Frere
+--
+-- SPDX-License-Identifier: MPL-2.0
+-- how many days before today a completion can be recorded for, or NULL for
+-- no limit
+ALTER TABLE households
+ADD COLUMN backdate_days integer;
\ No newline at end of file
@@ -6,30 +6,35 @@ use std::fmt::Debug;
 
 use axum::{
     extract::State,
-    http::Request,
+    http::{header, Request, StatusCode},
     middleware::Next,
     response::{IntoResponse, Response},
     routing::post,
     Json, Router,
 };
+use base64::{engine::general_purpose::STANDARD, Engine};
 
 mod store;
 mod types;
 
 pub use store::AuthStore;
-pub use types::{AuthError, Token};
+pub use types::{AuthError, ExportedUser, HouseholdId, Token};
 
 #[derive(Debug, serde::Deserialize)]
 struct LoginArgs {
     username: String,
     password: String,
+    /// Name of the household to log in to, if not the user's first one
+    household: Option<String>,
 }
 
 async fn login(
     State(auth): State<AuthStore>,
     args: Json<LoginArgs>,
 ) -> Result<Json<Token>, AuthError> {
-    let token = auth.login(&args.username, &args.password).await?;
+    let token = auth
+        .login_to(&args.username, &args.password, args.household.as_deref())
+        .await?;
     Ok(Json(token))
 }
 
@@ -39,7 +44,20 @@ pub fn routes(auth_state: AuthStore) -> Router {
         .with_state(auth_state)
 }
 
-async fn evaluate_token<B: Debug>(auth: &AuthStore, request: &Request<B>) -> Result<(), AuthError> {
+/// Any request can be made on behalf of another household the user is a member
+/// of by naming it in this header.
+fn requested_household<B>(request: &Request<B>) -> Option<&str> {
+    request
+        .headers()
+        .get("household")
+        .and_then(|h| h.to_str().ok())
+}
+
+async fn evaluate_token<B: Debug>(
+    auth: &AuthStore,
+    request: &Request<B>,
+    admin: bool,
+) -> Result<HouseholdId, AuthError> {
     let token = request
         .headers()
         .get("token")
@@ -47,18 +65,102 @@ async fn evaluate_token<B: Debug>(auth: &AuthStore, request: &Request<B>) -> Res
         .ok_or(AuthError::MissingToken)?;
 
     let token = token.parse()?;
-    auth.validate_token(&token).await?;
-    Ok(())
+    match admin {
+        true => {
+            auth.validate_admin_token(&token, requested_household(request))
+                .await
+        }
+        false => {
+            auth.validate_token(&token, requested_household(request))
+                .await
+        }
+    }
 }
 
+/// Checks the request's token, and makes the household it is for available to
+/// handlers as a [`HouseholdId`] extension.
 pub async fn login_middleware<B: Debug>(
     State(auth): State<AuthStore>,
-    request: Request<B>,
+    mut request: Request<B>,
+    next: Next<B>,
+) -> Response {
+    match evaluate_token(&auth, &request, false).await {
+        Ok(household) => {
+            request.extensions_mut().insert(household);
+            next.run(request).await
+        }
+        Err(error) => error.into_response(),
+    }
+}
+
+/// The same as [`login_middleware`], but only lets admins of the household
+/// through.
+pub async fn admin_middleware<B: Debug>(
+    State(auth): State<AuthStore>,
+    mut request: Request<B>,
     next: Next<B>,
 ) -> Response {
-    if let Err(error) = evaluate_token(&auth, &request).await {
-        return error.into_response();
+    match evaluate_token(&auth, &request, true).await {
+        Ok(household) => {
+            request.extensions_mut().insert(household);
+            next.run(request).await
+        }
+        Err(error) => error.into_response(),
     }
+}
+
+/// The user who authenticated a request with HTTP Basic credentials.
+#[derive(Debug, Clone)]
+pub struct BasicUser(pub String);
+
+async fn evaluate_basic_auth<B: Debug>(
+    auth: &AuthStore,
+    request: &Request<B>,
+) -> Result<(BasicUser, HouseholdId), AuthError> {
+    let credentials = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.strip_prefix("Basic "))
+        .and_then(|encoded| STANDARD.decode(encoded.trim()).ok())
+        .and_then(|decoded| String::from_utf8(decoded).ok())
+        .ok_or(AuthError::MissingCredentials)?;
 
-    next.run(request).await
+    let (username, password) = credentials
+        .split_once(':')
+        .ok_or(AuthError::MissingCredentials)?;
+    let household = auth
+        .check_password(username, password, requested_household(request))
+        .await?;
+    Ok((BasicUser(username.to_owned()), household))
+}
+
+/// Authenticates requests from clients that can only send a username and
+/// password (e.g. CalDAV apps), and makes the user and their household
+/// available to handlers as [`BasicUser`] and [`HouseholdId`] extensions.
+pub async fn basic_auth_middleware<B: Debug>(
+    State(auth): State<AuthStore>,
+    mut request: Request<B>,
+    next: Next<B>,
+) -> Response {
+    match evaluate_basic_auth(&auth, &request).await {
+        Ok((user, household)) => {
+            request.extensions_mut().insert(user);
+            request.extensions_mut().insert(household);
+            next.run(request).await
+        }
+        Err(error @ AuthError::DbError(_)) => error.into_response(),
+        Err(error) => {
+            tracing::warn!({ details = error.to_string() }, "Authentication failure");
+            (
+                StatusCode::UNAUTHORIZED,
+                [(
+                    header::WWW_AUTHENTICATE,
+                    r#"Basic realm="homie", charset="UTF-8""#,
+                )],
+                error.to_string(),
+            )
+                .into_response()
+        }
+    }
 }
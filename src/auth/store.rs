@@ -7,7 +7,9 @@ use rand_core::OsRng;
 use sqlx::SqlitePool;
 use uuid::Uuid;
 
-use super::types::{AuthError, Token, UserId};
+use crate::db;
+
+use super::types::{AuthError, ExportedUser, HouseholdId, Token, UserId};
 
 #[derive(Clone)]
 pub struct AuthStore {
@@ -34,7 +36,101 @@ impl AuthStore {
         }
     }
 
+    /// Logs in to the first household the user is a member of.
     pub async fn login(&self, username: &str, password: &str) -> Result<Token, AuthError> {
+        self.login_to(username, password, None).await
+    }
+
+    /// Logs in to the household with the given name, or to the first household
+    /// the user is a member of if `household` is `None`.
+    pub async fn login_to(
+        &self,
+        username: &str,
+        password: &str,
+        household: Option<&str>,
+    ) -> Result<Token, AuthError> {
+        let id = self.verify_password(username, password).await?;
+        let household_id = self.member_household(id, household, None).await?;
+
+        let token = Token::from_uuid(Uuid::new_v4());
+        sqlx::query("INSERT INTO tokens (id, token, household_id) VALUES (?, ?, ?)")
+            .bind(id)
+            .bind(&token)
+            .bind(household_id)
+            .execute(&self.conn)
+            .await?;
+
+        Ok(token)
+    }
+
+    /// Checks a token, and works out which household the request is for: the
+    /// one named in `household` if given, or else the one chosen when logging in.
+    pub async fn validate_token(
+        &self,
+        token: &Token,
+        household: Option<&str>,
+    ) -> Result<HouseholdId, AuthError> {
+        self.token_household(token, household)
+            .await
+            .map(|(_, household_id)| household_id)
+    }
+
+    /// The same as [`AuthStore::validate_token`], but also checks that the
+    /// user is an admin of the household.
+    pub async fn validate_admin_token(
+        &self,
+        token: &Token,
+        household: Option<&str>,
+    ) -> Result<HouseholdId, AuthError> {
+        let (id, household_id) = self.token_household(token, household).await?;
+        let (admin,) = sqlx::query_as::<_, (bool,)>(
+            "SELECT admin FROM household_members WHERE user_id = ? AND household_id = ?",
+        )
+        .bind(id)
+        .bind(household_id)
+        .fetch_one(&self.conn)
+        .await?;
+        if !admin {
+            Err(AuthError::NotAdmin)?;
+        }
+
+        Ok(household_id)
+    }
+
+    async fn token_household(
+        &self,
+        token: &Token,
+        household: Option<&str>,
+    ) -> Result<(UserId, HouseholdId), AuthError> {
+        let (id, household_id) = sqlx::query_as::<_, (UserId, Option<HouseholdId>)>(
+            "SELECT id, household_id FROM tokens WHERE token = ?",
+        )
+        .bind(token)
+        .fetch_optional(&self.conn)
+        .await?
+        .ok_or(AuthError::UnknownToken(None))?;
+
+        let household_id = match household {
+            Some(_) => self.member_household(id, household, None).await?,
+            None => self.member_household(id, None, household_id).await?,
+        };
+        Ok((id, household_id))
+    }
+
+    /// Checks a username and password without handing out a token, for
+    /// clients that send their credentials with every request.  Works out the
+    /// household in the same way as [`AuthStore::login_to`].
+    pub async fn check_password(
+        &self,
+        username: &str,
+        password: &str,
+        household: Option<&str>,
+    ) -> Result<HouseholdId, AuthError> {
+        let id = self.verify_password(username, password).await?;
+        self.member_household(id, household, None).await
+    }
+
+    async fn verify_password(&self, username: &str, password: &str) -> Result<UserId, AuthError> {
         let stored_hash = sqlx::query_as::<_, (UserId, String)>(
             "SELECT id, hash FROM users WHERE username = ? COLLATE NOCASE",
         )
@@ -55,45 +151,215 @@ impl AuthStore {
             None => Err(AuthError::UserPasswordMismatch)?,
         };
 
-        let token = Token::from_uuid(Uuid::new_v4());
-        sqlx::query("INSERT INTO tokens (id, token) VALUES (?, ?)")
-            .bind(&id)
-            .bind(&token)
+        Ok(id)
+    }
+
+    /// Finds a household the user is a member of, optionally limited to the one
+    /// with the given name or ID.  If several match, the oldest one is used.
+    async fn member_household(
+        &self,
+        id: UserId,
+        name: Option<&str>,
+        household_id: Option<HouseholdId>,
+    ) -> Result<HouseholdId, AuthError> {
+        let (household_id,) = sqlx::query_as::<_, (HouseholdId,)>(
+            "SELECT households.id FROM household_members
+             INNER JOIN households ON households.id = household_members.household_id
+             WHERE household_members.user_id = ?1
+             AND (?2 IS NULL OR households.name = ?2)
+             AND (?3 IS NULL OR households.id = ?3)
+             ORDER BY households.id LIMIT 1",
+        )
+        .bind(id)
+        .bind(name)
+        .bind(household_id)
+        .fetch_optional(&self.conn)
+        .await?
+        .ok_or(AuthError::NotHouseholdMember)?;
+
+        Ok(household_id)
+    }
+
+    /// Creates a user who is a member of the given household.
+    pub async fn create_user(
+        &self,
+        username: &str,
+        password: &str,
+        household: HouseholdId,
+    ) -> Result<(), AuthError> {
+        self.create_user_with_hash(username, &self.hash(password), household)
+            .await
+    }
+
+    /// Creates a user with an already hashed password, as a member of the
+    /// given household.  Users with an empty hash can't log in until their
+    /// password is set.
+    pub async fn create_user_with_hash(
+        &self,
+        username: &str,
+        hash: &str,
+        household: HouseholdId,
+    ) -> Result<(), AuthError> {
+        sqlx::query("INSERT INTO users (username, hash) VALUES (?, ?)")
+            .bind(username)
+            .bind(hash)
             .execute(&self.conn)
             .await?;
+        self.join_household(username, household).await
+    }
 
-        Ok(token)
+    fn hash(&self, password: &str) -> String {
+        self.hasher
+            .hash_password(password.as_bytes(), &SaltString::generate(OsRng))
+            .unwrap()
+            .to_string()
     }
 
-    pub async fn validate_token(&self, token: &Token) -> Result<(), AuthError> {
-        let (found,) = sqlx::query_as::<_, (u8,)>("SELECT COUNT(*) FROM tokens WHERE token = ?")
-            .bind(token)
-            .fetch_one(&self.conn)
+    pub async fn create_household(&self, name: &str) -> Result<HouseholdId, AuthError> {
+        let mut transaction = self.conn.begin().await?;
+
+        let household_id: HouseholdId = db::insert_returning_id(
+            &mut transaction,
+            sqlx::query_as("INSERT INTO households (name) VALUES (?) RETURNING households.id")
+                .bind(name),
+        )
+        .await?
+        .ok_or(sqlx::Error::RowNotFound)?;
+
+        transaction.commit().await?;
+        Ok(household_id)
+    }
+
+    pub async fn household_named(&self, name: &str) -> Result<HouseholdId, AuthError> {
+        let (household_id,) =
+            sqlx::query_as::<_, (HouseholdId,)>("SELECT id FROM households WHERE name = ?")
+                .bind(name)
+                .fetch_optional(&self.conn)
+                .await?
+                .ok_or_else(|| AuthError::UnknownHousehold(name.to_owned()))?;
+
+        Ok(household_id)
+    }
+
+    /// Lists every household, e.g. for maintenance that has to go through all of them.
+    pub async fn households(&self) -> Result<Vec<HouseholdId>, AuthError> {
+        let households = sqlx::query_as::<_, (HouseholdId,)>("SELECT id FROM households")
+            .fetch_all(&self.conn)
             .await?;
+        Ok(households.into_iter().map(|(id,)| id).collect())
+    }
 
-        if found > 0 {
-            Ok(())
-        } else {
-            Err(AuthError::UnknownToken(None))
+    /// Makes an existing user a member of another household as well.  Joining a
+    /// household twice does nothing.
+    pub async fn join_household(
+        &self,
+        username: &str,
+        household: HouseholdId,
+    ) -> Result<(), AuthError> {
+        if !self.user_exists(username).await? {
+            Err(AuthError::UnknownUser(username.to_owned()))?;
         }
+
+        sqlx::query(
+            "INSERT OR IGNORE INTO household_members (household_id, user_id)
+             SELECT ?, id FROM users WHERE username = ? COLLATE NOCASE",
+        )
+        .bind(household)
+        .bind(username)
+        .execute(&self.conn)
+        .await?;
+
+        Ok(())
     }
 
-    pub async fn create_user(&self, username: &str, password: &str) -> Result<(), AuthError> {
-        let hash = self
-            .hasher
-            .hash_password(password.as_bytes(), &SaltString::generate(OsRng))
-            .unwrap()
-            .to_string();
+    /// Lets a member of a household export and import it, or stops them
+    /// from doing so.
+    pub async fn set_admin(
+        &self,
+        username: &str,
+        household: HouseholdId,
+        admin: bool,
+    ) -> Result<(), AuthError> {
+        let result = sqlx::query(
+            "UPDATE household_members SET admin = ?
+             WHERE household_id = ?
+             AND user_id = (SELECT id FROM users WHERE username = ? COLLATE NOCASE)",
+        )
+        .bind(admin)
+        .bind(household)
+        .bind(username)
+        .execute(&self.conn)
+        .await?;
+        if result.rows_affected() == 0 {
+            Err(AuthError::NotHouseholdMember)?;
+        }
 
-        sqlx::query("INSERT INTO users (username, hash) VALUES (?, ?)")
+        Ok(())
+    }
+
+    pub async fn set_password(&self, username: &str, password: &str) -> Result<(), AuthError> {
+        let result = sqlx::query("UPDATE users SET hash = ? WHERE username = ? COLLATE NOCASE")
+            .bind(self.hash(password))
             .bind(username)
-            .bind(&hash)
             .execute(&self.conn)
             .await?;
+        if result.rows_affected() == 0 {
+            Err(AuthError::UnknownUser(username.to_owned()))?;
+        }
 
         Ok(())
     }
 
+    pub async fn user_exists(&self, username: &str) -> Result<bool, AuthError> {
+        let (found,) = sqlx::query_as::<_, (u8,)>(
+            "SELECT COUNT(*) FROM users WHERE username = ? COLLATE NOCASE",
+        )
+        .bind(username)
+        .fetch_one(&self.conn)
+        .await?;
+        Ok(found > 0)
+    }
+
+    /// Lists the usernames of everyone in a household.
+    pub async fn members(&self, household: HouseholdId) -> Result<Vec<String>, AuthError> {
+        let members = sqlx::query_as::<_, (String,)>(
+            "SELECT users.username FROM household_members
+             INNER JOIN users ON users.id = household_members.user_id
+             WHERE household_members.household_id = ?
+             ORDER BY users.username",
+        )
+        .bind(household)
+        .fetch_all(&self.conn)
+        .await?;
+        Ok(members.into_iter().map(|(username,)| username).collect())
+    }
+
+    /// Lists everyone in a household, along with their password hashes if
+    /// `with_hashes` is set.
+    pub async fn export_users(
+        &self,
+        household: HouseholdId,
+        with_hashes: bool,
+    ) -> Result<Vec<ExportedUser>, AuthError> {
+        let mut users = sqlx::query_as::<_, ExportedUser>(
+            "SELECT users.username AS name, users.hash, household_members.admin
+             FROM household_members
+             INNER JOIN users ON users.id = household_members.user_id
+             WHERE household_members.household_id = ?
+             ORDER BY users.username",
+        )
+        .bind(household)
+        .fetch_all(&self.conn)
+        .await?;
+        if !with_hashes {
+            for user in &mut users {
+                user.hash = None;
+            }
+        }
+        Ok(users)
+    }
+
+    /// Creates a user who can't log in, as a member of the default household.
     #[cfg(test)]
     pub async fn create_test_user(&self, username: &str) -> Result<(), AuthError> {
         sqlx::query("INSERT INTO users (username, hash) VALUES (?, ?)")
@@ -102,6 +368,6 @@ impl AuthStore {
             .execute(&self.conn)
             .await?;
 
-        Ok(())
+        self.join_household(username, HouseholdId::default()).await
     }
 }
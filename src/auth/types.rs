@@ -2,7 +2,7 @@
 //
 // SPDX-License-Identifier: MPL-2.0
 
-use std::str::FromStr;
+use std::{fmt::Display, str::FromStr};
 
 use axum::{
     http::{HeaderValue, StatusCode},
@@ -38,7 +38,7 @@ impl Token {
     }
 }
 
-#[derive(Debug, serde::Deserialize, serde::Serialize, sqlx::Encode, sqlx::Decode)]
+#[derive(Debug, Clone, Copy, serde::Deserialize, serde::Serialize, sqlx::Encode, sqlx::Decode)]
 pub struct UserId(i32);
 
 impl sqlx::Type<sqlx::Sqlite> for UserId {
@@ -47,6 +47,59 @@ impl sqlx::Type<sqlx::Sqlite> for UserId {
     }
 }
 
+/// A group of people sharing tasks, e.g. a flat.  Everything to do with tasks
+/// belongs to exactly one household, and people can be members of several.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    serde::Deserialize,
+    serde::Serialize,
+    sqlx::Encode,
+    sqlx::Decode,
+)]
+pub struct HouseholdId(i32);
+
+impl sqlx::Type<sqlx::Sqlite> for HouseholdId {
+    fn type_info() -> <sqlx::Sqlite as sqlx::Database>::TypeInfo {
+        <i32 as sqlx::Type<sqlx::Sqlite>>::type_info()
+    }
+}
+
+impl From<i32> for HouseholdId {
+    fn from(value: i32) -> Self {
+        Self(value)
+    }
+}
+
+impl Display for HouseholdId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// The household that existing data was moved into when households were
+/// introduced, which is used whenever no other household has been chosen.
+impl Default for HouseholdId {
+    fn default() -> Self {
+        Self(1)
+    }
+}
+
+/// A member of a household, for moving them to another database.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize, serde::Serialize, sqlx::FromRow)]
+pub struct ExportedUser {
+    pub name: String,
+    /// Password hash, which is left out unless asked for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hash: Option<String>,
+    #[serde(default)]
+    pub admin: bool,
+}
+
 #[derive(thiserror::Error, Debug)]
 pub enum AuthError {
     // 500 type errors (it's probably our fault)
@@ -60,6 +113,16 @@ pub enum AuthError {
     UnknownToken(#[from] Option<uuid::Error>),
     #[error("missing token")]
     MissingToken,
+    #[error("missing or malformed credentials")]
+    MissingCredentials,
+    #[error("user is not a member of this household")]
+    NotHouseholdMember,
+    #[error("unknown household was used")]
+    UnknownHousehold(String),
+    #[error("unknown user was used")]
+    UnknownUser(String),
+    #[error("only admins of this household can do this")]
+    NotAdmin,
 }
 
 impl IntoResponse for AuthError {
@@ -71,10 +134,18 @@ impl IntoResponse for AuthError {
             }
             AuthError::UserPasswordMismatch
             | AuthError::UnknownToken(_)
-            | AuthError::MissingToken => {
+            | AuthError::MissingToken
+            | AuthError::MissingCredentials
+            | AuthError::NotHouseholdMember
+            | AuthError::UnknownHousehold(_)
+            | AuthError::UnknownUser(_) => {
                 tracing::warn!({ details = self.to_string() }, "Authentication failure");
                 (StatusCode::BAD_REQUEST, self.to_string()).into_response()
             }
+            AuthError::NotAdmin => {
+                tracing::warn!({ details = self.to_string() }, "Authorisation failure");
+                (StatusCode::FORBIDDEN, self.to_string()).into_response()
+            }
         }
     }
 }
@@ -2,6 +2,7 @@
 //
 // SPDX-License-Identifier: MPL-2.0
 
+use std::collections::HashMap;
 use std::env::current_exe;
 use std::fs;
 use std::io::Write;
@@ -9,89 +10,714 @@ use std::os::unix;
 use std::process::Command;
 
 use clap::Parser;
+use rand_core::{OsRng, RngCore};
 
 #[derive(clap::Parser)]
 #[command(author, version, about, long_about = None)]
 #[command(propagate_version = true)]
 struct Args {
+    /// Name of the household to work with (defaults to the first one)
+    #[arg(long, global = true)]
+    household: Option<String>,
     #[command(subcommand)]
     command: Commands,
 }
 
 #[derive(clap::Subcommand)]
+#[allow(clippy::large_enum_variant)]
 enum Commands {
     /// Migrates a database to the latest version
     Migrate,
-    /// Adds a new user to the database
+    /// Adds a new user to the database, as a member of the household
     AddUser {
         #[arg(short, long)]
         name: String,
         #[arg(short, long)]
         password: String,
     },
+    /// Adds a new household, which has its own tasks, tags and feeds
+    AddHousehold {
+        #[arg(long)]
+        name: String,
+    },
+    /// Makes an existing user a member of the household as well
+    JoinHousehold {
+        #[arg(long)]
+        person: String,
+    },
+    /// Sets a new password for an existing user
+    SetPassword {
+        #[arg(short, long)]
+        name: String,
+        #[arg(short, long)]
+        password: String,
+    },
+    /// Lets a member of the household export and import it through the API
+    MakeAdmin {
+        #[arg(long)]
+        person: String,
+        /// Take the permission away again instead
+        #[arg(long)]
+        revoke: bool,
+    },
+    /// Prints the whole household as a JSON document that can be imported again
+    Export {
+        /// Include password hashes, so that users can log in after an import
+        #[arg(long)]
+        with_hashes: bool,
+        /// Write to this file rather than printing the document
+        #[arg(long)]
+        output: Option<std::path::PathBuf>,
+    },
+    /// Imports a document written by export, merging it into the household.
+    /// Users who are already in another household join this one as well
+    Import {
+        file: std::path::PathBuf,
+    },
+    /// Brings the household in line with a config file (see
+    /// scripts/household_example.toml), creating and updating users and tasks,
+    /// and archiving tasks that have been taken out of it
+    Apply {
+        file: std::path::PathBuf,
+        /// Only print what would change
+        #[arg(long)]
+        dry_run: bool,
+    },
     /// Adds a new task to the database
     AddTask {
-        #[arg(long, required = true)]
+        /// Template to take the names, routine and duration from (see
+        /// list-templates), which the other options override
+        #[arg(long)]
+        template: Option<String>,
+        /// Name in the form lang=text
+        #[arg(long, required_unless_present = "template")]
         name: Vec<String>,
+        /// Markdown description in the form lang=text
         #[arg(long)]
-        routine: String,
+        description: Vec<String>,
+        /// Checklist step in the form lang=text, the nth step given for each language
+        /// forms the nth step of the checklist
         #[arg(long)]
-        duration: u16,
+        step: Vec<String>,
+        /// One of schedule, interval, calendar, one-off or triggered
+        #[arg(long, required_unless_present = "template")]
+        routine: Option<String>,
+        /// Length of each cycle in days (not needed for one-off or triggered tasks)
+        #[arg(long)]
+        duration: Option<u16>,
+        /// Recurrence rule for calendar routines, e.g. "FREQ=WEEKLY;BYDAY=MO,TH"
+        #[arg(long)]
+        recurrence: Option<homie::tasks::Recurrence>,
         #[arg(long, required = true)]
         participant: Vec<String>,
+        /// First person to do the task (for one-off tasks, leave empty to let anyone claim it)
         #[arg(long)]
-        starts_with: String,
+        starts_with: Option<String>,
         #[arg(long)]
         starts_on: chrono::NaiveDate,
+        /// Due date for one-off tasks
+        #[arg(long)]
+        due_on: Option<chrono::NaiveDate>,
+        /// Part of the year a seasonal task is done in, e.g. "04-01/10-31"
+        #[arg(long)]
+        active_window: Option<homie::tasks::ActiveWindow>,
+        /// Number of days before the due date that the task counts as due soon
+        #[arg(long, default_value_t = 0)]
+        due_soon_days: u16,
+        /// Number of days the task can be overdue before it is badly overdue
+        #[arg(long)]
+        grace_days: Option<u16>,
+        /// Points earned by each person who does the task
+        #[arg(long, default_value_t = 0)]
+        points: u32,
+        /// One of round-robin, least-recently-done, fewest-completions, fixed-owner or seeded-random
+        #[arg(long, default_value = "round-robin")]
+        rotation: String,
+        /// Number of days counted by the fewest-completions rotation
+        #[arg(long)]
+        rotation_days: Option<u16>,
+        /// Participant who always gets the task under the fixed-owner rotation
+        #[arg(long)]
+        rotation_owner: Option<String>,
+        /// Seed for the seeded-random rotation (random if not given)
+        #[arg(long)]
+        rotation_seed: Option<u32>,
+        /// What happens to missed cycles of a schedule task, one of accumulate,
+        /// collapse or skip
+        #[arg(long, default_value = "accumulate")]
+        catch_up: String,
+    },
+    /// Lists the templates that add-task can create tasks from
+    ListTemplates,
+    /// Adds someone to the end of a task's rotation
+    AddParticipant {
+        #[arg(long)]
+        task: i32,
+        #[arg(long)]
+        person: String,
+    },
+    /// Takes someone out of a task's rotation
+    RemoveParticipant {
+        #[arg(long)]
+        task: i32,
+        #[arg(long)]
+        person: String,
+    },
+    /// Sets the order people take turns in, which must include every participant
+    ReorderParticipants {
+        #[arg(long)]
+        task: i32,
+        #[arg(long, required = true)]
+        participant: Vec<String>,
+    },
+    /// Limits a task to part of the year, e.g. "04-01/10-31"
+    SetActiveWindow {
+        #[arg(long)]
+        task: i32,
+        /// Leave out to make the task active all year round
+        #[arg(long)]
+        window: Option<homie::tasks::ActiveWindow>,
+    },
+    /// Changes what happens to missed cycles of a schedule task, one of
+    /// accumulate, collapse or skip
+    SetCatchUp {
+        #[arg(long)]
+        task: i32,
+        #[arg(long)]
+        catch_up: String,
+    },
+    /// Changes when a task counts as due soon or badly overdue
+    SetUrgency {
+        #[arg(long)]
+        task: i32,
+        #[arg(long, default_value_t = 0)]
+        due_soon_days: u16,
+        /// Leave out if the task should never be badly overdue
+        #[arg(long)]
+        grace_days: Option<u16>,
+    },
+    /// Changes how many points each person who does a task earns
+    SetPoints {
+        #[arg(long)]
+        task: i32,
+        #[arg(long)]
+        points: u32,
+    },
+    /// Gives someone extra points, or takes some away if the number is negative
+    AdjustPoints {
+        #[arg(long)]
+        person: String,
+        #[arg(long, allow_negative_numbers = true)]
+        points: i32,
+        #[arg(long)]
+        description: Option<String>,
+    },
+    /// Spends some of someone's points on a reward
+    RedeemPoints {
+        #[arg(long)]
+        person: String,
+        #[arg(long)]
+        points: i32,
+        #[arg(long)]
+        description: Option<String>,
+    },
+    /// Prints the points ledger as CSV
+    ExportPoints {
+        /// Only export this person's entries
+        #[arg(long)]
+        person: Option<String>,
+    },
+    /// Sets the timezone that decides when each day starts, e.g. "Europe/Berlin"
+    SetTimezone {
+        /// Only set the timezone for this person, rather than the household
+        #[arg(long)]
+        person: Option<String>,
+        /// Leave out to use the household's (or the server's) timezone
+        #[arg(long)]
+        timezone: Option<homie::tasks::Timezone>,
+    },
+    /// Limits how many days ago tasks can be marked as done
+    SetBackdateLimit {
+        /// Leave out to allow any date since the task started
+        #[arg(long)]
+        days: Option<u16>,
+    },
+    /// Marks someone as away, so that they aren't given tasks due during that time
+    AddAbsence {
+        #[arg(long)]
+        person: String,
+        #[arg(long)]
+        starts_on: chrono::NaiveDate,
+        /// Last day of the absence
+        #[arg(long)]
+        ends_on: chrono::NaiveDate,
+    },
+    /// Removes an absence again, using the ID printed by add-absence
+    RemoveAbsence {
+        #[arg(long)]
+        id: i32,
+    },
+    /// Makes completing one task trigger another (triggered) task
+    AddChain {
+        #[arg(long)]
+        from: i32,
+        #[arg(long)]
+        to: i32,
+        /// Days after the first task is done that the second one is due
+        #[arg(long, default_value_t = 0)]
+        offset_days: u16,
+        /// One of same-person or next-in-rotation
+        #[arg(long, default_value = "same-person")]
+        assignee: String,
+    },
+    /// Removes a chain again, using the ID printed by add-chain
+    RemoveChain {
+        #[arg(long)]
+        id: i32,
+    },
+    /// Creates a calendar feed that calendar apps can subscribe to
+    AddFeed {
+        /// Leave out for a feed of the whole household's tasks
+        #[arg(long)]
+        person: Option<String>,
+        /// Language to show task names in
+        #[arg(long, default_value = "en")]
+        lang: String,
+    },
+    /// Removes a calendar feed again, using the ID printed by add-feed
+    RemoveFeed {
+        #[arg(long)]
+        id: i32,
+    },
+    /// Deletes images attached to completions that are older than the given
+    /// number of days, keeping the completions and their notes
+    PruneAttachments {
+        #[arg(long)]
+        older_than_days: u32,
     },
     Install,
 }
 
+fn split_translation(pair: &str) -> (String, String) {
+    let (left, right) = pair.split_once('=').unwrap();
+    (left.to_owned(), right.to_owned())
+}
+
+fn checklist_steps(steps: &[String]) -> Vec<HashMap<String, String>> {
+    let mut checklist = Vec::<HashMap<String, String>>::new();
+    let mut positions = HashMap::<String, usize>::new();
+    for pair in steps {
+        let (lang, name) = split_translation(pair);
+        let position = positions.entry(lang.clone()).or_default();
+        if *position == checklist.len() {
+            checklist.push(HashMap::new());
+        }
+        checklist[*position].insert(lang, name);
+        *position += 1;
+    }
+    checklist
+}
+
+fn parse_catch_up(catch_up: &str) -> homie::tasks::CatchUp {
+    match catch_up.to_lowercase().as_str() {
+        "accumulate" => homie::tasks::CatchUp::Accumulate,
+        "collapse" => homie::tasks::CatchUp::Collapse,
+        "skip" => homie::tasks::CatchUp::Skip,
+        _ => panic!("Unrecognised catch-up policy {catch_up}"),
+    }
+}
+
+async fn find_household(conn: &sqlx::SqlitePool, name: Option<&str>) -> homie::auth::HouseholdId {
+    match name {
+        Some(name) => homie::auth::AuthStore::new(conn.clone())
+            .household_named(name)
+            .await
+            .unwrap(),
+        None => homie::auth::HouseholdId::default(),
+    }
+}
+
+/// Brings every household's schedules up to date, e.g. after a migration.
+async fn refresh_schedules(conn: &sqlx::SqlitePool) {
+    let households = homie::auth::AuthStore::new(conn.clone())
+        .households()
+        .await
+        .unwrap();
+    for household in households {
+        homie::tasks::TaskStore::new(conn.clone(), household)
+            .refresh_schedules()
+            .await
+            .unwrap();
+    }
+}
+
+async fn task_store(household_name: Option<&str>) -> homie::tasks::TaskStore {
+    let conn = homie::db::create_connection().await;
+    let household = find_household(&conn, household_name).await;
+    homie::tasks::TaskStore::new(conn, household)
+}
+
 #[tokio::main]
 async fn main() {
-    let cli = Args::parse();
-    match cli.command {
+    let Args { household, command } = Args::parse();
+    let household_name = household.as_deref();
+    match command {
         Commands::Migrate => {
             let conn = homie::db::create_connection().await;
             homie::db::migrate(&conn).await.unwrap();
+            refresh_schedules(&conn).await;
         }
         Commands::AddUser { name, password } => {
             let conn = homie::db::create_connection().await;
+            let household = find_household(&conn, household_name).await;
+            let store = homie::auth::AuthStore::new(conn);
+            store
+                .create_user(&name, &password, household)
+                .await
+                .unwrap();
+        }
+        Commands::AddHousehold { name } => {
+            let conn = homie::db::create_connection().await;
+            let store = homie::auth::AuthStore::new(conn);
+            let id = store.create_household(&name).await.unwrap();
+            println!("Added household {id}");
+        }
+        Commands::JoinHousehold { person } => {
+            let conn = homie::db::create_connection().await;
+            let household = find_household(&conn, household_name).await;
+            let store = homie::auth::AuthStore::new(conn);
+            store.join_household(&person, household).await.unwrap();
+        }
+        Commands::SetPassword { name, password } => {
+            let conn = homie::db::create_connection().await;
+            let store = homie::auth::AuthStore::new(conn);
+            store.set_password(&name, &password).await.unwrap();
+        }
+        Commands::MakeAdmin { person, revoke } => {
+            let conn = homie::db::create_connection().await;
+            let household = find_household(&conn, household_name).await;
             let store = homie::auth::AuthStore::new(conn);
-            store.create_user(&name, &password).await.unwrap();
+            store.set_admin(&person, household, !revoke).await.unwrap();
+        }
+        Commands::Export {
+            with_hashes,
+            output,
+        } => {
+            let conn = homie::db::create_connection().await;
+            let household = find_household(&conn, household_name).await;
+            let document = homie::export::export(
+                &homie::auth::AuthStore::new(conn.clone()),
+                &homie::tasks::TaskStore::new(conn, household),
+                household,
+                with_hashes,
+            )
+            .await
+            .unwrap_or_else(|error| panic!("{error}"));
+            let json = serde_json::to_string_pretty(&document).unwrap();
+            match output {
+                Some(output) => fs::write(output, json).unwrap(),
+                None => println!("{json}"),
+            }
+        }
+        Commands::Import { file } => {
+            let document = serde_json::from_str(&fs::read_to_string(file).unwrap())
+                .unwrap_or_else(|error| panic!("{error}"));
+            let conn = homie::db::create_connection().await;
+            let household = find_household(&conn, household_name).await;
+            let summary = homie::export::import(
+                document,
+                &homie::auth::AuthStore::new(conn.clone()),
+                &homie::tasks::TaskStore::new(conn, household),
+                household,
+                true,
+            )
+            .await
+            .unwrap_or_else(|error| panic!("{error}"));
+            println!(
+                "Added {} users and {} tasks, {} users joined, {} tasks merged, {} completions added",
+                summary.users_added,
+                summary.tasks_added,
+                summary.users_joined,
+                summary.tasks_merged,
+                summary.completions_added,
+            );
+        }
+        Commands::Apply { file, dry_run } => {
+            let config = fs::read_to_string(file)
+                .unwrap()
+                .parse::<homie::config::HouseholdConfig>()
+                .unwrap_or_else(|error| panic!("{error}"));
+            let conn = homie::db::create_connection().await;
+            let household = find_household(&conn, household_name).await;
+            let changes = homie::config::apply(
+                &config,
+                &homie::auth::AuthStore::new(conn.clone()),
+                &homie::tasks::TaskStore::new(conn, household),
+                household,
+                dry_run,
+            )
+            .await
+            .unwrap_or_else(|error| panic!("{error}"));
+
+            if changes.is_empty() {
+                println!("Nothing to change");
+            }
+            for change in changes {
+                println!("{change}");
+            }
         }
         Commands::AddTask {
+            template,
             name,
+            description,
+            step,
             routine,
             duration,
+            recurrence,
             participant,
             starts_with,
             starts_on,
+            due_on,
+            active_window,
+            due_soon_days,
+            grace_days,
+            points,
+            rotation,
+            rotation_days,
+            rotation_owner,
+            rotation_seed,
+            catch_up,
         } => {
-            let conn = homie::db::create_connection().await;
-            let store = homie::tasks::TaskStore::new(conn);
+            let template = template.map(|id| {
+                homie::tasks::Template::find(&id)
+                    .unwrap_or_else(|| panic!("Unrecognised template {id}"))
+            });
+            let mut new_task = template
+                .as_ref()
+                .map(homie::tasks::Template::new_task)
+                .unwrap_or_default();
+            new_task
+                .names
+                .extend(name.iter().map(|pair| split_translation(pair)));
+
+            let routine = match routine.as_deref().map(str::to_lowercase).as_deref() {
+                Some("schedule") => homie::tasks::Routine::Schedule,
+                Some("interval") => homie::tasks::Routine::Interval,
+                Some("calendar") => homie::tasks::Routine::Calendar,
+                Some("one-off") => homie::tasks::Routine::OneOff,
+                Some("triggered") => homie::tasks::Routine::Triggered,
+                Some(routine) => panic!("Unrecognised routine type {routine}"),
+                None => new_task.routine,
+            };
+            let duration = match routine {
+                homie::tasks::Routine::OneOff | homie::tasks::Routine::Triggered => {
+                    duration.unwrap_or(0)
+                }
+                _ => duration
+                    .or(template.map(|template| template.duration))
+                    .expect("--duration is required for recurring tasks"),
+            };
+
+            let store = task_store(household_name).await;
             store
                 .add_task(homie::tasks::NewTask {
-                    names: name
-                        .into_iter()
-                        .map(|pair| {
-                            let (left, right) = pair.split_once('=').unwrap();
-                            (left.to_owned(), right.to_owned())
-                        })
+                    names: new_task.names,
+                    descriptions: description
+                        .iter()
+                        .map(|pair| split_translation(pair))
                         .collect(),
-                    routine: match routine.to_lowercase().as_str() {
-                        "schedule" => homie::tasks::Routine::Schedule,
-                        "interval" => homie::tasks::Routine::Interval,
-                        _ => panic!("Unrecognised routine type {routine}"),
-                    },
+                    checklist: checklist_steps(&step),
+                    routine,
                     duration,
+                    recurrence,
                     participants: participant,
-                    starts_with,
+                    starts_with: starts_with.unwrap_or_default(),
                     starts_on,
+                    due_on,
+                    active_window,
+                    urgency: homie::tasks::Urgency {
+                        due_soon_days,
+                        grace_days,
+                    },
+                    points,
+                    config_key: None,
+                    catch_up: parse_catch_up(&catch_up),
+                    rotation: match rotation.to_lowercase().as_str() {
+                        "round-robin" => homie::tasks::Rotation::RoundRobin,
+                        "least-recently-done" => homie::tasks::Rotation::LeastRecentlyDone,
+                        "fewest-completions" => homie::tasks::Rotation::FewestCompletions {
+                            days: rotation_days.expect("--rotation-days is required"),
+                        },
+                        "fixed-owner" => homie::tasks::Rotation::FixedOwner(
+                            rotation_owner.expect("--rotation-owner is required"),
+                        ),
+                        "seeded-random" => homie::tasks::Rotation::SeededRandom {
+                            seed: rotation_seed.unwrap_or_else(|| OsRng.next_u32()),
+                        },
+                        _ => panic!("Unrecognised rotation type {rotation}"),
+                    },
                 })
                 .await
                 .unwrap();
         }
+        Commands::ListTemplates => {
+            for template in homie::tasks::Template::catalogue() {
+                let name = template.names.get("en").cloned().unwrap_or_default();
+                println!(
+                    "{}\t{name} ({:?}, every {} days)",
+                    template.id, template.routine, template.duration
+                );
+            }
+        }
+        Commands::AddParticipant { task, person } => {
+            let store = task_store(household_name).await;
+            store.add_participant(task.into(), &person).await.unwrap();
+        }
+        Commands::RemoveParticipant { task, person } => {
+            let store = task_store(household_name).await;
+            store
+                .remove_participant(task.into(), &person)
+                .await
+                .unwrap();
+        }
+        Commands::ReorderParticipants { task, participant } => {
+            let store = task_store(household_name).await;
+            store
+                .reorder_participants(task.into(), &participant)
+                .await
+                .unwrap();
+        }
+        Commands::SetActiveWindow { task, window } => {
+            let store = task_store(household_name).await;
+            store.set_active_window(task.into(), window).await.unwrap();
+        }
+        Commands::SetCatchUp { task, catch_up } => {
+            let store = task_store(household_name).await;
+            store
+                .set_catch_up(task.into(), parse_catch_up(&catch_up))
+                .await
+                .unwrap();
+        }
+        Commands::SetUrgency {
+            task,
+            due_soon_days,
+            grace_days,
+        } => {
+            let store = task_store(household_name).await;
+            store
+                .set_urgency(
+                    task.into(),
+                    homie::tasks::Urgency {
+                        due_soon_days,
+                        grace_days,
+                    },
+                )
+                .await
+                .unwrap();
+        }
+        Commands::SetPoints { task, points } => {
+            let store = task_store(household_name).await;
+            store.set_points(task.into(), points).await.unwrap();
+        }
+        Commands::AdjustPoints {
+            person,
+            points,
+            description,
+        } => {
+            let store = task_store(household_name).await;
+            let id = store
+                .adjust_points(&person, points, description.as_deref())
+                .await
+                .unwrap();
+            println!("Added ledger entry {id}");
+        }
+        Commands::RedeemPoints {
+            person,
+            points,
+            description,
+        } => {
+            let store = task_store(household_name).await;
+            let id = store
+                .redeem_points(&person, points, description.as_deref())
+                .await
+                .unwrap();
+            println!("Added ledger entry {id}");
+        }
+        Commands::ExportPoints { person } => {
+            let store = task_store(household_name).await;
+            print!("{}", store.ledger_csv(person.as_deref()).await.unwrap());
+        }
+        Commands::SetTimezone { person, timezone } => {
+            let store = task_store(household_name).await;
+            match person {
+                Some(person) => store.set_person_timezone(&person, timezone).await,
+                None => store.set_household_timezone(timezone).await,
+            }
+            .unwrap();
+        }
+        Commands::SetBackdateLimit { days } => {
+            let store = task_store(household_name).await;
+            store.set_backdate_limit(days).await.unwrap();
+        }
+        Commands::AddAbsence {
+            person,
+            starts_on,
+            ends_on,
+        } => {
+            let store = task_store(household_name).await;
+            let id = store
+                .add_absence(&person, starts_on, ends_on)
+                .await
+                .unwrap();
+            println!("Added absence {id}");
+        }
+        Commands::RemoveAbsence { id } => {
+            let store = task_store(household_name).await;
+            store.remove_absence(id.into()).await.unwrap();
+        }
+        Commands::AddChain {
+            from,
+            to,
+            offset_days,
+            assignee,
+        } => {
+            let assignee = match assignee.to_lowercase().as_str() {
+                "same-person" => homie::tasks::ChainAssignee::SamePerson,
+                "next-in-rotation" => homie::tasks::ChainAssignee::NextInRotation,
+                _ => panic!("Unrecognised chain assignee {assignee}"),
+            };
+
+            let store = task_store(household_name).await;
+            let id = store
+                .add_chain(from.into(), to.into(), offset_days, assignee)
+                .await
+                .unwrap();
+            println!("Added chain {id}");
+        }
+        Commands::RemoveChain { id } => {
+            let store = task_store(household_name).await;
+            store.delete_chain(id.into()).await.unwrap();
+        }
+        Commands::AddFeed { person, lang } => {
+            let store = task_store(household_name).await;
+            let id = store
+                .add_feed(person.as_deref(), &lang.into())
+                .await
+                .unwrap();
+            let feed = store.feed(id).await.unwrap();
+            println!("Added feed {id} at /api/calendar/{}.ics", feed.secret);
+        }
+        Commands::RemoveFeed { id } => {
+            let store = task_store(household_name).await;
+            store.delete_feed(id.into()).await.unwrap();
+        }
+        Commands::PruneAttachments { older_than_days } => {
+            let store = task_store(household_name).await;
+            let today = store.today_for(None).await.unwrap();
+            let before = today - chrono::Duration::days(older_than_days.into());
+            let count = store.prune_attachments(before).await.unwrap();
+            println!("Removed {count} attachments from before {before}");
+        }
         Commands::Install => {
             let symlink_source = format!(
                 "{}/homie",
@@ -101,6 +727,7 @@ async fn main() {
             fs::create_dir_all("/opt/homie/data").unwrap();
             let conn = homie::db::create_connection_in_location("/opt/homie/data").await;
             homie::db::migrate(&conn).await.unwrap();
+            refresh_schedules(&conn).await;
 
             let _ = fs::remove_file("/usr/local/bin/homie");
             unix::fs::symlink(symlink_source, "/usr/local/bin/homie").unwrap();
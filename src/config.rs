@@ -0,0 +1,656 @@
+// SPDX-FileCopyrightText: 2023 Jonathan Frere
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! Household config files, which describe a household's users and tasks in a
+//! single TOML file that can be kept under version control.  Applying one
+//! brings the database in line with it: missing users and tasks are created,
+//! tasks that have changed are updated, and tasks that have been taken out of
+//! the file are archived.
+//!
+//! Tasks are matched up with the file by their `key`, so tasks that were added
+//! in some other way are left alone.  Users who aren't in the file are left
+//! alone too, and existing users keep their passwords.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+    str::FromStr,
+};
+
+use chrono::NaiveDate;
+
+use crate::{
+    auth::{AuthError, AuthStore, HouseholdId},
+    tasks::{
+        ActiveWindow, CatchUp, ConfiguredTask, NewTask, Recurrence, Rotation, Routine,
+        TaskSettings, TaskStore, TaskStoreError, Template, Urgency,
+    },
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigError {
+    #[error("config file is not valid: {0}")]
+    Invalid(#[from] toml::de::Error),
+    #[error(transparent)]
+    AuthError(#[from] AuthError),
+    #[error(transparent)]
+    TaskStoreError(#[from] TaskStoreError),
+    #[error("a password is needed to create user {0}")]
+    MissingPassword(String),
+    #[error("more than one task has the key {0}")]
+    DuplicateKey(String),
+    #[error("task {0} needs a name, or a template to take one from")]
+    MissingName(String),
+    #[error("task {0} needs a routine, or a template to take one from")]
+    MissingRoutine(String),
+    #[error("task {0} needs a duration, or a template to take one from")]
+    MissingDuration(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HouseholdConfig {
+    #[serde(default, rename = "user")]
+    pub users: Vec<UserConfig>,
+    #[serde(default, rename = "task")]
+    pub tasks: Vec<TaskConfig>,
+}
+
+impl FromStr for HouseholdConfig {
+    type Err = ConfigError;
+
+    fn from_str(config: &str) -> Result<Self, Self::Err> {
+        Ok(toml::from_str(config)?)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct UserConfig {
+    pub name: String,
+    /// Only used when the user is created
+    pub password: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TaskConfig {
+    /// Identifies the task, so shouldn't be changed once it has been applied
+    pub key: String,
+    /// Template to take the names, routine and duration from, which the
+    /// other settings override
+    pub template: Option<String>,
+    /// Names, keyed by language
+    #[serde(default)]
+    pub names: HashMap<String, String>,
+    /// Markdown descriptions, keyed by language
+    #[serde(default)]
+    pub descriptions: HashMap<String, String>,
+    pub routine: Option<Routine>,
+    /// Length of each cycle in days (not needed for one-off or triggered tasks)
+    pub duration: Option<u16>,
+    pub recurrence: Option<Recurrence>,
+    /// In the order people take turns in
+    pub participants: Vec<String>,
+    /// First person to do the task, or the first participant if not given.
+    /// Only used when the task is created.
+    pub starts_with: Option<String>,
+    /// Today if not given.  Only used when the task is created.
+    #[serde(default, deserialize_with = "deserialize_date")]
+    pub starts_on: Option<NaiveDate>,
+    #[serde(default)]
+    pub rotation: Rotation,
+    /// What happens to missed cycles of a schedule task
+    #[serde(default)]
+    pub catch_up: CatchUp,
+    pub active_window: Option<ActiveWindow>,
+    #[serde(default)]
+    pub due_soon_days: u16,
+    pub grace_days: Option<u16>,
+    #[serde(default)]
+    pub points: u32,
+}
+
+/// TOML dates are their own type rather than strings, so chrono can't read
+/// them directly.
+fn deserialize_date<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<NaiveDate>, D::Error> {
+    let date = <Option<toml::value::Datetime> as serde::Deserialize>::deserialize(deserializer)?;
+    date.map(|date| date.to_string().parse())
+        .transpose()
+        .map_err(serde::de::Error::custom)
+}
+
+impl TaskConfig {
+    /// The task's settings, filled in from its template where needed.
+    fn settings(&self) -> Result<TaskSettings, ConfigError> {
+        let template = match &self.template {
+            Some(id) => Some(
+                Template::find(id).ok_or_else(|| TaskStoreError::UnknownTemplate(id.clone()))?,
+            ),
+            None => None,
+        };
+
+        let mut names = template
+            .as_ref()
+            .map(|template| template.names.clone())
+            .unwrap_or_default();
+        names.extend(self.names.clone());
+        if names.is_empty() {
+            Err(ConfigError::MissingName(self.key.clone()))?;
+        }
+
+        let routine = self
+            .routine
+            .or(template.as_ref().map(|template| template.routine))
+            .ok_or_else(|| ConfigError::MissingRoutine(self.key.clone()))?;
+        let duration = match routine {
+            Routine::OneOff | Routine::Triggered => self.duration.unwrap_or(0),
+            _ => self
+                .duration
+                .or(template.as_ref().map(|template| template.duration))
+                .ok_or_else(|| ConfigError::MissingDuration(self.key.clone()))?,
+        };
+
+        // descriptions are only kept for languages the task has a name in
+        let descriptions = self
+            .descriptions
+            .iter()
+            .filter(|(lang, _)| names.contains_key(*lang))
+            .map(|(lang, description)| (lang.clone(), description.clone()))
+            .collect();
+
+        Ok(TaskSettings {
+            names,
+            descriptions,
+            routine,
+            duration,
+            rotation: self.rotation.clone(),
+            catch_up: self.catch_up,
+            recurrence: self.recurrence.clone(),
+            active_window: self.active_window,
+            urgency: Urgency {
+                due_soon_days: self.due_soon_days,
+                grace_days: self.grace_days,
+            },
+            points: self.points,
+        })
+    }
+}
+
+/// Something that applying a config file changes (or would change).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    AddUser(String),
+    JoinHousehold(String),
+    AddTask(String),
+    /// The task's key, and the names of the settings that changed
+    UpdateTask(String, Vec<&'static str>),
+    ArchiveTask(String),
+    RestoreTask(String),
+}
+
+impl Display for Change {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Change::AddUser(name) => write!(f, "+ user {name}"),
+            Change::JoinHousehold(name) => write!(f, "+ member {name}"),
+            Change::AddTask(key) => write!(f, "+ task {key}"),
+            Change::UpdateTask(key, settings) => {
+                write!(f, "~ task {key} ({})", settings.join(", "))
+            }
+            Change::ArchiveTask(key) => write!(f, "- task {key} (archived)"),
+            Change::RestoreTask(key) => write!(f, "+ task {key} (restored)"),
+        }
+    }
+}
+
+/// Brings the household in line with the config file, returning everything
+/// that changed.  With `dry_run`, only works out what would change.  Applying
+/// the same file twice changes nothing the second time.
+pub async fn apply(
+    config: &HouseholdConfig,
+    auth_store: &AuthStore,
+    task_store: &TaskStore,
+    household: HouseholdId,
+    dry_run: bool,
+) -> Result<Vec<Change>, ConfigError> {
+    // check the whole file before changing anything, so that a mistake in it
+    // is reported before any of it is applied (the changes themselves are
+    // still saved one at a time)
+    let members = auth_store.members(household).await?;
+    let mut joining = Vec::new();
+    for user in &config.users {
+        if members.iter().any(|member| same_person(member, &user.name)) {
+            continue;
+        }
+        let exists = auth_store.user_exists(&user.name).await?;
+        if !exists && user.password.is_none() {
+            Err(ConfigError::MissingPassword(user.name.clone()))?;
+        }
+        joining.push((user, exists));
+    }
+
+    let is_member = |name: &str| {
+        members.iter().any(|member| same_person(member, name))
+            || config
+                .users
+                .iter()
+                .any(|user| same_person(&user.name, name))
+    };
+    let today = task_store.today_for(None).await?;
+    let mut keys = HashSet::new();
+    let mut wanted = Vec::new();
+    for task in &config.tasks {
+        if !keys.insert(task.key.as_str()) {
+            Err(ConfigError::DuplicateKey(task.key.clone()))?;
+        }
+        if task.participants.is_empty() {
+            Err(TaskStoreError::NoParticipants)?;
+        }
+        if let Some(person) = task.participants.iter().find(|person| !is_member(person)) {
+            Err(TaskStoreError::PersonDoesNotExist(person.clone()))?;
+        }
+        if let Some(starts_with) = &task.starts_with {
+            if !task
+                .participants
+                .iter()
+                .any(|person| same_person(person, starts_with))
+            {
+                Err(TaskStoreError::PersonNotParticipant(starts_with.clone()))?;
+            }
+        }
+        let settings = task.settings()?;
+        settings.check(&task.participants, task.starts_on.unwrap_or(today))?;
+        wanted.push((task, settings));
+    }
+
+    let mut changes = Vec::new();
+
+    for (user, exists) in joining {
+        if exists {
+            changes.push(Change::JoinHousehold(user.name.clone()));
+            if !dry_run {
+                auth_store.join_household(&user.name, household).await?;
+            }
+        } else {
+            changes.push(Change::AddUser(user.name.clone()));
+            if !dry_run {
+                let password = user.password.as_deref().unwrap_or_default();
+                auth_store
+                    .create_user(&user.name, password, household)
+                    .await?;
+            }
+        }
+    }
+
+    let existing = task_store.configured_tasks().await?;
+    for (task, settings) in wanted {
+        match existing.iter().find(|existing| existing.key == task.key) {
+            Some(existing) => {
+                changes.extend(update_task(task_store, existing, task, settings, dry_run).await?);
+            }
+            None => {
+                changes.push(Change::AddTask(task.key.clone()));
+                if !dry_run {
+                    add_task(task_store, task, settings).await?;
+                }
+            }
+        }
+    }
+
+    for task in existing {
+        if !task.archived && !keys.contains(task.key.as_str()) {
+            changes.push(Change::ArchiveTask(task.key));
+            if !dry_run {
+                task_store.set_archived(task.id, true).await?;
+            }
+        }
+    }
+
+    Ok(changes)
+}
+
+async fn add_task(
+    task_store: &TaskStore,
+    task: &TaskConfig,
+    settings: TaskSettings,
+) -> Result<(), ConfigError> {
+    let starts_on = match task.starts_on {
+        Some(starts_on) => starts_on,
+        None => task_store.today_for(None).await?,
+    };
+    let starts_with = task
+        .starts_with
+        .clone()
+        .or_else(|| task.participants.first().cloned())
+        .unwrap_or_default();
+
+    task_store
+        .add_task(NewTask {
+            names: settings.names,
+            descriptions: settings.descriptions,
+            routine: settings.routine,
+            duration: settings.duration,
+            participants: task.participants.clone(),
+            starts_on,
+            starts_with,
+            rotation: settings.rotation,
+            catch_up: settings.catch_up,
+            recurrence: settings.recurrence,
+            active_window: settings.active_window,
+            urgency: settings.urgency,
+            points: settings.points,
+            config_key: Some(task.key.clone()),
+            ..Default::default()
+        })
+        .await?;
+    Ok(())
+}
+
+async fn update_task(
+    task_store: &TaskStore,
+    existing: &ConfiguredTask,
+    task: &TaskConfig,
+    settings: TaskSettings,
+    dry_run: bool,
+) -> Result<Vec<Change>, ConfigError> {
+    let mut changes = Vec::new();
+    if existing.archived {
+        changes.push(Change::RestoreTask(task.key.clone()));
+        if !dry_run {
+            task_store.set_archived(existing.id, false).await?;
+        }
+    }
+
+    let current = &existing.settings;
+    let changed = [
+        ("names", current.names != settings.names),
+        (
+            "descriptions",
+            current.descriptions != settings.descriptions,
+        ),
+        ("routine", current.routine != settings.routine),
+        ("duration", current.duration != settings.duration),
+        ("rotation", current.rotation != settings.rotation),
+        ("catch_up", current.catch_up != settings.catch_up),
+        ("recurrence", current.recurrence != settings.recurrence),
+        (
+            "active_window",
+            current.active_window != settings.active_window,
+        ),
+        ("urgency", current.urgency != settings.urgency),
+        ("points", current.points != settings.points),
+    ]
+    .into_iter()
+    .filter_map(|(setting, changed)| changed.then_some(setting))
+    .collect::<Vec<_>>();
+
+    let participants_changed = existing.participants.len() != task.participants.len()
+        || existing
+            .participants
+            .iter()
+            .zip(&task.participants)
+            .any(|(current, wanted)| !same_person(current, wanted));
+
+    if participants_changed && !dry_run {
+        update_participants(task_store, existing, &task.participants).await?;
+    }
+    if !changed.is_empty() && !dry_run {
+        task_store.update_task(existing.id, settings).await?;
+    }
+
+    let mut updated = changed;
+    if participants_changed {
+        updated.insert(0, "participants");
+    }
+    if !updated.is_empty() {
+        changes.push(Change::UpdateTask(task.key.clone(), updated));
+    }
+    Ok(changes)
+}
+
+/// Adds and removes participants so that a task has exactly the given ones, in
+/// the given order.  New participants are added before anyone is removed, so
+/// that the task is never left without any.
+async fn update_participants(
+    task_store: &TaskStore,
+    existing: &ConfiguredTask,
+    participants: &[String],
+) -> Result<(), ConfigError> {
+    for person in participants {
+        task_store.add_participant(existing.id, person).await?;
+    }
+    for person in &existing.participants {
+        if !participants
+            .iter()
+            .any(|participant| same_person(participant, person))
+        {
+            task_store.remove_participant(existing.id, person).await?;
+        }
+    }
+    task_store
+        .reorder_participants(existing.id, participants)
+        .await?;
+    Ok(())
+}
+
+fn same_person(left: &str, right: &str) -> bool {
+    left.to_lowercase() == right.to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tasks::time;
+
+    use super::*;
+
+    #[test]
+    fn reads_users_and_tasks() {
+        let config = r#"
+            [[user]]
+            name = "Alice"
+            password = "hunter2"
+
+            [[task]]
+            key = "bins"
+            names = { en = "Take out the bins", de = "Müll rausbringen" }
+            routine = "Calendar"
+            recurrence = "FREQ=WEEKLY;BYDAY=TU"
+            duration = 2
+            participants = ["Alice", "Bob"]
+            starts_on = 2022-01-01
+            rotation = { FewestCompletions = { days = 28 } }
+
+            [[task]]
+            key = "hoover"
+            template = "hoover"
+            participants = ["Bob"]
+        "#
+        .parse::<HouseholdConfig>()
+        .unwrap();
+
+        assert_eq!(config.users[0].password.as_deref(), Some("hunter2"));
+        let bins = &config.tasks[0];
+        assert_eq!(bins.starts_on, NaiveDate::from_ymd_opt(2022, 1, 1));
+        assert_eq!(bins.rotation, Rotation::FewestCompletions { days: 28 });
+        assert_eq!(bins.settings().unwrap().routine, Routine::Calendar);
+
+        let hoover = config.tasks[1].settings().unwrap();
+        assert_eq!(hoover.names["de"], "Staubsaugen");
+        assert_eq!(hoover.duration, 7);
+    }
+
+    #[sqlx::test]
+    async fn applying_reconciles_the_household(conn: sqlx::SqlitePool) {
+        time::mock::set(NaiveDate::from_ymd_opt(2020, 1, 10).unwrap());
+        let auth_store = AuthStore::new(conn.clone());
+        let task_store = TaskStore::new(conn, HouseholdId::default());
+        let household = HouseholdId::default();
+        let first = r#"
+            [[user]]
+            name = "Alice"
+            password = "hunter2"
+
+            [[user]]
+            name = "Bob"
+            password = "hunter2"
+
+            [[task]]
+            key = "dishes"
+            names = { en = "Do the dishes" }
+            routine = "Interval"
+            duration = 7
+            participants = ["Alice", "Bob"]
+
+            [[task]]
+            key = "hoover"
+            template = "hoover"
+            participants = ["Bob"]
+        "#
+        .parse::<HouseholdConfig>()
+        .unwrap();
+
+        let changes = apply(&first, &auth_store, &task_store, household, true)
+            .await
+            .unwrap();
+        assert_eq!(changes.len(), 4);
+        assert!(task_store.configured_tasks().await.unwrap().is_empty());
+
+        apply(&first, &auth_store, &task_store, household, false)
+            .await
+            .unwrap();
+        let changes = apply(&first, &auth_store, &task_store, household, false)
+            .await
+            .unwrap();
+        assert_eq!(changes, vec![]);
+
+        let second = r#"
+            [[user]]
+            name = "alice"
+
+            [[task]]
+            key = "dishes"
+            names = { en = "Do the dishes", de = "Abwaschen" }
+            routine = "Interval"
+            duration = 3
+            participants = ["Bob", "alice"]
+        "#
+        .parse::<HouseholdConfig>()
+        .unwrap();
+        let changes = apply(&second, &auth_store, &task_store, household, false)
+            .await
+            .unwrap();
+        assert_eq!(
+            changes,
+            vec![
+                Change::UpdateTask(
+                    "dishes".to_owned(),
+                    vec!["participants", "names", "duration"]
+                ),
+                Change::ArchiveTask("hoover".to_owned()),
+            ]
+        );
+
+        let tasks = task_store.tasks(&"de".into()).await.unwrap();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].name, "Abwaschen");
+        assert_eq!(tasks[0].length_days, 3);
+        assert_eq!(tasks[0].participants, vec!["Bob", "Alice"]);
+
+        let changes = apply(&first, &auth_store, &task_store, household, false)
+            .await
+            .unwrap();
+        assert_eq!(
+            changes[0],
+            Change::UpdateTask(
+                "dishes".to_owned(),
+                vec!["participants", "names", "duration"]
+            )
+        );
+        assert_eq!(changes[1], Change::RestoreTask("hoover".to_owned()));
+        assert_eq!(task_store.tasks(&"en".into()).await.unwrap().len(), 2);
+    }
+
+    #[sqlx::test]
+    async fn invalid_files_change_nothing(conn: sqlx::SqlitePool) {
+        time::mock::set(NaiveDate::from_ymd_opt(2020, 1, 10).unwrap());
+        let auth_store = AuthStore::new(conn.clone());
+        let task_store = TaskStore::new(conn, HouseholdId::default());
+        let household = HouseholdId::default();
+        let config = |participants: &str, starts_with: &str| {
+            format!(
+                r#"
+                [[user]]
+                name = "Alice"
+                password = "hunter2"
+
+                [[task]]
+                key = "dishes"
+                names = {{ en = "Do the dishes" }}
+                routine = "Interval"
+                duration = 7
+                participants = [{participants}]
+                starts_with = "{starts_with}"
+
+                [[task]]
+                key = "hoover"
+                template = "hoover"
+                participants = ["Alice"]
+                "#
+            )
+            .parse::<HouseholdConfig>()
+            .unwrap()
+        };
+
+        let result = apply(
+            &config(r#""Alice", "Zed""#, "Alice"),
+            &auth_store,
+            &task_store,
+            household,
+            false,
+        )
+        .await;
+        assert!(matches!(
+            result,
+            Err(ConfigError::TaskStoreError(
+                TaskStoreError::PersonDoesNotExist(_)
+            ))
+        ));
+
+        let result = apply(
+            &config(r#""Alice""#, "Bob"),
+            &auth_store,
+            &task_store,
+            household,
+            false,
+        )
+        .await;
+        assert!(matches!(
+            result,
+            Err(ConfigError::TaskStoreError(
+                TaskStoreError::PersonNotParticipant(_)
+            ))
+        ));
+
+        assert!(!auth_store.user_exists("Alice").await.unwrap());
+        assert!(task_store.configured_tasks().await.unwrap().is_empty());
+    }
+
+    #[test]
+    fn typos_and_missing_settings_are_errors() {
+        let result =
+            "[[task]]\nkey = \"bins\"\nnmaes = {}\nparticipants = []".parse::<HouseholdConfig>();
+        assert!(matches!(result, Err(ConfigError::Invalid(_))));
+
+        let config = "[[task]]\nkey = \"bins\"\nnames = { en = \"Bins\" }\nparticipants = []"
+            .parse::<HouseholdConfig>()
+            .unwrap();
+        assert!(matches!(
+            config.tasks[0].settings(),
+            Err(ConfigError::MissingRoutine(_))
+        ));
+    }
+}
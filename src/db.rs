@@ -5,8 +5,9 @@
 use std::path::Path;
 
 use sqlx::migrate::Migrator;
-use sqlx::sqlite::SqliteConnectOptions;
-use sqlx::SqlitePool;
+use sqlx::query::QueryAs;
+use sqlx::sqlite::{SqliteArguments, SqliteConnectOptions};
+use sqlx::{Sqlite, SqliteConnection, SqlitePool};
 
 static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
 
@@ -34,3 +35,20 @@ pub async fn create_connection_in_location(location: impl AsRef<Path>) -> Sqlite
 pub async fn migrate(conn: &SqlitePool) -> Result<(), sqlx::migrate::MigrateError> {
     MIGRATOR.run(conn).await
 }
+
+/// Runs an `INSERT ... RETURNING id` query, returning the new ID, or `None` if
+/// nothing was inserted (e.g. because the row it inserts from doesn't exist).
+///
+/// This uses `fetch_all` rather than `fetch_one` or `fetch_optional`, because
+/// those stop reading once they have a row and can leave the statement
+/// unfinished, so the insert isn't guaranteed to have run to completion.
+pub async fn insert_returning_id<'q, Id>(
+    conn: &mut SqliteConnection,
+    query: QueryAs<'q, Sqlite, (Id,), SqliteArguments<'q>>,
+) -> Result<Option<Id>, sqlx::Error>
+where
+    Id: Send + Unpin + for<'r> sqlx::Decode<'r, Sqlite> + sqlx::Type<Sqlite>,
+{
+    let ids = query.fetch_all(conn).await?;
+    Ok(ids.into_iter().next().map(|(id,)| id))
+}
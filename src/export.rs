@@ -0,0 +1,397 @@
+// SPDX-FileCopyrightText: 2023 Jonathan Frere
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! Exports a whole household as a versioned JSON document, and imports one
+//! again, e.g. to move to another machine or to look at the data without
+//! opening the database.  Documents hold the household's members, its tasks
+//! (with their translations and participant order) and every completion.
+//! Checklists, tags, chains, absences, calendar feeds, attachments and points
+//! aren't included.
+//!
+//! Importing merges by name: tasks with the same key or name in any language
+//! get the completions they are missing, and everything else is created.
+//! Users who already exist in another household can only be made to join this
+//! one from the command line; imports through the API can only create users.
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    routing::{get, post},
+    Extension, Json, Router,
+};
+use sqlx::SqlitePool;
+
+use crate::{
+    auth::{AuthError, AuthStore, ExportedUser, HouseholdId},
+    tasks::{ExportedTask, TaskStore, TaskStoreError, Timezone},
+};
+
+/// The version of the document that [`export`] creates, which should go up
+/// whenever its format changes.
+pub const VERSION: u32 = 1;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ExportError {
+    #[error(transparent)]
+    AuthError(#[from] AuthError),
+    #[error(transparent)]
+    TaskStoreError(#[from] TaskStoreError),
+    #[error("exports with version {0} can't be imported")]
+    UnsupportedVersion(u32),
+    #[error("user {0} can't be imported, because the name is taken")]
+    UserTaken(String),
+}
+
+impl IntoResponse for ExportError {
+    fn into_response(self) -> axum::response::Response {
+        match self {
+            ExportError::AuthError(error) => error.into_response(),
+            ExportError::TaskStoreError(error) => error.into_response(),
+            ExportError::UnsupportedVersion(_) | ExportError::UserTaken(_) => {
+                (StatusCode::BAD_REQUEST, self.to_string()).into_response()
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct HouseholdExport {
+    pub version: u32,
+    pub timezone: Option<Timezone>,
+    pub users: Vec<ExportedUser>,
+    pub tasks: Vec<ExportedTask>,
+}
+
+/// What importing a document changed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Deserialize, serde::Serialize)]
+pub struct ImportSummary {
+    pub users_added: usize,
+    /// Users who already existed, and have joined the household
+    pub users_joined: usize,
+    pub tasks_added: usize,
+    /// Tasks that already existed, and have been given the completions they
+    /// were missing
+    pub tasks_merged: usize,
+    pub completions_added: usize,
+}
+
+/// Exports the whole household.  Password hashes are only included if
+/// `with_hashes` is set.
+pub async fn export(
+    auth_store: &AuthStore,
+    task_store: &TaskStore,
+    household: HouseholdId,
+    with_hashes: bool,
+) -> Result<HouseholdExport, ExportError> {
+    Ok(HouseholdExport {
+        version: VERSION,
+        timezone: task_store.household_timezone().await?,
+        users: auth_store.export_users(household, with_hashes).await?,
+        tasks: task_store
+            .export_tasks()
+            .await?
+            .into_iter()
+            .map(|(_, task)| task)
+            .collect(),
+    })
+}
+
+/// Imports a document into the household, merging it with whatever is already
+/// there.  Users who exist but aren't members of the household make the import
+/// fail, unless `join_existing` is set, in which case they join it.  Users
+/// created without a password hash can't log in until their password is set.
+pub async fn import(
+    document: HouseholdExport,
+    auth_store: &AuthStore,
+    task_store: &TaskStore,
+    household: HouseholdId,
+    join_existing: bool,
+) -> Result<ImportSummary, ExportError> {
+    if document.version != VERSION {
+        Err(ExportError::UnsupportedVersion(document.version))?;
+    }
+    let mut summary = ImportSummary::default();
+
+    // users are checked before anything changes
+    let members = auth_store.members(household).await?;
+    let mut missing = Vec::new();
+    for user in &document.users {
+        if members.iter().any(|member| same_name(member, &user.name)) {
+            continue;
+        }
+        let exists = auth_store.user_exists(&user.name).await?;
+        if exists && !join_existing {
+            Err(ExportError::UserTaken(user.name.clone()))?;
+        }
+        missing.push((user, exists));
+    }
+
+    // and so are tasks, so that a bad one doesn't leave the import half done
+    let is_member = |name: &str| {
+        members.iter().any(|member| same_name(member, name))
+            || document
+                .users
+                .iter()
+                .any(|user| same_name(&user.name, name))
+    };
+    let existing = task_store.export_tasks().await?;
+    let mut tasks = Vec::new();
+    for task in document.tasks {
+        if let Some(person) = task.people().find(|person| !is_member(person)) {
+            Err(TaskStoreError::PersonDoesNotExist(person.to_owned()))?;
+        }
+        let matching = existing.iter().find(|(_, existing)| match &task.key {
+            Some(key) if existing.key.as_ref() == Some(key) => true,
+            _ => task.settings.names.iter().any(|(lang, name)| {
+                existing
+                    .settings
+                    .names
+                    .get(lang)
+                    .is_some_and(|existing| same_name(existing, name))
+            }),
+        });
+        if matching.is_none() {
+            task.check()?;
+        }
+        tasks.push((task, matching.map(|(task_id, _)| *task_id)));
+    }
+
+    if task_store.household_timezone().await?.is_none() && document.timezone.is_some() {
+        task_store.set_household_timezone(document.timezone).await?;
+    }
+
+    for (user, exists) in missing {
+        if exists {
+            auth_store.join_household(&user.name, household).await?;
+            summary.users_joined += 1;
+        } else {
+            let hash = user.hash.as_deref().unwrap_or_default();
+            auth_store
+                .create_user_with_hash(&user.name, hash, household)
+                .await?;
+            summary.users_added += 1;
+        }
+        if user.admin {
+            auth_store.set_admin(&user.name, household, true).await?;
+        }
+    }
+
+    for (task, matching) in tasks {
+        match matching {
+            Some(task_id) => {
+                summary.completions_added += task_store
+                    .import_completions(task_id, &task.completions)
+                    .await?;
+                summary.tasks_merged += 1;
+            }
+            None => {
+                summary.completions_added += task
+                    .completions
+                    .iter()
+                    .filter(|completion| !completion.initial)
+                    .count();
+                task_store.import_task(task).await?;
+                summary.tasks_added += 1;
+            }
+        }
+    }
+
+    Ok(summary)
+}
+
+fn same_name(left: &str, right: &str) -> bool {
+    left.to_lowercase() == right.to_lowercase()
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct ExportQuery {
+    #[serde(default)]
+    with_hashes: bool,
+}
+
+async fn export_household(
+    Query(query): Query<ExportQuery>,
+    State(conn): State<SqlitePool>,
+    Extension(household): Extension<HouseholdId>,
+    task_store: TaskStore,
+) -> Result<Json<HouseholdExport>, ExportError> {
+    let auth_store = AuthStore::new(conn);
+    export(&auth_store, &task_store, household, query.with_hashes)
+        .await
+        .map(Json)
+}
+
+async fn import_household(
+    State(conn): State<SqlitePool>,
+    Extension(household): Extension<HouseholdId>,
+    task_store: TaskStore,
+    Json(document): Json<HouseholdExport>,
+) -> Result<Json<ImportSummary>, ExportError> {
+    let auth_store = AuthStore::new(conn);
+    import(document, &auth_store, &task_store, household, false)
+        .await
+        .map(Json)
+}
+
+/// Routes for household admins, which need the admin middleware.
+pub fn admin_routes(conn: SqlitePool) -> Router {
+    Router::new()
+        .route("/export", get(export_household))
+        .route("/import", post(import_household))
+        .with_state(conn)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use chrono::NaiveDate;
+
+    use super::*;
+    use crate::tasks::{time, CompletionDetails, NewTask, Routine};
+
+    #[sqlx::test]
+    async fn exports_can_be_imported_and_merged_again(conn: sqlx::SqlitePool) {
+        time::mock::set(NaiveDate::from_ymd_opt(2020, 1, 10).unwrap());
+        let auth_store = AuthStore::new(conn.clone());
+        let task_store = TaskStore::new(conn.clone(), HouseholdId::default());
+        for person in ["arthur", "bob"] {
+            auth_store.create_test_user(person).await.unwrap();
+        }
+        auth_store
+            .set_admin("arthur", HouseholdId::default(), true)
+            .await
+            .unwrap();
+        let task_id = task_store
+            .add_task(NewTask {
+                names: HashMap::from([
+                    ("en".to_owned(), "Wash the car".to_owned()),
+                    ("de".to_owned(), "Auto waschen".to_owned()),
+                ]),
+                starts_with: "bob".into(),
+                routine: Routine::Interval,
+                duration: 7,
+                starts_on: NaiveDate::from_ymd_opt(2020, 1, 1).unwrap(),
+                participants: vec!["bob".into(), "arthur".into()],
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        task_store
+            .mark_task_done_with(
+                task_id,
+                "bob",
+                &NaiveDate::from_ymd_opt(2020, 1, 8).unwrap(),
+                None,
+                CompletionDetails {
+                    helpers: vec!["arthur".into()],
+                    note: Some("Inside as well".into()),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+
+        let document = export(&auth_store, &task_store, HouseholdId::default(), false)
+            .await
+            .unwrap();
+        assert_eq!(document.version, VERSION);
+        assert!(document.users.iter().all(|user| user.hash.is_none()));
+        assert_eq!(document.tasks[0].participants, vec!["bob", "arthur"]);
+
+        let household = auth_store.create_household("Second").await.unwrap();
+        let other_store = TaskStore::new(conn, household);
+        let result = import(
+            document.clone(),
+            &auth_store,
+            &other_store,
+            household,
+            false,
+        )
+        .await;
+        assert!(matches!(result, Err(ExportError::UserTaken(_))));
+        assert!(auth_store.members(household).await.unwrap().is_empty());
+
+        let summary = import(document.clone(), &auth_store, &other_store, household, true)
+            .await
+            .unwrap();
+        assert_eq!(
+            summary,
+            ImportSummary {
+                users_joined: 2,
+                tasks_added: 1,
+                completions_added: 1,
+                ..Default::default()
+            }
+        );
+        let imported = export(&auth_store, &other_store, household, false)
+            .await
+            .unwrap();
+        assert_eq!(imported.users, document.users);
+        assert_eq!(imported.tasks, document.tasks);
+
+        let summary = import(document, &auth_store, &other_store, household, false)
+            .await
+            .unwrap();
+        assert_eq!(
+            summary,
+            ImportSummary {
+                tasks_merged: 1,
+                ..Default::default()
+            }
+        );
+    }
+    #[sqlx::test]
+    async fn bad_tasks_stop_the_import_before_anything_changes(conn: sqlx::SqlitePool) {
+        time::mock::set(NaiveDate::from_ymd_opt(2020, 1, 10).unwrap());
+        let auth_store = AuthStore::new(conn.clone());
+        let task_store = TaskStore::new(conn.clone(), HouseholdId::default());
+        for person in ["arthur", "bob"] {
+            auth_store.create_test_user(person).await.unwrap();
+        }
+        for name in ["Wash the car", "Water the plants"] {
+            task_store
+                .add_task(NewTask {
+                    names: HashMap::from([("en".to_owned(), name.to_owned())]),
+                    starts_with: "bob".into(),
+                    routine: Routine::Interval,
+                    duration: 7,
+                    starts_on: NaiveDate::from_ymd_opt(2020, 1, 1).unwrap(),
+                    participants: vec!["bob".into(), "arthur".into()],
+                    ..Default::default()
+                })
+                .await
+                .unwrap();
+        }
+        let document = export(&auth_store, &task_store, HouseholdId::default(), false)
+            .await
+            .unwrap();
+
+        let household = auth_store.create_household("Second").await.unwrap();
+        let other_store = TaskStore::new(conn, household);
+
+        let mut stranger = document.clone();
+        stranger.tasks[1].participants.push("charlie".into());
+        let result = import(stranger, &auth_store, &other_store, household, true).await;
+        assert!(matches!(
+            result,
+            Err(ExportError::TaskStoreError(TaskStoreError::PersonDoesNotExist(person)))
+                if person == "charlie"
+        ));
+
+        let mut unstarted = document;
+        unstarted.tasks[1].completions.clear();
+        let result = import(unstarted, &auth_store, &other_store, household, true).await;
+        assert!(matches!(
+            result,
+            Err(ExportError::TaskStoreError(
+                TaskStoreError::NoInitialCompletion
+            ))
+        ));
+
+        assert!(auth_store.members(household).await.unwrap().is_empty());
+        assert!(other_store.export_tasks().await.unwrap().is_empty());
+    }
+}
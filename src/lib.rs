@@ -6,7 +6,9 @@ use axum::{middleware, routing::IntoMakeService, Router};
 use sqlx::SqlitePool;
 
 pub mod auth;
+pub mod config;
 pub mod db;
+pub mod export;
 pub mod static_files;
 pub mod tasks;
 mod translations;
@@ -19,11 +21,33 @@ pub fn server(conn: SqlitePool) -> IntoMakeService<Router> {
         .merge(static_files::routes())
         .nest(
             "/api/tasks",
-            tasks::routes(conn).route_layer(middleware::from_fn_with_state(
+            tasks::routes(conn.clone()).route_layer(middleware::from_fn_with_state(
                 auth.clone(),
                 auth::login_middleware,
             )),
         )
+        .nest(
+            "/api/stats",
+            tasks::stats_routes(conn.clone()).route_layer(middleware::from_fn_with_state(
+                auth.clone(),
+                auth::login_middleware,
+            )),
+        )
+        .nest(
+            "/api/admin",
+            export::admin_routes(conn.clone()).route_layer(middleware::from_fn_with_state(
+                auth.clone(),
+                auth::admin_middleware,
+            )),
+        )
+        .nest("/api/calendar", tasks::calendar_routes(conn.clone()))
+        .nest(
+            tasks::CALDAV_ROOT,
+            tasks::caldav_routes(conn).route_layer(middleware::from_fn_with_state(
+                auth.clone(),
+                auth::basic_auth_middleware,
+            )),
+        )
         .nest("/api/auth", auth::routes(auth))
         .layer(tower_http::trace::TraceLayer::new_for_http());
 
@@ -0,0 +1,86 @@
+// SPDX-FileCopyrightText: 2023 Jonathan Frere
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! Images uploaded when tasks are done, such as a photo of the cleaned oven.
+//! The images themselves are kept as files in an `attachments` directory next
+//! to the database, and everything else about them in the database.
+
+use std::{
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use sqlx::SqlitePool;
+
+/// Largest image that can be attached to a completion, in bytes.
+pub const MAX_ATTACHMENT_SIZE: usize = 5 * 1024 * 1024;
+
+/// Most images that can be attached to a single completion.
+pub const MAX_ATTACHMENTS: usize = 4;
+
+/// Largest request that can be made when uploading images along with a
+/// completion, leaving some room for the rest of the form.
+pub const MAX_UPLOAD_SIZE: usize = MAX_ATTACHMENTS * MAX_ATTACHMENT_SIZE + 64 * 1024;
+
+/// Image formats that browsers can show.  SVGs are left out, because they can
+/// contain scripts.
+const IMAGE_TYPES: &[&str] = &[
+    "image/jpeg",
+    "image/png",
+    "image/gif",
+    "image/webp",
+    "image/avif",
+    "image/heic",
+];
+
+/// An image to attach to a completion.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NewAttachment {
+    pub content_type: String,
+    pub data: Vec<u8>,
+}
+
+pub fn is_image_type(content_type: &str) -> bool {
+    IMAGE_TYPES.contains(&content_type.to_lowercase().as_str())
+}
+
+/// Where the attachments are stored, which is alongside the database file.
+pub async fn directory(conn: &SqlitePool) -> Result<PathBuf, sqlx::Error> {
+    let (file,) =
+        sqlx::query_as::<_, (String,)>("SELECT file FROM pragma_database_list WHERE name = 'main'")
+            .fetch_one(conn)
+            .await?;
+    Ok(Path::new(&file).with_file_name("attachments"))
+}
+
+pub async fn write(directory: &Path, file_name: &str, data: &[u8]) -> std::io::Result<()> {
+    tokio::fs::create_dir_all(directory).await?;
+    tokio::fs::write(directory.join(file_name), data).await
+}
+
+pub async fn read(directory: &Path, file_name: &str) -> std::io::Result<Vec<u8>> {
+    tokio::fs::read(directory.join(file_name)).await
+}
+
+/// Deletes an attachment's file, if it still exists.
+pub async fn remove(directory: &Path, file_name: &str) -> std::io::Result<()> {
+    match tokio::fs::remove_file(directory.join(file_name)).await {
+        Err(error) if error.kind() != ErrorKind::NotFound => Err(error),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_images_that_browsers_show_safely_are_allowed() {
+        assert!(is_image_type("image/jpeg"));
+        assert!(is_image_type("IMAGE/PNG"));
+        assert!(!is_image_type("image/svg+xml"));
+        assert!(!is_image_type("text/html"));
+        assert!(!is_image_type(""));
+    }
+}
@@ -0,0 +1,383 @@
+// SPDX-FileCopyrightText: 2023 Jonathan Frere
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! A minimal CalDAV (RFC 4791) server, so that task apps on phones can show
+//! people the tasks assigned to them as to-dos, and mark them as done.
+//!
+//! Each person gets a single calendar collection at `<root>/<person>/tasks/`,
+//! containing one `<task id>.ics` resource per task assigned to them.  The
+//! only change clients can make is completing a to-do, which marks the task
+//! as done; everything else is read-only.
+
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+};
+
+use axum::{
+    extract::Path,
+    http::{header, HeaderMap, Method, StatusCode},
+    response::{IntoResponse, Response},
+    routing::any,
+    Extension, Router,
+};
+use chrono::NaiveDate;
+use sqlx::SqlitePool;
+
+use crate::{
+    auth::BasicUser,
+    translations::{ExtractLanguage, Language},
+};
+
+use super::{
+    calendar::{self, Completed},
+    store::TaskStoreError,
+    time::{date_in, now},
+    types::{Task, TaskId},
+    TaskStore,
+};
+
+/// Where the CalDAV routes are mounted, which is needed to build the links
+/// between resources.
+pub const CALDAV_ROOT: &str = "/caldav";
+
+const ALLOWED_METHODS: &str = "OPTIONS, GET, PUT, DELETE, PROPFIND, REPORT";
+
+const ICALENDAR_TODO: &str = "text/calendar; charset=utf-8; component=VTODO";
+
+/// A task as it is shown to CalDAV clients.
+struct Todo {
+    task: Task,
+    due: Option<NaiveDate>,
+}
+
+impl Todo {
+    fn href(&self, person: &str) -> String {
+        format!("{}{}.ics", collection_href(person), self.task.id)
+    }
+
+    /// Changes whenever anything the client can see about the task changes.
+    fn etag(&self) -> String {
+        let mut hasher = DefaultHasher::new();
+        (&self.task.name, &self.task.description, self.due).hash(&mut hasher);
+        format!("\"{}-{:x}\"", self.task.id, hasher.finish())
+    }
+
+    fn render(&self) -> String {
+        calendar::render_todo(&self.task, self.due, now())
+    }
+}
+
+async fn todos(
+    store: &TaskStore,
+    person: &str,
+    language: &Language,
+) -> Result<Vec<Todo>, TaskStoreError> {
+    let today = store.today_for(Some(person)).await?;
+    let tasks = store.tasks_for(person, language).await?;
+    Ok(tasks
+        .into_iter()
+        .map(|task| Todo {
+            due: task.deadline.due_date(today),
+            task,
+        })
+        .collect())
+}
+
+/// Changes whenever any of the to-dos in a collection change, so that clients
+/// know when they need to sync.
+fn ctag(todos: &[Todo]) -> String {
+    let mut hasher = DefaultHasher::new();
+    for todo in todos {
+        todo.etag().hash(&mut hasher);
+    }
+    format!("{:x}", hasher.finish())
+}
+
+fn principal_href(person: &str) -> String {
+    format!("{CALDAV_ROOT}/{person}/")
+}
+
+fn collection_href(person: &str) -> String {
+    format!("{CALDAV_ROOT}/{person}/tasks/")
+}
+
+/// One `<response>` in a multi-status reply, with the given properties already
+/// rendered as XML.
+struct PropResponse {
+    href: String,
+    props: Vec<String>,
+}
+
+fn multistatus(responses: Vec<PropResponse>) -> Response {
+    let mut body = String::from(concat!(
+        r#"<?xml version="1.0" encoding="utf-8"?>"#,
+        r#"<D:multistatus xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:caldav" "#,
+        r#"xmlns:CS="http://calendarserver.org/ns/">"#
+    ));
+    for response in responses {
+        body.push_str(&format!(
+            "<D:response><D:href>{}</D:href><D:propstat><D:prop>{}</D:prop>\
+             <D:status>HTTP/1.1 200 OK</D:status></D:propstat></D:response>",
+            escape(&response.href),
+            response.props.concat()
+        ));
+    }
+    body.push_str("</D:multistatus>");
+
+    (
+        StatusCode::MULTI_STATUS,
+        [(header::CONTENT_TYPE, "application/xml; charset=utf-8")],
+        body,
+    )
+        .into_response()
+}
+
+fn resource_type(types: &str) -> String {
+    format!("<D:resourcetype>{types}</D:resourcetype>")
+}
+
+fn href_prop(name: &str, href: &str) -> String {
+    format!("<{name}><D:href>{}</D:href></{name}>", escape(href))
+}
+
+fn text_prop(name: &str, text: &str) -> String {
+    format!("<{name}>{}</{name}>", escape(text))
+}
+
+fn principal_response(person: &str, user: &str) -> PropResponse {
+    PropResponse {
+        href: principal_href(person),
+        props: vec![
+            resource_type("<D:collection/><D:principal/>"),
+            text_prop("D:displayname", person),
+            href_prop("D:current-user-principal", &principal_href(user)),
+            href_prop("D:principal-URL", &principal_href(person)),
+            href_prop("C:calendar-home-set", &principal_href(person)),
+        ],
+    }
+}
+
+fn collection_response(person: &str, todos: &[Todo]) -> PropResponse {
+    PropResponse {
+        href: collection_href(person),
+        props: vec![
+            resource_type("<D:collection/><C:calendar/>"),
+            text_prop("D:displayname", &format!("Tasks for {person}")),
+            r#"<C:supported-calendar-component-set><C:comp name="VTODO"/></C:supported-calendar-component-set>"#.to_owned(),
+            text_prop("CS:getctag", &ctag(todos)),
+        ],
+    }
+}
+
+fn todo_response(person: &str, todo: &Todo, with_data: bool) -> PropResponse {
+    let mut props = vec![
+        resource_type(""),
+        text_prop("D:getetag", &todo.etag()),
+        text_prop("D:getcontenttype", ICALENDAR_TODO),
+    ];
+    if with_data {
+        props.push(text_prop("C:calendar-data", &todo.render()));
+    }
+
+    PropResponse {
+        href: todo.href(person),
+        props,
+    }
+}
+
+/// Escapes text for use inside an XML element.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Whether a PROPFIND should also describe the resource's children.
+fn includes_children(headers: &HeaderMap) -> bool {
+    headers
+        .get("depth")
+        .and_then(|depth| depth.to_str().ok())
+        .is_none_or(|depth| depth.trim() != "0")
+}
+
+fn options() -> Response {
+    (
+        StatusCode::OK,
+        [
+            (header::ALLOW, ALLOWED_METHODS),
+            (
+                header::HeaderName::from_static("dav"),
+                "1, 3, calendar-access",
+            ),
+        ],
+    )
+        .into_response()
+}
+
+fn method_not_allowed() -> Response {
+    (
+        StatusCode::METHOD_NOT_ALLOWED,
+        [(header::ALLOW, ALLOWED_METHODS)],
+    )
+        .into_response()
+}
+
+/// People can only see their own tasks.
+fn is_own(user: &BasicUser, person: &str) -> bool {
+    user.0.to_lowercase() == person.to_lowercase()
+}
+
+/// Points clients that only know the server's address at the user's principal.
+async fn root(method: Method, Extension(user): Extension<BasicUser>) -> Response {
+    match method.as_str() {
+        "OPTIONS" => options(),
+        "PROPFIND" => multistatus(vec![PropResponse {
+            href: format!("{CALDAV_ROOT}/"),
+            props: vec![
+                resource_type("<D:collection/>"),
+                href_prop("D:current-user-principal", &principal_href(&user.0)),
+            ],
+        }]),
+        _ => method_not_allowed(),
+    }
+}
+
+async fn principal(
+    method: Method,
+    Path(person): Path<String>,
+    Extension(user): Extension<BasicUser>,
+    store: TaskStore,
+    ExtractLanguage(language): ExtractLanguage,
+    headers: HeaderMap,
+) -> Result<Response, TaskStoreError> {
+    if !is_own(&user, &person) {
+        return Ok(StatusCode::FORBIDDEN.into_response());
+    }
+
+    Ok(match method.as_str() {
+        "OPTIONS" => options(),
+        "PROPFIND" => {
+            let mut responses = vec![principal_response(&person, &user.0)];
+            if includes_children(&headers) {
+                let todos = todos(&store, &person, &language).await?;
+                responses.push(collection_response(&person, &todos));
+            }
+            multistatus(responses)
+        }
+        _ => method_not_allowed(),
+    })
+}
+
+async fn collection(
+    method: Method,
+    Path(person): Path<String>,
+    Extension(user): Extension<BasicUser>,
+    store: TaskStore,
+    ExtractLanguage(language): ExtractLanguage,
+    headers: HeaderMap,
+    body: String,
+) -> Result<Response, TaskStoreError> {
+    if !is_own(&user, &person) {
+        return Ok(StatusCode::FORBIDDEN.into_response());
+    }
+
+    Ok(match method.as_str() {
+        "OPTIONS" => options(),
+        "PROPFIND" => {
+            let todos = todos(&store, &person, &language).await?;
+            let mut responses = vec![collection_response(&person, &todos)];
+            if includes_children(&headers) {
+                responses.extend(todos.iter().map(|todo| todo_response(&person, todo, false)));
+            }
+            multistatus(responses)
+        }
+        "REPORT" => {
+            // Both calendar queries and multigets are answered with every
+            // to-do (there are never many), except that multigets only get
+            // the to-dos they asked for.
+            let todos = todos(&store, &person, &language).await?;
+            let multiget = body.contains("calendar-multiget");
+            multistatus(
+                todos
+                    .iter()
+                    .filter(|todo| !multiget || body.contains(&format!("/{}.ics", todo.task.id)))
+                    .map(|todo| todo_response(&person, todo, true))
+                    .collect(),
+            )
+        }
+        _ => method_not_allowed(),
+    })
+}
+
+async fn todo(
+    method: Method,
+    Path((person, file_name)): Path<(String, String)>,
+    Extension(user): Extension<BasicUser>,
+    store: TaskStore,
+    ExtractLanguage(language): ExtractLanguage,
+    body: String,
+) -> Result<Response, TaskStoreError> {
+    if !is_own(&user, &person) {
+        return Ok(StatusCode::FORBIDDEN.into_response());
+    }
+
+    let task_id = file_name
+        .strip_suffix(".ics")
+        .and_then(|id| id.parse::<i32>().ok())
+        .map(TaskId::from);
+    let todos = todos(&store, &person, &language).await?;
+    let todo = todos.into_iter().find(|todo| Some(todo.task.id) == task_id);
+
+    let Some(todo) = todo else {
+        return Ok(match method.as_str() {
+            "OPTIONS" => options(),
+            // new to-dos can't be created from a client
+            "PUT" => StatusCode::FORBIDDEN.into_response(),
+            _ => StatusCode::NOT_FOUND.into_response(),
+        });
+    };
+
+    Ok(match method.as_str() {
+        "OPTIONS" => options(),
+        "GET" => (
+            [
+                (header::CONTENT_TYPE, ICALENDAR_TODO.to_owned()),
+                (header::ETAG, todo.etag()),
+            ],
+            todo.render(),
+        )
+            .into_response(),
+        "PUT" => {
+            // Anything other than completing the to-do is ignored.  No ETag
+            // is sent back, because the client's copy is now out of date:
+            // either the task is due again later, or nothing has changed.
+            if let Some(completed) = calendar::completion(&body) {
+                let on = match completed {
+                    Completed::At(time) => date_in(time, store.timezone_for(Some(&user.0)).await?),
+                    Completed::On(date) => date,
+                    Completed::Unknown => store.today_for(Some(&user.0)).await?,
+                };
+                store
+                    .mark_task_done(todo.task.id, &user.0, &on, None)
+                    .await?;
+            }
+            StatusCode::NO_CONTENT.into_response()
+        }
+        "DELETE" => StatusCode::FORBIDDEN.into_response(),
+        _ => method_not_allowed(),
+    })
+}
+
+/// CalDAV routes, which expect an authenticated [`BasicUser`].
+pub fn caldav_routes(conn: SqlitePool) -> Router {
+    Router::new()
+        .route("/", any(root))
+        .route("/:person", any(principal))
+        .route("/:person/", any(principal))
+        .route("/:person/tasks", any(collection))
+        .route("/:person/tasks/", any(collection))
+        .route("/:person/tasks/:file", any(todo))
+        .with_state(conn)
+}
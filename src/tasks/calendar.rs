@@ -0,0 +1,351 @@
+// SPDX-FileCopyrightText: 2023 Jonathan Frere
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! iCalendar (RFC 5545) feeds, so that upcoming tasks show up in calendar
+//! apps.  Each time a task is forecast to be due becomes an all-day event.
+//! Tasks can also be rendered as to-dos (VTODOs) for CalDAV clients, and the
+//! to-dos those clients send back can be checked for completion.
+
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, Utc};
+
+use super::types::{ForecastEntry, Task};
+
+/// Lines longer than this many bytes are folded onto continuation lines.
+const MAX_LINE_LENGTH: usize = 75;
+
+/// Renders the given forecast as an iCalendar document.  If `show_assignee` is
+/// set, each event's summary also says who the task is assigned to.
+pub(super) fn render(
+    calendar_name: &str,
+    entries: &[ForecastEntry],
+    show_assignee: bool,
+    stamp: DateTime<Utc>,
+) -> String {
+    let mut calendar = begin_calendar();
+    push_line(
+        &mut calendar,
+        &format!("X-WR-CALNAME:{}", escape(calendar_name)),
+    );
+
+    for entry in entries {
+        let summary = match (&entry.assigned_to, show_assignee) {
+            (Some(person), true) => format!("{} ({person})", entry.name),
+            _ => entry.name.clone(),
+        };
+
+        push_line(&mut calendar, "BEGIN:VEVENT");
+        push_line(
+            &mut calendar,
+            &format!("UID:task-{}-{}@homie", entry.task_id, date(entry.due)),
+        );
+        push_line(
+            &mut calendar,
+            &format!("DTSTAMP:{}", stamp.format("%Y%m%dT%H%M%SZ")),
+        );
+        push_line(
+            &mut calendar,
+            &format!("DTSTART;VALUE=DATE:{}", date(entry.due)),
+        );
+        push_line(
+            &mut calendar,
+            &format!("DTEND;VALUE=DATE:{}", date(entry.due + Duration::days(1))),
+        );
+        push_line(&mut calendar, &format!("SUMMARY:{}", escape(&summary)));
+        push_line(&mut calendar, "END:VEVENT");
+    }
+
+    push_line(&mut calendar, "END:VCALENDAR");
+    calendar
+}
+
+/// Renders a task as an iCalendar document containing a single to-do, which
+/// is due on `due` if the task has a due date.
+pub(super) fn render_todo(task: &Task, due: Option<NaiveDate>, stamp: DateTime<Utc>) -> String {
+    let mut calendar = begin_calendar();
+    push_line(&mut calendar, "BEGIN:VTODO");
+    push_line(&mut calendar, &format!("UID:task-{}@homie", task.id));
+    push_line(
+        &mut calendar,
+        &format!("DTSTAMP:{}", stamp.format("%Y%m%dT%H%M%SZ")),
+    );
+    push_line(&mut calendar, &format!("SUMMARY:{}", escape(&task.name)));
+    if let Some(description) = &task.description {
+        push_line(
+            &mut calendar,
+            &format!("DESCRIPTION:{}", escape(description)),
+        );
+    }
+    if let Some(due) = due {
+        push_line(&mut calendar, &format!("DUE;VALUE=DATE:{}", date(due)));
+    }
+    push_line(&mut calendar, "STATUS:NEEDS-ACTION");
+    push_line(&mut calendar, "END:VTODO");
+    push_line(&mut calendar, "END:VCALENDAR");
+    calendar
+}
+
+/// When a client says that a to-do was completed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Completed {
+    /// At a fixed moment, which falls on different dates in different timezones
+    At(DateTime<Utc>),
+    /// On a date, without a timezone
+    On(NaiveDate),
+    /// The client didn't say when
+    Unknown,
+}
+
+/// Checks whether the to-do in an iCalendar document has been completed, and
+/// if so, when.
+pub(super) fn completion(calendar: &str) -> Option<Completed> {
+    let mut in_todo = false;
+    let mut status_completed = false;
+    let mut completed = Completed::Unknown;
+
+    for line in unfold(calendar) {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        // drop any parameters, e.g. the `TZID` in `COMPLETED;TZID=...`
+        let name = name.split(';').next().unwrap_or_default();
+
+        match name.to_ascii_uppercase().as_str() {
+            "BEGIN" if value.eq_ignore_ascii_case("VTODO") => in_todo = true,
+            "END" if value.eq_ignore_ascii_case("VTODO") => in_todo = false,
+            "STATUS" if in_todo => status_completed = value.eq_ignore_ascii_case("COMPLETED"),
+            "COMPLETED" if in_todo => completed = parse_completed(value),
+            _ => {}
+        }
+    }
+
+    status_completed.then_some(completed)
+}
+
+fn parse_completed(value: &str) -> Completed {
+    if let Some(utc) = value.strip_suffix('Z') {
+        if let Ok(time) = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S") {
+            return Completed::At(time.and_utc());
+        }
+    }
+
+    value
+        .get(..8)
+        .and_then(|date| NaiveDate::parse_from_str(date, "%Y%m%d").ok())
+        .map_or(Completed::Unknown, Completed::On)
+}
+
+/// Joins folded content lines back together.
+fn unfold(calendar: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in calendar.lines() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(continuation), Some(previous)) => previous.push_str(continuation),
+            _ => lines.push(line.to_owned()),
+        }
+    }
+    lines
+}
+
+fn begin_calendar() -> String {
+    let mut calendar = String::new();
+    push_line(&mut calendar, "BEGIN:VCALENDAR");
+    push_line(&mut calendar, "VERSION:2.0");
+    push_line(&mut calendar, "PRODID:-//homie//tasks//EN");
+    push_line(&mut calendar, "CALSCALE:GREGORIAN");
+    calendar
+}
+
+fn date(date: NaiveDate) -> String {
+    date.format("%Y%m%d").to_string()
+}
+
+/// Escapes the characters that have a special meaning in text values.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+/// Adds a content line, folding it so that no line is longer than
+/// [`MAX_LINE_LENGTH`] bytes (without splitting any characters).
+fn push_line(calendar: &mut String, line: &str) {
+    let mut length = 0;
+    for char in line.chars() {
+        if length + char.len_utf8() > MAX_LINE_LENGTH {
+            calendar.push_str("\r\n ");
+            // the leading space counts towards the continuation line's length
+            length = 1;
+        }
+        calendar.push(char);
+        length += char.len_utf8();
+    }
+    calendar.push_str("\r\n");
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+    use crate::tasks::types::{CatchUp, Deadline, Rotation, Routine, Urgency};
+
+    fn entry(name: &str, due: NaiveDate, assigned_to: Option<&str>) -> ForecastEntry {
+        ForecastEntry {
+            task_id: 1.into(),
+            name: name.to_owned(),
+            due,
+            assigned_to: assigned_to.map(str::to_owned),
+        }
+    }
+
+    #[test]
+    fn renders_forecast_as_all_day_events() {
+        let stamp = Utc.with_ymd_and_hms(2023, 1, 10, 8, 30, 0).unwrap();
+        let due = NaiveDate::from_ymd_opt(2023, 1, 31).unwrap();
+        let calendar = render(
+            "Household",
+            &[entry("Hoover", due, Some("arthur"))],
+            true,
+            stamp,
+        );
+
+        assert_eq!(
+            calendar,
+            [
+                "BEGIN:VCALENDAR",
+                "VERSION:2.0",
+                "PRODID:-//homie//tasks//EN",
+                "CALSCALE:GREGORIAN",
+                "X-WR-CALNAME:Household",
+                "BEGIN:VEVENT",
+                "UID:task-1-20230131@homie",
+                "DTSTAMP:20230110T083000Z",
+                "DTSTART;VALUE=DATE:20230131",
+                "DTEND;VALUE=DATE:20230201",
+                "SUMMARY:Hoover (arthur)",
+                "END:VEVENT",
+                "END:VCALENDAR",
+                "",
+            ]
+            .join("\r\n")
+        );
+    }
+
+    #[test]
+    fn renders_tasks_as_todos() {
+        let stamp = Utc.with_ymd_and_hms(2023, 1, 10, 8, 30, 0).unwrap();
+        let due = NaiveDate::from_ymd_opt(2023, 1, 31).unwrap();
+        let task = Task {
+            id: 3.into(),
+            name: "Hoover".to_owned(),
+            description: Some("Upstairs, too".to_owned()),
+            kind: Routine::Interval,
+            rotation: Rotation::RoundRobin,
+            catch_up: CatchUp::default(),
+            recurrence: None,
+            active_window: None,
+            active: true,
+            assigned_to: Some("arthur".to_owned()),
+            deadline: Deadline::Upcoming(21),
+            urgency: Urgency::default(),
+            points: 0,
+            length_days: 28,
+            last_completed: NaiveDate::from_ymd_opt(2023, 1, 3).unwrap(),
+            participants: vec!["arthur".to_owned()],
+            tags: vec![],
+            checklist: vec![],
+            last_steps_done: None,
+        };
+
+        assert_eq!(
+            render_todo(&task, Some(due), stamp),
+            [
+                "BEGIN:VCALENDAR",
+                "VERSION:2.0",
+                "PRODID:-//homie//tasks//EN",
+                "CALSCALE:GREGORIAN",
+                "BEGIN:VTODO",
+                "UID:task-3@homie",
+                "DTSTAMP:20230110T083000Z",
+                "SUMMARY:Hoover",
+                "DESCRIPTION:Upstairs\\, too",
+                "DUE;VALUE=DATE:20230131",
+                "STATUS:NEEDS-ACTION",
+                "END:VTODO",
+                "END:VCALENDAR",
+                "",
+            ]
+            .join("\r\n")
+        );
+    }
+
+    #[test]
+    fn finds_when_todos_were_completed() {
+        let todo = |properties: &[&str]| {
+            [
+                &["BEGIN:VCALENDAR", "BEGIN:VTODO", "UID:task-3@homie"],
+                properties,
+                &["END:VTODO", "END:VCALENDAR"],
+            ]
+            .concat()
+            .join("\r\n")
+        };
+
+        assert_eq!(completion(&todo(&["STATUS:NEEDS-ACTION"])), None);
+        assert_eq!(
+            completion(&todo(&["STATUS:COMPLETED"])),
+            Some(Completed::Unknown)
+        );
+        assert_eq!(
+            completion(&todo(&["COMPLETED:20230110T233000Z", "STATUS:COMPLETED"])),
+            Some(Completed::At(
+                Utc.with_ymd_and_hms(2023, 1, 10, 23, 30, 0).unwrap()
+            ))
+        );
+        assert_eq!(
+            completion(&todo(&[
+                "status:completed",
+                "COMPLETED;TZID=Europe/Berlin:20230111T003000"
+            ])),
+            Some(Completed::On(NaiveDate::from_ymd_opt(2023, 1, 11).unwrap()))
+        );
+        // a completion date on its own doesn't count, only the status does
+        assert_eq!(completion(&todo(&["COMPLETED:20230110T233000Z"])), None);
+    }
+
+    #[test]
+    fn unfolds_lines_before_reading_them() {
+        let calendar = "BEGIN:VTODO\r\nSTATUS:COMP\r\n LETED\r\nCOMPLETED:20230110\r\n\tT233000Z\r\nEND:VTODO\r\n";
+        assert_eq!(
+            completion(calendar),
+            Some(Completed::At(
+                Utc.with_ymd_and_hms(2023, 1, 10, 23, 30, 0).unwrap()
+            ))
+        );
+    }
+
+    #[test]
+    fn escapes_special_characters() {
+        assert_eq!(
+            escape("Wash, dry; fold\\iron\nput away"),
+            "Wash\\, dry\\; fold\\\\iron\\nput away"
+        );
+    }
+
+    #[test]
+    fn folds_long_lines_without_splitting_characters() {
+        let mut calendar = String::new();
+        let line = format!("SUMMARY:{}", "ä".repeat(40));
+        push_line(&mut calendar, &line);
+
+        let lines = calendar.split("\r\n").collect::<Vec<_>>();
+        assert_eq!(lines.len(), 3);
+        assert!(lines.iter().all(|line| line.len() <= MAX_LINE_LENGTH));
+        assert!(lines[1].starts_with(' '));
+        assert_eq!(lines.concat().replace(' ', ""), line.replace(' ', ""));
+        assert_eq!(lines[2], "");
+    }
+}
@@ -0,0 +1,16 @@
+-- SPDX-FileCopyrightText: 2023 Jonathan Frere
+--
+-- SPDX-License-Identifier: MPL-2.0
+UPDATE tasks
+SET
+  claimed_by = NULL
+WHERE
+  id = ?1
+  AND claimed_by = (
+    SELECT
+      id
+    FROM
+      users
+    WHERE
+      username = ?2 COLLATE NOCASE
+  )
\ No newline at end of file
@@ -0,0 +1,14 @@
+-- SPDX-FileCopyrightText: 2023 Jonathan Frere
+--
+-- SPDX-License-Identifier: MPL-2.0
+DELETE FROM absences
+WHERE
+  id = ?
+  AND user_id IN (
+    SELECT
+      user_id
+    FROM
+      household_members
+    WHERE
+      household_id = ?
+  )
\ No newline at end of file
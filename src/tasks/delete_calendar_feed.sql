@@ -0,0 +1,7 @@
+-- SPDX-FileCopyrightText: 2023 Jonathan Frere
+--
+-- SPDX-License-Identifier: MPL-2.0
+DELETE FROM calendar_feeds
+WHERE
+  id = ?
+  AND household_id = ?
\ No newline at end of file
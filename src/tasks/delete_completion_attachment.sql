@@ -0,0 +1,6 @@
+-- SPDX-FileCopyrightText: 2023 Jonathan Frere
+--
+-- SPDX-License-Identifier: MPL-2.0
+DELETE FROM completion_attachments
+WHERE
+  id = ?
\ No newline at end of file
@@ -0,0 +1,6 @@
+-- SPDX-FileCopyrightText: 2023 Jonathan Frere
+--
+-- SPDX-License-Identifier: MPL-2.0
+DELETE FROM task_tag_link
+WHERE
+  tag_id = ?
\ No newline at end of file
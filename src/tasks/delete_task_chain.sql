@@ -0,0 +1,14 @@
+-- SPDX-FileCopyrightText: 2023 Jonathan Frere
+--
+-- SPDX-License-Identifier: MPL-2.0
+DELETE FROM task_chains
+WHERE
+  id = ?
+  AND from_task_id IN (
+    SELECT
+      id
+    FROM
+      tasks
+    WHERE
+      household_id = ?
+  )
\ No newline at end of file
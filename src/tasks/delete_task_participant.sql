@@ -0,0 +1,14 @@
+-- SPDX-FileCopyrightText: 2023 Jonathan Frere
+--
+-- SPDX-License-Identifier: MPL-2.0
+DELETE FROM task_participant_link
+WHERE
+  task_id = ?1
+  AND user_id = (
+    SELECT
+      id
+    FROM
+      users
+    WHERE
+      username = ?2 COLLATE NOCASE
+  )
\ No newline at end of file
@@ -0,0 +1,7 @@
+-- SPDX-FileCopyrightText: 2023 Jonathan Frere
+--
+-- SPDX-License-Identifier: MPL-2.0
+DELETE FROM task_tag_link
+WHERE
+  task_id = ?
+  AND tag_id = ?
\ No newline at end of file
@@ -0,0 +1,15 @@
+-- SPDX-FileCopyrightText: 2023 Jonathan Frere
+--
+-- SPDX-License-Identifier: MPL-2.0
+INSERT INTO
+  absences (user_id, starts_on, ends_on)
+SELECT
+  id,
+  ?,
+  ?
+FROM
+  users
+  INNER JOIN household_members ON household_members.user_id = users.id
+WHERE
+  users.username = ? COLLATE NOCASE
+  AND household_members.household_id = ? RETURNING absences.id
\ No newline at end of file
@@ -0,0 +1,27 @@
+-- SPDX-FileCopyrightText: 2023 Jonathan Frere
+--
+-- SPDX-License-Identifier: MPL-2.0
+INSERT INTO
+  calendar_feeds (secret, user_id, lang, household_id)
+SELECT
+  ?1,
+  users.id,
+  ?3,
+  ?4
+FROM
+  (
+    SELECT
+      1
+  )
+  LEFT JOIN users ON users.username = ?2 COLLATE NOCASE
+  AND users.id IN (
+    SELECT
+      user_id
+    FROM
+      household_members
+    WHERE
+      household_id = ?4
+  )
+WHERE
+  ?2 IS NULL
+  OR users.id IS NOT NULL RETURNING calendar_feeds.id
\ No newline at end of file
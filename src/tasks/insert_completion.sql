@@ -2,7 +2,7 @@
 --
 -- SPDX-License-Identifier: MPL-2.0
 INSERT INTO
-  completions (task_id, completed_on, completed_by)
+  completions (task_id, completed_on, completed_by, steps_done, note)
 VALUES
   (
     ?,
@@ -12,7 +12,13 @@ VALUES
         users.id
       FROM
         users
+        INNER JOIN household_members ON household_members.user_id = users.id
       WHERE
         users.username = ? COLLATE NOCASE
-    )
-  )
\ No newline at end of file
+        AND household_members.household_id = ?
+    ),
+    ?,
+    ?
+  )
+RETURNING
+  completions.id
\ No newline at end of file
@@ -0,0 +1,7 @@
+-- SPDX-FileCopyrightText: 2023 Jonathan Frere
+--
+-- SPDX-License-Identifier: MPL-2.0
+INSERT INTO
+  completion_attachments (completion_id, file_name, content_type, size)
+VALUES
+  (?, ?, ?, ?)
\ No newline at end of file
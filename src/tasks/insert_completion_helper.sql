@@ -0,0 +1,14 @@
+-- SPDX-FileCopyrightText: 2023 Jonathan Frere
+--
+-- SPDX-License-Identifier: MPL-2.0
+INSERT INTO
+  completion_helpers (completion_id, user_id)
+SELECT
+  ?,
+  users.id
+FROM
+  users
+  INNER JOIN household_members ON household_members.user_id = users.id
+WHERE
+  users.username = ? COLLATE NOCASE
+  AND household_members.household_id = ?
\ No newline at end of file
@@ -0,0 +1,28 @@
+-- SPDX-FileCopyrightText: 2023 Jonathan Frere
+--
+-- SPDX-License-Identifier: MPL-2.0
+INSERT INTO
+  points_ledger (
+    household_id,
+    user_id,
+    entry_on,
+    kind,
+    points,
+    task_id,
+    completion_id
+  )
+SELECT
+  tasks.household_id,
+  users.id,
+  ?,
+  'Completion',
+  tasks.points,
+  tasks.id,
+  ?
+FROM
+  tasks,
+  users
+WHERE
+  tasks.id = ?
+  AND tasks.points > 0
+  AND users.username = ? COLLATE NOCASE
\ No newline at end of file
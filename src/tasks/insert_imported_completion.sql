@@ -0,0 +1,19 @@
+-- SPDX-FileCopyrightText: 2023 Jonathan Frere
+--
+-- SPDX-License-Identifier: MPL-2.0
+INSERT INTO
+  completions (task_id, completed_on, completed_by, note, initial)
+SELECT
+  ?,
+  ?,
+  users.id,
+  ?,
+  ?
+FROM
+  users
+  INNER JOIN household_members ON household_members.user_id = users.id
+WHERE
+  users.username = ? COLLATE NOCASE
+  AND household_members.household_id = ?
+RETURNING
+  completions.id
\ No newline at end of file
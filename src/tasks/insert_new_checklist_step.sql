@@ -0,0 +1,7 @@
+-- SPDX-FileCopyrightText: 2023 Jonathan Frere
+--
+-- SPDX-License-Identifier: MPL-2.0
+INSERT INTO
+  checklist_steps (task_id, position)
+VALUES
+  (?, ?) RETURNING checklist_steps.id
\ No newline at end of file
@@ -0,0 +1,7 @@
+-- SPDX-FileCopyrightText: 2023 Jonathan Frere
+--
+-- SPDX-License-Identifier: MPL-2.0
+INSERT INTO
+  checklist_step_translations (step_id, lang, step_name)
+VALUES
+  (?, ?, ?)
\ No newline at end of file
@@ -0,0 +1,7 @@
+-- SPDX-FileCopyrightText: 2023 Jonathan Frere
+--
+-- SPDX-License-Identifier: MPL-2.0
+INSERT INTO
+  tags (household_id)
+VALUES
+  (?) RETURNING tags.id
\ No newline at end of file
@@ -0,0 +1,7 @@
+-- SPDX-FileCopyrightText: 2023 Jonathan Frere
+--
+-- SPDX-License-Identifier: MPL-2.0
+INSERT INTO
+  tag_translations (tag_id, lang, tag_name)
+VALUES
+  (?, ?, ?)
\ No newline at end of file
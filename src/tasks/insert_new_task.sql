@@ -2,6 +2,19 @@
 --
 -- SPDX-License-Identifier: MPL-2.0
 INSERT INTO
-  tasks (kind, duration)
+  tasks (
+    kind,
+    duration,
+    rotation,
+    catch_up,
+    recurrence,
+    due_on,
+    active_window,
+    due_soon_days,
+    grace_days,
+    points,
+    config_key,
+    household_id
+  )
 VALUES
-  (?, ?) RETURNING tasks.id
\ No newline at end of file
+  (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?) RETURNING tasks.id
\ No newline at end of file
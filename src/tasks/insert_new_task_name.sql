@@ -2,6 +2,6 @@
 --
 -- SPDX-License-Identifier: MPL-2.0
 INSERT INTO
-    task_translations (task_id, lang, task_name)
+  task_translations (task_id, lang, task_name, description)
 VALUES
-    (?, ?, ?)
\ No newline at end of file
+  (?, ?, ?, ?)
\ No newline at end of file
@@ -1,13 +1,25 @@
 -- SPDX-FileCopyrightText: 2023 Jonathan Frere
 --
 -- SPDX-License-Identifier: MPL-2.0
-
 INSERT INTO
-  task_participant_link (task_id, user_id)
+  task_participant_link (task_id, user_id, position)
 SELECT
-  ?,
-  id
+  ?1,
+  id,
+  coalesce(
+    (
+      SELECT
+        max(position) + 1
+      FROM
+        task_participant_link
+      WHERE
+        task_id = ?1
+    ),
+    0
+  )
 FROM
   users
+  INNER JOIN household_members ON household_members.user_id = users.id
 WHERE
-  users.username = ? COLLATE nocase
\ No newline at end of file
+  users.username = ?2 COLLATE nocase
+  AND household_members.household_id = ?3
\ No newline at end of file
@@ -0,0 +1,27 @@
+-- SPDX-FileCopyrightText: 2023 Jonathan Frere
+--
+-- SPDX-License-Identifier: MPL-2.0
+INSERT INTO
+  points_ledger (
+    household_id,
+    user_id,
+    entry_on,
+    kind,
+    points,
+    description
+  )
+SELECT
+  household_members.household_id,
+  users.id,
+  ?,
+  ?,
+  ?,
+  ?
+FROM
+  users
+  INNER JOIN household_members ON household_members.user_id = users.id
+WHERE
+  users.username = ? COLLATE NOCASE
+  AND household_members.household_id = ?
+RETURNING
+  points_ledger.id
\ No newline at end of file
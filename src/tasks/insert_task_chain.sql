@@ -0,0 +1,7 @@
+-- SPDX-FileCopyrightText: 2023 Jonathan Frere
+--
+-- SPDX-License-Identifier: MPL-2.0
+INSERT INTO
+  task_chains (from_task_id, to_task_id, offset_days, assignee)
+VALUES
+  (?, ?, ?, ?) RETURNING task_chains.id
\ No newline at end of file
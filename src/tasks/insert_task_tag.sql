@@ -0,0 +1,7 @@
+-- SPDX-FileCopyrightText: 2023 Jonathan Frere
+--
+-- SPDX-License-Identifier: MPL-2.0
+INSERT
+OR IGNORE INTO task_tag_link (task_id, tag_id)
+VALUES
+  (?, ?)
\ No newline at end of file
@@ -2,11 +2,35 @@
 //
 // SPDX-License-Identifier: MPL-2.0
 
+mod attachments;
+mod caldav;
+mod calendar;
+mod points;
+mod recurrence;
+mod rotation;
 mod routes;
+mod season;
+mod stats;
 mod store;
-mod time;
+mod templates;
+pub(crate) mod time;
 mod types;
 
-pub use routes::routes;
-pub use store::{NewTask, TaskStore};
-pub use types::{Deadline, Routine, Task};
+pub use attachments::NewAttachment;
+pub use caldav::{caldav_routes, CALDAV_ROOT};
+pub use recurrence::{Recurrence, RecurrenceError};
+pub use routes::{calendar_routes, routes, stats_routes};
+pub use season::{ActiveWindow, ActiveWindowError};
+pub use stats::{PeriodCompletions, PersonStats, Punctuality, Stats, StatsPeriod, TaskStats};
+pub use store::{
+    CompletionDetails, ConfiguredTask, ExportedCompletion, ExportedTask, NewTag, NewTask,
+    TaskQuery, TaskSettings, TaskStore, TaskStoreError,
+};
+pub use templates::Template;
+pub use time::{Timezone, TimezoneError};
+pub use types::{
+    Absence, AbsenceId, Attachment, AttachmentId, Balance, CatchUp, Chain, ChainAssignee, ChainId,
+    ChecklistStep, CompletionId, Deadline, DueStatus, Feed, FeedId, ForecastEntry, HistoryEntry,
+    LedgerEntry, LedgerEntryId, LedgerKind, Rotation, Routine, SortOrder, StepId, Tag, TagId, Task,
+    TaskSort, Urgency,
+};
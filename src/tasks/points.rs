@@ -0,0 +1,81 @@
+// SPDX-FileCopyrightText: 2023 Jonathan Frere
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! Exports the points ledger as CSV, so that allowances can be worked out in
+//! a spreadsheet.
+
+use super::types::LedgerEntry;
+
+const HEADER: &str = "id,date,person,kind,points,task_id,description";
+
+/// Renders the given ledger entries as a CSV document with a header row.
+pub(super) fn render_csv(entries: &[LedgerEntry]) -> String {
+    let mut csv = format!("{HEADER}\r\n");
+    for entry in entries {
+        let task_id = entry
+            .task_id
+            .map(|task_id| task_id.to_string())
+            .unwrap_or_default();
+        let fields = [
+            entry.id.to_string(),
+            entry.entry_on.format("%Y-%m-%d").to_string(),
+            escape(&entry.person),
+            entry.kind.to_string(),
+            entry.points.to_string(),
+            task_id,
+            escape(entry.description.as_deref().unwrap_or_default()),
+        ];
+        csv.push_str(&fields.join(","));
+        csv.push_str("\r\n");
+    }
+    csv
+}
+
+/// Quotes a field if it contains anything that would otherwise end it early.
+fn escape(field: &str) -> String {
+    if field.contains(['"', ',', '\r', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+    use crate::tasks::types::{LedgerEntryId, LedgerKind, TaskId};
+
+    #[test]
+    fn renders_entries_and_quotes_awkward_descriptions() {
+        let entries = [
+            LedgerEntry {
+                id: LedgerEntryId::from(1),
+                person: "Alice".to_owned(),
+                entry_on: NaiveDate::from_ymd_opt(2023, 5, 1).unwrap(),
+                kind: LedgerKind::Completion,
+                points: 5,
+                task_id: Some(TaskId::from(3)),
+                description: None,
+            },
+            LedgerEntry {
+                id: LedgerEntryId::from(2),
+                person: "Alice".to_owned(),
+                entry_on: NaiveDate::from_ymd_opt(2023, 5, 2).unwrap(),
+                kind: LedgerKind::Redemption,
+                points: -4,
+                task_id: None,
+                description: Some("Cinema, \"the big one\"".to_owned()),
+            },
+        ];
+
+        assert_eq!(
+            render_csv(&entries),
+            "id,date,person,kind,points,task_id,description\r\n\
+             1,2023-05-01,Alice,Completion,5,3,\r\n\
+             2,2023-05-02,Alice,Redemption,-4,,\"Cinema, \"\"the big one\"\"\"\r\n"
+        );
+    }
+}
@@ -0,0 +1,539 @@
+// SPDX-FileCopyrightText: 2023 Jonathan Frere
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! A subset of the RFC 5545 recurrence rules (RRULE), enough to describe
+//! routines such as "every Monday and Thursday", "the first Saturday of the
+//! month" or "the 15th of each month".
+//!
+//! Supported parts are `FREQ` (`DAILY`, `WEEKLY`, `MONTHLY` or `YEARLY`),
+//! `INTERVAL`, `BYDAY` (with ordinals such as `1SA` or `-1FR` for monthly and
+//! yearly rules), `BYMONTHDAY`, `BYMONTH` and `WKST=MO`.  The start of the
+//! recurrence (`DTSTART`) is the day that the task starts on.
+
+use std::{fmt::Display, str::FromStr};
+
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+use sqlx::Sqlite;
+
+#[derive(thiserror::Error, Debug, PartialEq, Eq)]
+pub enum RecurrenceError {
+    #[error("recurrence rule is missing FREQ")]
+    MissingFrequency,
+    #[error("recurrence rule part {0} is not supported")]
+    Unsupported(String),
+    #[error("recurrence rule part {0} has an invalid value")]
+    InvalidValue(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recurrence {
+    frequency: Frequency,
+    interval: u32,
+    by_day: Vec<(Option<i8>, Weekday)>,
+    by_month_day: Vec<i8>,
+    by_month: Vec<u32>,
+}
+
+/// Gives up looking for the next occurrence after this many periods in a row
+/// without one, so that rules which can never match don't loop forever.
+const MAX_EMPTY_PERIODS: u32 = 5000;
+
+impl Recurrence {
+    /// Iterates over every occurrence on or after `start`, in order.
+    pub fn occurrences(&self, start: NaiveDate) -> impl Iterator<Item = NaiveDate> + '_ {
+        let mut period = 0;
+        let mut empty_periods = 0;
+        let mut pending = Vec::new();
+        std::iter::from_fn(move || {
+            while pending.is_empty() {
+                if empty_periods > MAX_EMPTY_PERIODS {
+                    return None;
+                }
+
+                pending = self.period_dates(start, period)?;
+                pending.retain(|date| *date >= start);
+                pending.reverse();
+                period += 1;
+                empty_periods = if pending.is_empty() {
+                    empty_periods + 1
+                } else {
+                    0
+                };
+            }
+
+            pending.pop()
+        })
+    }
+
+    /// All matching dates in the `period`th period (day, week, month or year,
+    /// depending on the frequency) counting from the one containing `start`.
+    fn period_dates(&self, start: NaiveDate, period: u32) -> Option<Vec<NaiveDate>> {
+        let step = i64::from(period) * i64::from(self.interval);
+        let candidates = match self.frequency {
+            Frequency::Daily => vec![start.checked_add_signed(Duration::days(step))?],
+            Frequency::Weekly => {
+                let monday = start - Duration::days(start.weekday().num_days_from_monday().into());
+                let monday = monday.checked_add_signed(Duration::weeks(step))?;
+                monday.iter_days().take(7).collect()
+            }
+            Frequency::Monthly => {
+                let months = i64::from(start.year()) * 12 + i64::from(start.month0()) + step;
+                let first = NaiveDate::from_ymd_opt(
+                    (months / 12).try_into().ok()?,
+                    (months % 12) as u32 + 1,
+                    1,
+                )?;
+                first
+                    .iter_days()
+                    .take_while(|date| date.month() == first.month())
+                    .collect()
+            }
+            Frequency::Yearly => {
+                let year = i64::from(start.year()) + step;
+                let first = NaiveDate::from_ymd_opt(year.try_into().ok()?, 1, 1)?;
+                first
+                    .iter_days()
+                    .take_while(|date| date.year() == first.year())
+                    .collect()
+            }
+        };
+
+        Some(
+            candidates
+                .into_iter()
+                .filter(|date| self.matches(*date, start))
+                .collect(),
+        )
+    }
+
+    fn matches(&self, date: NaiveDate, start: NaiveDate) -> bool {
+        if !self.by_month.is_empty() && !self.by_month.contains(&date.month()) {
+            return false;
+        }
+
+        if !self.by_month_day.is_empty()
+            && !self
+                .by_month_day
+                .iter()
+                .any(|day| month_day_matches(date, *day))
+        {
+            return false;
+        }
+
+        if !self.by_day.is_empty()
+            && !self.by_day.iter().any(|(ordinal, weekday)| {
+                date.weekday() == *weekday
+                    && ordinal.is_none_or(|ordinal| self.ordinal_matches(date, ordinal))
+            })
+        {
+            return false;
+        }
+
+        // without any explicit BY* parts, the rule repeats on the same
+        // weekday/day/date as the start
+        match self.frequency {
+            Frequency::Daily => true,
+            Frequency::Weekly => !self.by_day.is_empty() || date.weekday() == start.weekday(),
+            Frequency::Monthly => {
+                !self.by_day.is_empty()
+                    || !self.by_month_day.is_empty()
+                    || date.day() == start.day()
+            }
+            Frequency::Yearly => {
+                !self.by_day.is_empty()
+                    || !self.by_month_day.is_empty()
+                    || (date.day() == start.day()
+                        && (!self.by_month.is_empty() || date.month() == start.month()))
+            }
+        }
+    }
+
+    /// Checks e.g. whether a date is the 1st or the last (-1) of its weekday
+    /// in the month (or the year, for yearly rules without a month).
+    fn ordinal_matches(&self, date: NaiveDate, ordinal: i8) -> bool {
+        let (position, length) = if self.frequency == Frequency::Yearly && self.by_month.is_empty()
+        {
+            (date.ordinal(), days_in_year(date))
+        } else {
+            (date.day(), days_in_month(date))
+        };
+
+        if ordinal > 0 {
+            (position - 1) / 7 + 1 == ordinal as u32
+        } else {
+            (length - position) / 7 + 1 == ordinal.unsigned_abs() as u32
+        }
+    }
+}
+
+fn month_day_matches(date: NaiveDate, day: i8) -> bool {
+    if day > 0 {
+        date.day() == day as u32
+    } else {
+        days_in_month(date) + 1 - day.unsigned_abs() as u32 == date.day()
+    }
+}
+
+fn days_in_month(date: NaiveDate) -> u32 {
+    let (year, month) = if date.month() == 12 {
+        (date.year() + 1, 1)
+    } else {
+        (date.year(), date.month() + 1)
+    };
+    NaiveDate::from_ymd_opt(year, month, 1)
+        .map(|next| next.pred_opt().unwrap().day())
+        .unwrap_or(31)
+}
+
+fn days_in_year(date: NaiveDate) -> u32 {
+    NaiveDate::from_ymd_opt(date.year(), 12, 31)
+        .unwrap()
+        .ordinal()
+}
+
+fn parse_weekday(value: &str) -> Option<Weekday> {
+    Some(match value {
+        "MO" => Weekday::Mon,
+        "TU" => Weekday::Tue,
+        "WE" => Weekday::Wed,
+        "TH" => Weekday::Thu,
+        "FR" => Weekday::Fri,
+        "SA" => Weekday::Sat,
+        "SU" => Weekday::Sun,
+        _ => None?,
+    })
+}
+
+fn format_weekday(weekday: Weekday) -> &'static str {
+    match weekday {
+        Weekday::Mon => "MO",
+        Weekday::Tue => "TU",
+        Weekday::Wed => "WE",
+        Weekday::Thu => "TH",
+        Weekday::Fri => "FR",
+        Weekday::Sat => "SA",
+        Weekday::Sun => "SU",
+    }
+}
+
+fn parse_list<T>(
+    key: &str,
+    value: &str,
+    parse: impl Fn(&str) -> Option<T>,
+) -> Result<Vec<T>, RecurrenceError> {
+    value
+        .split(',')
+        .map(|item| parse(item).ok_or_else(|| RecurrenceError::InvalidValue(key.to_owned())))
+        .collect()
+}
+
+impl FromStr for Recurrence {
+    type Err = RecurrenceError;
+
+    fn from_str(rule: &str) -> Result<Self, Self::Err> {
+        let rule = rule.trim().to_ascii_uppercase();
+        let rule = rule.strip_prefix("RRULE:").unwrap_or(&rule);
+
+        let mut frequency = None;
+        let mut recurrence = Recurrence {
+            frequency: Frequency::Daily,
+            interval: 1,
+            by_day: Vec::new(),
+            by_month_day: Vec::new(),
+            by_month: Vec::new(),
+        };
+
+        for part in rule.split(';').filter(|part| !part.is_empty()) {
+            let (key, value) = part
+                .split_once('=')
+                .ok_or_else(|| RecurrenceError::InvalidValue(part.to_owned()))?;
+            match key {
+                "FREQ" => {
+                    frequency = Some(match value {
+                        "DAILY" => Frequency::Daily,
+                        "WEEKLY" => Frequency::Weekly,
+                        "MONTHLY" => Frequency::Monthly,
+                        "YEARLY" => Frequency::Yearly,
+                        _ => Err(RecurrenceError::Unsupported(part.to_owned()))?,
+                    })
+                }
+                "INTERVAL" => {
+                    recurrence.interval = value
+                        .parse()
+                        .ok()
+                        .filter(|interval| (1..=1000).contains(interval))
+                        .ok_or_else(|| RecurrenceError::InvalidValue(key.to_owned()))?
+                }
+                "BYDAY" => {
+                    recurrence.by_day = parse_list(key, value, |item| {
+                        let (ordinal, weekday) = item.split_at(item.len().checked_sub(2)?);
+                        let ordinal = match ordinal {
+                            "" => None,
+                            ordinal => Some(
+                                ordinal
+                                    .parse::<i8>()
+                                    .ok()
+                                    .filter(|o| *o != 0 && (-53..=53).contains(o))?,
+                            ),
+                        };
+                        Some((ordinal, parse_weekday(weekday)?))
+                    })?
+                }
+                "BYMONTHDAY" => {
+                    recurrence.by_month_day = parse_list(key, value, |item| {
+                        item.parse::<i8>()
+                            .ok()
+                            .filter(|day| *day != 0 && (-31..=31).contains(day))
+                    })?
+                }
+                "BYMONTH" => {
+                    recurrence.by_month = parse_list(key, value, |item| {
+                        item.parse::<u32>()
+                            .ok()
+                            .filter(|month| (1..=12).contains(month))
+                    })?
+                }
+                "WKST" if value == "MO" => {}
+                _ => Err(RecurrenceError::Unsupported(key.to_owned()))?,
+            }
+        }
+
+        recurrence.frequency = frequency.ok_or(RecurrenceError::MissingFrequency)?;
+        let has_ordinals = recurrence.by_day.iter().any(|(o, _)| o.is_some());
+        if has_ordinals && matches!(recurrence.frequency, Frequency::Daily | Frequency::Weekly) {
+            Err(RecurrenceError::InvalidValue("BYDAY".to_owned()))?;
+        }
+
+        Ok(recurrence)
+    }
+}
+
+impl Display for Recurrence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let frequency = match self.frequency {
+            Frequency::Daily => "DAILY",
+            Frequency::Weekly => "WEEKLY",
+            Frequency::Monthly => "MONTHLY",
+            Frequency::Yearly => "YEARLY",
+        };
+        write!(f, "FREQ={frequency}")?;
+        if self.interval != 1 {
+            write!(f, ";INTERVAL={}", self.interval)?;
+        }
+        if !self.by_day.is_empty() {
+            let days = self
+                .by_day
+                .iter()
+                .map(|(ordinal, weekday)| match ordinal {
+                    Some(ordinal) => format!("{ordinal}{}", format_weekday(*weekday)),
+                    None => format_weekday(*weekday).to_owned(),
+                })
+                .collect::<Vec<_>>();
+            write!(f, ";BYDAY={}", days.join(","))?;
+        }
+        if !self.by_month_day.is_empty() {
+            let days = self
+                .by_month_day
+                .iter()
+                .map(|day| day.to_string())
+                .collect::<Vec<_>>();
+            write!(f, ";BYMONTHDAY={}", days.join(","))?;
+        }
+        if !self.by_month.is_empty() {
+            let months = self
+                .by_month
+                .iter()
+                .map(|month| month.to_string())
+                .collect::<Vec<_>>();
+            write!(f, ";BYMONTH={}", months.join(","))?;
+        }
+        Ok(())
+    }
+}
+
+impl serde::Serialize for Recurrence {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for Recurrence {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+impl sqlx::Type<Sqlite> for Recurrence {
+    fn type_info() -> <Sqlite as sqlx::Database>::TypeInfo {
+        <String as sqlx::Type<Sqlite>>::type_info()
+    }
+}
+
+impl<'q> sqlx::Encode<'q, Sqlite> for Recurrence {
+    fn encode_by_ref(
+        &self,
+        buf: &mut <Sqlite as sqlx::database::HasArguments<'q>>::ArgumentBuffer,
+    ) -> sqlx::encode::IsNull {
+        <String as sqlx::Encode<Sqlite>>::encode(self.to_string(), buf)
+    }
+}
+
+impl<'r> sqlx::Decode<'r, Sqlite> for Recurrence {
+    fn decode(
+        value: <Sqlite as sqlx::database::HasValueRef<'r>>::ValueRef,
+    ) -> Result<Self, sqlx::error::BoxDynError> {
+        Ok(<&str as sqlx::Decode<Sqlite>>::decode(value)?.parse()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn first_occurrences(rule: &str, start: NaiveDate, count: usize) -> Vec<NaiveDate> {
+        rule.parse::<Recurrence>()
+            .unwrap()
+            .occurrences(start)
+            .take(count)
+            .collect()
+    }
+
+    #[test]
+    fn parses_and_formats_rules() {
+        for rule in [
+            "FREQ=DAILY",
+            "FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,TH",
+            "FREQ=MONTHLY;BYDAY=1SA",
+            "FREQ=MONTHLY;BYMONTHDAY=15,-1",
+            "FREQ=YEARLY;BYDAY=-1FR;BYMONTH=11",
+        ] {
+            assert_eq!(rule.parse::<Recurrence>().unwrap().to_string(), rule);
+        }
+
+        assert_eq!(
+            "rrule:freq=weekly;wkst=mo;byday=mo"
+                .parse::<Recurrence>()
+                .unwrap()
+                .to_string(),
+            "FREQ=WEEKLY;BYDAY=MO"
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_and_unsupported_rules() {
+        assert_eq!(
+            "BYDAY=MO".parse::<Recurrence>(),
+            Err(RecurrenceError::MissingFrequency)
+        );
+        assert_eq!(
+            "FREQ=HOURLY".parse::<Recurrence>(),
+            Err(RecurrenceError::Unsupported("FREQ=HOURLY".to_owned()))
+        );
+        assert_eq!(
+            "FREQ=DAILY;COUNT=3".parse::<Recurrence>(),
+            Err(RecurrenceError::Unsupported("COUNT".to_owned()))
+        );
+        assert_eq!(
+            "FREQ=WEEKLY;BYDAY=XX".parse::<Recurrence>(),
+            Err(RecurrenceError::InvalidValue("BYDAY".to_owned()))
+        );
+        assert_eq!(
+            "FREQ=WEEKLY;BYDAY=1MO".parse::<Recurrence>(),
+            Err(RecurrenceError::InvalidValue("BYDAY".to_owned()))
+        );
+        assert_eq!(
+            "FREQ=MONTHLY;BYMONTHDAY=32".parse::<Recurrence>(),
+            Err(RecurrenceError::InvalidValue("BYMONTHDAY".to_owned()))
+        );
+        assert_eq!(
+            "FREQ=DAILY;INTERVAL=0".parse::<Recurrence>(),
+            Err(RecurrenceError::InvalidValue("INTERVAL".to_owned()))
+        );
+    }
+
+    #[test]
+    fn weekly_rules_repeat_on_the_given_weekdays() {
+        // 2023-01-04 is a Wednesday
+        assert_eq!(
+            first_occurrences("FREQ=WEEKLY;BYDAY=MO,TH", date(2023, 1, 4), 4),
+            vec![
+                date(2023, 1, 5),
+                date(2023, 1, 9),
+                date(2023, 1, 12),
+                date(2023, 1, 16)
+            ]
+        );
+        assert_eq!(
+            first_occurrences("FREQ=WEEKLY", date(2023, 1, 4), 2),
+            vec![date(2023, 1, 4), date(2023, 1, 11)]
+        );
+        assert_eq!(
+            first_occurrences("FREQ=WEEKLY;INTERVAL=2;BYDAY=MO", date(2023, 1, 4), 2),
+            vec![date(2023, 1, 16), date(2023, 1, 30)]
+        );
+    }
+
+    #[test]
+    fn monthly_rules_can_use_weekday_ordinals() {
+        assert_eq!(
+            first_occurrences("FREQ=MONTHLY;BYDAY=1SA", date(2023, 1, 10), 3),
+            vec![date(2023, 2, 4), date(2023, 3, 4), date(2023, 4, 1)]
+        );
+        assert_eq!(
+            first_occurrences("FREQ=MONTHLY;BYDAY=-1FR", date(2023, 1, 1), 2),
+            vec![date(2023, 1, 27), date(2023, 2, 24)]
+        );
+    }
+
+    #[test]
+    fn monthly_rules_can_use_days_of_the_month() {
+        assert_eq!(
+            first_occurrences("FREQ=MONTHLY;BYMONTHDAY=15", date(2023, 1, 20), 2),
+            vec![date(2023, 2, 15), date(2023, 3, 15)]
+        );
+        assert_eq!(
+            first_occurrences("FREQ=MONTHLY;BYMONTHDAY=-1", date(2023, 1, 1), 3),
+            vec![date(2023, 1, 31), date(2023, 2, 28), date(2023, 3, 31)]
+        );
+        // months without a 31st are skipped
+        assert_eq!(
+            first_occurrences("FREQ=MONTHLY", date(2023, 1, 31), 2),
+            vec![date(2023, 1, 31), date(2023, 3, 31)]
+        );
+    }
+
+    #[test]
+    fn yearly_rules_repeat_on_the_same_date() {
+        assert_eq!(
+            first_occurrences("FREQ=YEARLY", date(2023, 3, 1), 2),
+            vec![date(2023, 3, 1), date(2024, 3, 1)]
+        );
+        assert_eq!(
+            first_occurrences("FREQ=YEARLY;BYMONTH=11;BYDAY=1SA", date(2023, 3, 1), 2),
+            vec![date(2023, 11, 4), date(2024, 11, 2)]
+        );
+    }
+
+    #[test]
+    fn rules_that_never_match_end_the_iterator() {
+        assert_eq!(
+            first_occurrences("FREQ=YEARLY;BYMONTH=2;BYMONTHDAY=30", date(2023, 1, 1), 1),
+            vec![]
+        );
+    }
+}
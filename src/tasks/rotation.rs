@@ -0,0 +1,363 @@
+// SPDX-FileCopyrightText: 2023 Jonathan Frere
+//
+// SPDX-License-Identifier: MPL-2.0
+
+use chrono::{Duration, NaiveDate};
+
+use super::types::{Completion, Rotation};
+
+/// Picks the participant who should do the task next, skipping anyone who
+/// is not available.  Returns `None` if nobody is available.
+///
+/// `history` contains the non-initial completions of the task, oldest first.
+/// `due` is when the task is next due, which is where the window of
+/// completions counted by [`Rotation::FewestCompletions`] ends (or the last
+/// completion, if the task isn't due).  Where a strategy leaves several
+/// participants tied, the tie is broken in round-robin order, starting from
+/// the person after `last_completed_by`.
+pub fn next_assignee<'a>(
+    rotation: &Rotation,
+    participants: &'a [String],
+    last_completed_by: &str,
+    history: &[Completion],
+    due: Option<NaiveDate>,
+    is_available: impl Fn(&str) -> bool,
+) -> Option<&'a str> {
+    let mut candidates =
+        in_turn_order(participants, last_completed_by).filter(|person| is_available(person));
+
+    let next = match rotation {
+        Rotation::RoundRobin => candidates.next(),
+        Rotation::LeastRecentlyDone => {
+            candidates.min_by_key(|person| last_done_by(history, person))
+        }
+        Rotation::FewestCompletions { days } => {
+            let window_end = due.or(history.last().map(|completion| completion.completed_on));
+            let window_start = window_end.map(|end| end - Duration::days((*days).into()));
+            let in_window = |date: NaiveDate| Some(date) > window_start && Some(date) <= window_end;
+            candidates.min_by_key(|person| {
+                let count = history
+                    .iter()
+                    .filter(|c| in_window(c.completed_on) && c.was_done_by(person))
+                    .count();
+                (count, last_done_by(history, person))
+            })
+        }
+        Rotation::FixedOwner(owner) => participants
+            .iter()
+            .find(|person| person.to_lowercase() == owner.to_lowercase())
+            .filter(|person| is_available(person))
+            .or_else(|| candidates.next()),
+        Rotation::SeededRandom { seed } => {
+            let roll = splitmix64(u64::from(*seed) ^ history.len() as u64);
+            let picked = (roll % participants.len() as u64) as usize;
+            // if the picked person is away, fall back to whoever comes after them
+            participants[picked..]
+                .iter()
+                .chain(&participants[..picked])
+                .find(|person| is_available(person))
+        }
+    };
+
+    next.map(|person| person.as_str())
+}
+
+pub fn round_robin<'a>(participants: &'a [String], last_completed_by: &str) -> &'a str {
+    let mut participants_iter = participants.iter();
+    while let Some(person) = participants_iter.next() {
+        if person == last_completed_by {
+            return participants_iter.next().unwrap_or(&participants[0]);
+        }
+    }
+
+    // whoever did the task last is no longer a participant, so start again from the top
+    &participants[0]
+}
+
+/// Of several people who did a task together, picks the one furthest along
+/// in the turn order (counting from whoever's turn it was), so that the
+/// rotation carries on after all of them.  Anyone who isn't a participant is
+/// only picked if none of them are.
+pub fn furthest_along<'a>(
+    participants: &[String],
+    last_completed_by: &str,
+    people: &'a [String],
+) -> Option<&'a str> {
+    let order = in_turn_order(participants, last_completed_by).collect::<Vec<_>>();
+    people
+        .iter()
+        .max_by_key(|person| {
+            order
+                .iter()
+                .position(|participant| participant.to_lowercase() == person.to_lowercase())
+        })
+        .map(|person| person.as_str())
+}
+
+/// Iterates over all participants, starting with the one whose turn it would
+/// be under a round-robin rotation.
+fn in_turn_order<'a>(
+    participants: &'a [String],
+    last_completed_by: &str,
+) -> impl Iterator<Item = &'a String> {
+    let next = round_robin(participants, last_completed_by);
+    let start = participants.iter().position(|p| p == next).unwrap();
+    participants[start..].iter().chain(&participants[..start])
+}
+
+fn last_done_by(history: &[Completion], person: &str) -> Option<NaiveDate> {
+    history
+        .iter()
+        .filter(|c| c.was_done_by(person))
+        .map(|c| c.completed_on)
+        .max()
+}
+
+// See https://prng.di.unimi.it/splitmix64.c
+fn splitmix64(state: u64) -> u64 {
+    let mut z = state.wrapping_add(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn people(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    fn assignee<'a>(
+        rotation: &Rotation,
+        participants: &'a [String],
+        last_completed_by: &str,
+        history: &[Completion],
+    ) -> &'a str {
+        next_assignee(
+            rotation,
+            participants,
+            last_completed_by,
+            history,
+            None,
+            |_| true,
+        )
+        .unwrap()
+    }
+
+    fn done(by: &str, day: u32) -> Completion {
+        Completion {
+            completed_by: by.to_owned(),
+            helpers: Vec::new(),
+            completed_on: NaiveDate::from_ymd_opt(2020, 1, day).unwrap(),
+        }
+    }
+
+    #[test]
+    fn round_robin_picks_the_person_after_the_last_one() {
+        let participants = people(&["arthur", "bob", "claire"]);
+        let rotation = Rotation::RoundRobin;
+        assert_eq!(assignee(&rotation, &participants, "arthur", &[]), "bob");
+        assert_eq!(assignee(&rotation, &participants, "claire", &[]), "arthur");
+    }
+
+    #[test]
+    fn joint_completions_count_as_done_by_whoever_is_furthest_along() {
+        let participants = people(&["arthur", "bob", "claire", "dan"]);
+        // when it is bob's turn, claire comes after him
+        assert_eq!(
+            furthest_along(&participants, "arthur", &people(&["claire", "bob"])),
+            Some("claire")
+        );
+        // but when it is claire's turn, arthur comes after her
+        assert_eq!(
+            furthest_along(&participants, "bob", &people(&["claire", "arthur"])),
+            Some("arthur")
+        );
+        assert_eq!(
+            furthest_along(&participants, "arthur", &people(&["eve", "Bob"])),
+            Some("Bob")
+        );
+        assert_eq!(
+            furthest_along(&participants, "arthur", &people(&["eve"])),
+            Some("eve")
+        );
+        assert_eq!(furthest_along(&participants, "arthur", &[]), None);
+    }
+
+    #[test]
+    fn round_robin_starts_from_the_top_if_the_last_person_has_left() {
+        let participants = people(&["arthur", "bob", "claire"]);
+        assert_eq!(
+            assignee(&Rotation::RoundRobin, &participants, "dan", &[]),
+            "arthur"
+        );
+    }
+
+    #[test]
+    fn least_recently_done_prefers_people_who_have_never_done_the_task() {
+        let participants = people(&["arthur", "bob", "claire"]);
+        let history = [done("bob", 1), done("arthur", 2)];
+        assert_eq!(
+            assignee(
+                &Rotation::LeastRecentlyDone,
+                &participants,
+                "arthur",
+                &history
+            ),
+            "claire"
+        );
+    }
+
+    #[test]
+    fn least_recently_done_corrects_for_out_of_turn_completions() {
+        let participants = people(&["arthur", "bob", "claire"]);
+        // claire jumped in out of turn, so bob is still owed a go
+        let history = [
+            done("bob", 1),
+            done("claire", 2),
+            done("arthur", 3),
+            done("claire", 4),
+        ];
+        assert_eq!(
+            assignee(
+                &Rotation::LeastRecentlyDone,
+                &participants,
+                "claire",
+                &history
+            ),
+            "bob"
+        );
+        assert_eq!(
+            assignee(&Rotation::RoundRobin, &participants, "claire", &history),
+            "arthur"
+        );
+    }
+
+    #[test]
+    fn least_recently_done_breaks_ties_in_turn_order() {
+        let participants = people(&["arthur", "bob", "claire"]);
+        assert_eq!(
+            assignee(&Rotation::LeastRecentlyDone, &participants, "bob", &[]),
+            "claire"
+        );
+    }
+
+    #[test]
+    fn fewest_completions_only_counts_completions_within_the_window() {
+        let participants = people(&["arthur", "bob"]);
+        let history = [
+            done("bob", 1),
+            done("bob", 2),
+            done("arthur", 20),
+            done("arthur", 21),
+            done("bob", 22),
+        ];
+        let rotation = Rotation::FewestCompletions { days: 7 };
+        assert_eq!(assignee(&rotation, &participants, "bob", &history), "bob");
+
+        let rotation = Rotation::FewestCompletions { days: 30 };
+        assert_eq!(
+            assignee(&rotation, &participants, "bob", &history),
+            "arthur"
+        );
+    }
+
+    #[test]
+    fn fewest_completions_counts_back_from_the_due_date() {
+        let participants = people(&["arthur", "bob"]);
+        let history = [done("arthur", 1), done("arthur", 2), done("bob", 9)];
+        let rotation = Rotation::FewestCompletions { days: 10 };
+        // counting back from the last completion, arthur has done it more often
+        assert_eq!(assignee(&rotation, &participants, "bob", &history), "bob");
+
+        // but by the time it is due, only bob's go is within the window
+        let due = NaiveDate::from_ymd_opt(2020, 1, 15);
+        assert_eq!(
+            next_assignee(&rotation, &participants, "bob", &history, due, |_| true),
+            Some("arthur")
+        );
+    }
+
+    #[test]
+    fn fixed_owner_always_picks_the_owner() {
+        let participants = people(&["arthur", "bob"]);
+        let rotation = Rotation::FixedOwner("Bob".to_owned());
+        assert_eq!(assignee(&rotation, &participants, "arthur", &[]), "bob");
+        assert_eq!(assignee(&rotation, &participants, "bob", &[]), "bob");
+    }
+
+    #[test]
+    fn seeded_random_is_reproducible() {
+        let participants = people(&["arthur", "bob", "claire", "dan"]);
+        let history = [done("bob", 1), done("claire", 2)];
+        let rotation = Rotation::SeededRandom { seed: 1234 };
+        let first = assignee(&rotation, &participants, "claire", &history);
+        for _ in 0..10 {
+            assert_eq!(
+                assignee(&rotation, &participants, "claire", &history),
+                first
+            );
+        }
+    }
+
+    #[test]
+    fn seeded_random_picks_different_people_over_time() {
+        let participants = people(&["arthur", "bob", "claire", "dan"]);
+        let rotation = Rotation::SeededRandom { seed: 1234 };
+        let mut history = Vec::new();
+        let mut picked = std::collections::HashSet::new();
+        for day in 1..=20 {
+            let next = assignee(&rotation, &participants, "arthur", &history);
+            picked.insert(next.to_owned());
+            history.push(done(next, day));
+        }
+        assert!(picked.len() > 1);
+    }
+
+    #[test]
+    fn unavailable_people_are_skipped() {
+        let participants = people(&["arthur", "bob", "claire"]);
+        let history = [done("arthur", 1), done("bob", 2)];
+        let not_claire = |person: &str| person != "claire";
+        let rotations = [
+            Rotation::RoundRobin,
+            Rotation::LeastRecentlyDone,
+            Rotation::FewestCompletions { days: 30 },
+            Rotation::FixedOwner("claire".to_owned()),
+        ];
+        for rotation in rotations {
+            assert_eq!(
+                next_assignee(&rotation, &participants, "bob", &history, None, not_claire),
+                Some("arthur")
+            );
+        }
+
+        let rotation = Rotation::SeededRandom { seed: 1234 };
+        for day in 1..=20 {
+            let history = vec![done("arthur", 1); day];
+            let next = next_assignee(&rotation, &participants, "bob", &history, None, not_claire);
+            assert_ne!(next, Some("claire"));
+            assert!(next.is_some());
+        }
+    }
+
+    #[test]
+    fn nobody_is_assigned_when_everyone_is_unavailable() {
+        let participants = people(&["arthur", "bob"]);
+        let rotations = [
+            Rotation::RoundRobin,
+            Rotation::LeastRecentlyDone,
+            Rotation::FewestCompletions { days: 30 },
+            Rotation::FixedOwner("bob".to_owned()),
+            Rotation::SeededRandom { seed: 1 },
+        ];
+        for rotation in rotations {
+            assert_eq!(
+                next_assignee(&rotation, &participants, "bob", &[], None, |_| false),
+                None
+            );
+        }
+    }
+}
@@ -3,76 +3,831 @@
 // SPDX-License-Identifier: MPL-2.0
 
 use axum::{
-    extract::{Path, Query, State},
-    http::StatusCode,
-    response::IntoResponse,
-    routing::{get, post},
+    body::Body,
+    extract::{
+        multipart, DefaultBodyLimit, FromRequest, FromRequestParts, Multipart, Path, Query, State,
+    },
+    http::{header, request::Parts, Request, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{delete, get, post},
     Json, Router,
 };
 use chrono::NaiveDate;
 use sqlx::SqlitePool;
 
-use crate::translations::ExtractLanguage;
+use crate::{auth::HouseholdId, translations::ExtractLanguage};
 
 use super::{
+    attachments::{NewAttachment, MAX_UPLOAD_SIZE},
+    season::ActiveWindow,
+    stats::{Stats, StatsPeriod},
     store::TaskStoreError,
-    time::today,
-    types::{Task, TaskId},
-    TaskStore,
+    templates::Template,
+    time::Timezone,
+    types::{
+        Absence, AbsenceId, AttachmentId, Balance, CatchUp, Chain, ChainAssignee, ChainId, Feed,
+        FeedId, ForecastEntry, HistoryEntry, LedgerEntry, StepId, Tag, TagId, Task, TaskId,
+        Urgency,
+    },
+    CompletionDetails, NewTag, NewTask, TaskQuery, TaskStore,
 };
 
 impl IntoResponse for TaskStoreError {
     fn into_response(self) -> axum::response::Response {
         match self {
-            TaskStoreError::DbError(_) => {
+            TaskStoreError::DbError(_) | TaskStoreError::FileError(_) => {
                 (StatusCode::INTERNAL_SERVER_ERROR, self.to_string()).into_response()
             }
             TaskStoreError::UnknownTaskName(_)
             | TaskStoreError::UnknownTaskId(_)
-            | TaskStoreError::PersonDoesNotExist(_) => {
+            | TaskStoreError::PersonDoesNotExist(_)
+            | TaskStoreError::PersonNotParticipant(_)
+            | TaskStoreError::CompletionInFuture(_)
+            | TaskStoreError::CompletionBeforeStart(_)
+            | TaskStoreError::CompletionTooOld(_)
+            | TaskStoreError::RecurrenceMismatch
+            | TaskStoreError::RecurrenceNeverOccurs
+            | TaskStoreError::NoParticipants
+            | TaskStoreError::TaskNotClaimable(_)
+            | TaskStoreError::UnknownTagId(_)
+            | TaskStoreError::NoTagNames
+            | TaskStoreError::UnknownStep(_)
+            | TaskStoreError::UnknownAbsenceId(_)
+            | TaskStoreError::AbsenceEndsBeforeStart
+            | TaskStoreError::ParticipantOrderMismatch
+            | TaskStoreError::ActiveWindowMismatch
+            | TaskStoreError::UnknownChainId(_)
+            | TaskStoreError::ChainTargetNotTriggered(_)
+            | TaskStoreError::ForecastTooLong
+            | TaskStoreError::UnknownFeedId(_)
+            | TaskStoreError::UnknownAttachmentId(_)
+            | TaskStoreError::NotAnImage(_)
+            | TaskStoreError::TooManyAttachments
+            | TaskStoreError::NotEnoughPoints(_)
+            | TaskStoreError::InvalidRedemption(_)
+            | TaskStoreError::UnknownLedgerEntryId(_)
+            | TaskStoreError::UnknownTemplate(_)
+            | TaskStoreError::NoInitialCompletion => {
                 (StatusCode::BAD_REQUEST, self.to_string()).into_response()
             }
+            TaskStoreError::AttachmentTooLarge => {
+                (StatusCode::PAYLOAD_TOO_LARGE, self.to_string()).into_response()
+            }
+            TaskStoreError::UnknownFeedSecret => {
+                (StatusCode::NOT_FOUND, self.to_string()).into_response()
+            }
         }
     }
 }
 
+/// Handlers are given a store for the household that the request is for, which
+/// the login middleware has worked out.
+#[axum::async_trait]
+impl FromRequestParts<SqlitePool> for TaskStore {
+    type Rejection = StatusCode;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        conn: &SqlitePool,
+    ) -> Result<Self, Self::Rejection> {
+        let household = parts
+            .extensions
+            .get::<HouseholdId>()
+            .copied()
+            .ok_or_else(|| {
+                tracing::error!("No household was chosen for a request that needs one");
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?;
+        Ok(TaskStore::new(conn.clone(), household))
+    }
+}
+
 async fn list_all_tasks(
-    State(store): State<TaskStore>,
+    Query(query): Query<TaskQuery>,
+    store: TaskStore,
     ExtractLanguage(language): ExtractLanguage,
 ) -> Result<Json<Vec<Task>>, TaskStoreError> {
-    store.tasks(&language).await.map(Json)
+    store.find_tasks(&query, &language).await.map(Json)
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct ForecastQuery {
+    #[serde(default = "default_forecast_weeks")]
+    weeks: u16,
+}
+
+fn default_forecast_weeks() -> u16 {
+    4
+}
+
+async fn forecast(
+    Query(query): Query<ForecastQuery>,
+    store: TaskStore,
+    ExtractLanguage(language): ExtractLanguage,
+) -> Result<Json<Vec<ForecastEntry>>, TaskStoreError> {
+    store.forecast(query.weeks, &language).await.map(Json)
 }
 
 async fn tasks_for_person(
     Path(person): Path<String>,
-    State(store): State<TaskStore>,
+    store: TaskStore,
     ExtractLanguage(language): ExtractLanguage,
 ) -> Result<Json<Vec<Task>>, TaskStoreError> {
     store.tasks_for(&person, &language).await.map(Json)
 }
 
+#[derive(Debug, serde::Deserialize, serde::Serialize)]
+struct TimezoneBody {
+    timezone: Option<Timezone>,
+}
+
+async fn household_timezone(store: TaskStore) -> Result<Json<TimezoneBody>, TaskStoreError> {
+    let timezone = store.household_timezone().await?;
+    Ok(Json(TimezoneBody { timezone }))
+}
+
+async fn set_household_timezone(
+    store: TaskStore,
+    Json(body): Json<TimezoneBody>,
+) -> Result<Json<TimezoneBody>, TaskStoreError> {
+    store.set_household_timezone(body.timezone).await?;
+    Ok(Json(body))
+}
+
+#[derive(Debug, serde::Deserialize, serde::Serialize)]
+struct BackdateLimitBody {
+    /// Leave out for no limit
+    days: Option<u16>,
+}
+
+async fn backdate_limit(store: TaskStore) -> Result<Json<BackdateLimitBody>, TaskStoreError> {
+    let days = store.backdate_limit().await?;
+    Ok(Json(BackdateLimitBody { days }))
+}
+
+async fn set_backdate_limit(
+    store: TaskStore,
+    Json(body): Json<BackdateLimitBody>,
+) -> Result<Json<BackdateLimitBody>, TaskStoreError> {
+    store.set_backdate_limit(body.days).await?;
+    Ok(Json(body))
+}
+
+async fn person_timezone(
+    Path(person): Path<String>,
+    store: TaskStore,
+) -> Result<Json<TimezoneBody>, TaskStoreError> {
+    let timezone = store.timezone_for(Some(&person)).await?;
+    Ok(Json(TimezoneBody { timezone }))
+}
+
+async fn set_person_timezone(
+    Path(person): Path<String>,
+    store: TaskStore,
+    Json(body): Json<TimezoneBody>,
+) -> Result<Json<TimezoneBody>, TaskStoreError> {
+    store.set_person_timezone(&person, body.timezone).await?;
+    person_timezone(Path(person), store).await
+}
+
+async fn list_absences(
+    Path(person): Path<String>,
+    store: TaskStore,
+) -> Result<Json<Vec<Absence>>, TaskStoreError> {
+    store.absences(&person).await.map(Json)
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct NewAbsence {
+    starts_on: NaiveDate,
+    ends_on: NaiveDate,
+}
+
+async fn add_absence(
+    Path(person): Path<String>,
+    store: TaskStore,
+    Json(absence): Json<NewAbsence>,
+) -> Result<Json<Absence>, TaskStoreError> {
+    let absence_id = store
+        .add_absence(&person, absence.starts_on, absence.ends_on)
+        .await?;
+    Ok(Json(store.absence(absence_id).await?))
+}
+
+async fn remove_absence(
+    Path(absence_id): Path<AbsenceId>,
+    store: TaskStore,
+) -> Result<StatusCode, TaskStoreError> {
+    store.remove_absence(absence_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
 #[derive(Debug, serde::Deserialize)]
 struct MarkTaskDoneQuery {
     by: String,
     on: Option<NaiveDate>,
 }
 
+#[derive(Debug, Default, serde::Deserialize)]
+struct MarkTaskDoneBody {
+    steps_done: Option<Vec<StepId>>,
+    #[serde(default)]
+    helpers: Vec<String>,
+    note: Option<String>,
+}
+
+/// What can be sent along with a completion: either JSON, or a multipart form
+/// with `steps_done` and `helpers` fields (one per step or person), a `note`
+/// field and any number of image `attachment` fields.
+#[derive(Debug, Default)]
+struct CompletionUpload {
+    steps_done: Option<Vec<StepId>>,
+    details: CompletionDetails,
+}
+
+fn bad_upload(error: multipart::MultipartError) -> Response {
+    (StatusCode::BAD_REQUEST, error.to_string()).into_response()
+}
+
+#[axum::async_trait]
+impl<S: Send + Sync> FromRequest<S, Body> for CompletionUpload {
+    type Rejection = Response;
+
+    async fn from_request(request: Request<Body>, state: &S) -> Result<Self, Self::Rejection> {
+        let content_type = request
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|h| h.to_str().ok())
+            .unwrap_or_default();
+
+        if content_type.starts_with("multipart/form-data") {
+            let mut multipart = Multipart::from_request(request, state)
+                .await
+                .map_err(IntoResponse::into_response)?;
+            let mut upload = Self::default();
+            while let Some(field) = multipart.next_field().await.map_err(bad_upload)? {
+                match field.name().unwrap_or_default() {
+                    "steps_done" => {
+                        let step = field
+                            .text()
+                            .await
+                            .map_err(bad_upload)?
+                            .parse::<i32>()
+                            .map_err(|_| {
+                                (StatusCode::BAD_REQUEST, "steps must be numbers").into_response()
+                            })?;
+                        upload
+                            .steps_done
+                            .get_or_insert_with(Vec::new)
+                            .push(step.into());
+                    }
+                    "helpers" => {
+                        let helper = field.text().await.map_err(bad_upload)?;
+                        upload.details.helpers.push(helper);
+                    }
+                    "note" => {
+                        let note = field.text().await.map_err(bad_upload)?;
+                        upload.details.note = Some(note);
+                    }
+                    "attachment" => {
+                        let content_type = field.content_type().unwrap_or_default().to_owned();
+                        let data = field.bytes().await.map_err(bad_upload)?;
+                        upload.details.attachments.push(NewAttachment {
+                            content_type,
+                            data: data.to_vec(),
+                        });
+                    }
+                    _ => {}
+                }
+            }
+            Ok(upload)
+        } else if content_type.starts_with("application/json") {
+            let Json(body) = Json::<MarkTaskDoneBody>::from_request(request, state)
+                .await
+                .map_err(IntoResponse::into_response)?;
+            Ok(Self {
+                steps_done: body.steps_done,
+                details: CompletionDetails {
+                    helpers: body.helpers,
+                    note: body.note,
+                    attachments: Vec::new(),
+                },
+            })
+        } else {
+            Ok(Self::default())
+        }
+    }
+}
+
 async fn mark_task_done(
     Path(task_id): Path<TaskId>,
     Query(query): Query<MarkTaskDoneQuery>,
-    State(store): State<TaskStore>,
+    store: TaskStore,
+    ExtractLanguage(language): ExtractLanguage,
+    upload: CompletionUpload,
+) -> Result<Json<Task>, TaskStoreError> {
+    let on = match query.on {
+        Some(on) => on,
+        None => store.today_for(Some(&query.by)).await?,
+    };
+    store
+        .mark_task_done_with(
+            task_id,
+            &query.by,
+            &on,
+            upload.steps_done.as_deref(),
+            upload.details,
+        )
+        .await?;
+    Ok(Json(store.task(task_id, &language).await?))
+}
+
+async fn task_history(
+    Path(task_id): Path<TaskId>,
+    store: TaskStore,
+) -> Result<Json<Vec<HistoryEntry>>, TaskStoreError> {
+    Ok(Json(store.history(task_id).await?))
+}
+
+async fn fetch_attachment(
+    Path(attachment_id): Path<AttachmentId>,
+    store: TaskStore,
+) -> Result<impl IntoResponse, TaskStoreError> {
+    let (attachment, data) = store.attachment(attachment_id).await?;
+    Ok((
+        [
+            (header::CONTENT_TYPE, attachment.content_type),
+            // only images are stored, but browsers shouldn't guess otherwise
+            (header::X_CONTENT_TYPE_OPTIONS, "nosniff".to_owned()),
+        ],
+        data,
+    ))
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct ClaimTaskQuery {
+    by: String,
+}
+
+async fn claim_task(
+    Path(task_id): Path<TaskId>,
+    Query(query): Query<ClaimTaskQuery>,
+    store: TaskStore,
+    ExtractLanguage(language): ExtractLanguage,
+) -> Result<Json<Task>, TaskStoreError> {
+    store.claim_task(task_id, &query.by).await?;
+    Ok(Json(store.task(task_id, &language).await?))
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct ParticipantQuery {
+    person: String,
+}
+
+async fn add_participant(
+    Path(task_id): Path<TaskId>,
+    Query(query): Query<ParticipantQuery>,
+    store: TaskStore,
+    ExtractLanguage(language): ExtractLanguage,
+) -> Result<Json<Task>, TaskStoreError> {
+    store.add_participant(task_id, &query.person).await?;
+    Ok(Json(store.task(task_id, &language).await?))
+}
+
+async fn remove_participant(
+    Path(task_id): Path<TaskId>,
+    Query(query): Query<ParticipantQuery>,
+    store: TaskStore,
     ExtractLanguage(language): ExtractLanguage,
+) -> Result<Json<Task>, TaskStoreError> {
+    store.remove_participant(task_id, &query.person).await?;
+    Ok(Json(store.task(task_id, &language).await?))
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct ReorderParticipantsBody {
+    participants: Vec<String>,
+}
+
+async fn reorder_participants(
+    Path(task_id): Path<TaskId>,
+    store: TaskStore,
+    ExtractLanguage(language): ExtractLanguage,
+    Json(body): Json<ReorderParticipantsBody>,
 ) -> Result<Json<Task>, TaskStoreError> {
     store
-        .mark_task_done(task_id, &query.by, &query.on.unwrap_or_else(today))
+        .reorder_participants(task_id, &body.participants)
+        .await?;
+    Ok(Json(store.task(task_id, &language).await?))
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct SetActiveWindowBody {
+    active_window: Option<ActiveWindow>,
+}
+
+async fn set_active_window(
+    Path(task_id): Path<TaskId>,
+    store: TaskStore,
+    ExtractLanguage(language): ExtractLanguage,
+    Json(body): Json<SetActiveWindowBody>,
+) -> Result<Json<Task>, TaskStoreError> {
+    store.set_active_window(task_id, body.active_window).await?;
+    Ok(Json(store.task(task_id, &language).await?))
+}
+
+async fn set_urgency(
+    Path(task_id): Path<TaskId>,
+    store: TaskStore,
+    ExtractLanguage(language): ExtractLanguage,
+    Json(urgency): Json<Urgency>,
+) -> Result<Json<Task>, TaskStoreError> {
+    store.set_urgency(task_id, urgency).await?;
+    Ok(Json(store.task(task_id, &language).await?))
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct SetPointsBody {
+    points: u32,
+}
+
+async fn set_points(
+    Path(task_id): Path<TaskId>,
+    store: TaskStore,
+    ExtractLanguage(language): ExtractLanguage,
+    Json(body): Json<SetPointsBody>,
+) -> Result<Json<Task>, TaskStoreError> {
+    store.set_points(task_id, body.points).await?;
+    Ok(Json(store.task(task_id, &language).await?))
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct SetCatchUpBody {
+    catch_up: CatchUp,
+}
+
+async fn set_catch_up(
+    Path(task_id): Path<TaskId>,
+    store: TaskStore,
+    ExtractLanguage(language): ExtractLanguage,
+    Json(body): Json<SetCatchUpBody>,
+) -> Result<Json<Task>, TaskStoreError> {
+    store.set_catch_up(task_id, body.catch_up).await?;
+    Ok(Json(store.task(task_id, &language).await?))
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct PointsQuery {
+    /// Leave out for everyone in the household
+    person: Option<String>,
+}
+
+async fn points_balances(
+    Query(query): Query<PointsQuery>,
+    store: TaskStore,
+) -> Result<Json<Vec<Balance>>, TaskStoreError> {
+    store.balances(query.person.as_deref()).await.map(Json)
+}
+
+async fn points_ledger(
+    Query(query): Query<PointsQuery>,
+    store: TaskStore,
+) -> Result<Json<Vec<LedgerEntry>>, TaskStoreError> {
+    store.ledger(query.person.as_deref()).await.map(Json)
+}
+
+async fn points_ledger_csv(
+    Query(query): Query<PointsQuery>,
+    store: TaskStore,
+) -> Result<impl IntoResponse, TaskStoreError> {
+    let csv = store.ledger_csv(query.person.as_deref()).await?;
+    Ok(([(header::CONTENT_TYPE, "text/csv; charset=utf-8")], csv))
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct PointsAdjustmentBody {
+    /// Negative to take points away
+    points: i32,
+    description: Option<String>,
+}
+
+async fn adjust_points(
+    Path(person): Path<String>,
+    store: TaskStore,
+    Json(body): Json<PointsAdjustmentBody>,
+) -> Result<Json<LedgerEntry>, TaskStoreError> {
+    let entry_id = store
+        .adjust_points(&person, body.points, body.description.as_deref())
         .await?;
+    Ok(Json(store.ledger_entry(entry_id).await?))
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct PointsRedemptionBody {
+    points: i32,
+    description: Option<String>,
+}
+
+async fn redeem_points(
+    Path(person): Path<String>,
+    store: TaskStore,
+    Json(body): Json<PointsRedemptionBody>,
+) -> Result<Json<LedgerEntry>, TaskStoreError> {
+    let entry_id = store
+        .redeem_points(&person, body.points, body.description.as_deref())
+        .await?;
+    Ok(Json(store.ledger_entry(entry_id).await?))
+}
+
+async fn list_templates() -> Json<Vec<Template>> {
+    Json(Template::catalogue())
+}
+
+async fn fetch_template(Path(template_id): Path<String>) -> Result<Json<Template>, TaskStoreError> {
+    Template::find(&template_id)
+        .map(Json)
+        .ok_or(TaskStoreError::UnknownTemplate(template_id))
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct UseTemplateBody {
+    participants: Vec<String>,
+    /// First person to do the task, or the first participant if not given
+    starts_with: Option<String>,
+    /// Today if not given
+    starts_on: Option<NaiveDate>,
+}
+
+async fn use_template(
+    Path(template_id): Path<String>,
+    store: TaskStore,
+    ExtractLanguage(language): ExtractLanguage,
+    Json(body): Json<UseTemplateBody>,
+) -> Result<Json<Task>, TaskStoreError> {
+    let template =
+        Template::find(&template_id).ok_or(TaskStoreError::UnknownTemplate(template_id))?;
+    let starts_on = match body.starts_on {
+        Some(starts_on) => starts_on,
+        None => store.today_for(None).await?,
+    };
+    let starts_with = body
+        .starts_with
+        .or_else(|| body.participants.first().cloned())
+        .unwrap_or_default();
+    let task_id = store
+        .add_task(NewTask {
+            participants: body.participants,
+            starts_with,
+            starts_on,
+            ..template.new_task()
+        })
+        .await?;
+    Ok(Json(store.task(task_id, &language).await?))
+}
+
+async fn list_tags(
+    store: TaskStore,
+    ExtractLanguage(language): ExtractLanguage,
+) -> Result<Json<Vec<Tag>>, TaskStoreError> {
+    store.tags(&language).await.map(Json)
+}
+
+async fn create_tag(
+    store: TaskStore,
+    ExtractLanguage(language): ExtractLanguage,
+    Json(new_tag): Json<NewTag>,
+) -> Result<Json<Tag>, TaskStoreError> {
+    let tag_id = store.add_tag(new_tag).await?;
+    Ok(Json(store.tag(tag_id, &language).await?))
+}
+
+async fn fetch_tag(
+    Path(tag_id): Path<TagId>,
+    store: TaskStore,
+    ExtractLanguage(language): ExtractLanguage,
+) -> Result<Json<Tag>, TaskStoreError> {
+    store.tag(tag_id, &language).await.map(Json)
+}
+
+async fn update_tag(
+    Path(tag_id): Path<TagId>,
+    store: TaskStore,
+    ExtractLanguage(language): ExtractLanguage,
+    Json(new_tag): Json<NewTag>,
+) -> Result<Json<Tag>, TaskStoreError> {
+    store.update_tag(tag_id, new_tag).await?;
+    Ok(Json(store.tag(tag_id, &language).await?))
+}
+
+async fn delete_tag(
+    Path(tag_id): Path<TagId>,
+    store: TaskStore,
+) -> Result<StatusCode, TaskStoreError> {
+    store.delete_tag(tag_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct TagTaskQuery {
+    tag: TagId,
+}
+
+async fn tag_task(
+    Path(task_id): Path<TaskId>,
+    Query(query): Query<TagTaskQuery>,
+    store: TaskStore,
+    ExtractLanguage(language): ExtractLanguage,
+) -> Result<Json<Task>, TaskStoreError> {
+    store.tag_task(task_id, query.tag).await?;
+    Ok(Json(store.task(task_id, &language).await?))
+}
+
+async fn untag_task(
+    Path(task_id): Path<TaskId>,
+    Query(query): Query<TagTaskQuery>,
+    store: TaskStore,
+    ExtractLanguage(language): ExtractLanguage,
+) -> Result<Json<Task>, TaskStoreError> {
+    store.untag_task(task_id, query.tag).await?;
     Ok(Json(store.task(task_id, &language).await?))
 }
 
+async fn list_chains(store: TaskStore) -> Result<Json<Vec<Chain>>, TaskStoreError> {
+    store.chains().await.map(Json)
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct NewChain {
+    from_task: TaskId,
+    to_task: TaskId,
+    offset_days: u16,
+    assignee: ChainAssignee,
+}
+
+async fn create_chain(
+    store: TaskStore,
+    Json(chain): Json<NewChain>,
+) -> Result<Json<Chain>, TaskStoreError> {
+    let chain_id = store
+        .add_chain(
+            chain.from_task,
+            chain.to_task,
+            chain.offset_days,
+            chain.assignee,
+        )
+        .await?;
+    Ok(Json(store.chain(chain_id).await?))
+}
+
+async fn fetch_chain(
+    Path(chain_id): Path<ChainId>,
+    store: TaskStore,
+) -> Result<Json<Chain>, TaskStoreError> {
+    store.chain(chain_id).await.map(Json)
+}
+
+async fn delete_chain(
+    Path(chain_id): Path<ChainId>,
+    store: TaskStore,
+) -> Result<StatusCode, TaskStoreError> {
+    store.delete_chain(chain_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn list_feeds(store: TaskStore) -> Result<Json<Vec<Feed>>, TaskStoreError> {
+    store.feeds().await.map(Json)
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct NewFeed {
+    /// Leave out for a feed of the whole household's tasks
+    person: Option<String>,
+}
+
+/// Creates a feed whose task names are in the language of the request.
+async fn create_feed(
+    store: TaskStore,
+    ExtractLanguage(language): ExtractLanguage,
+    Json(feed): Json<NewFeed>,
+) -> Result<Json<Feed>, TaskStoreError> {
+    let feed_id = store.add_feed(feed.person.as_deref(), &language).await?;
+    Ok(Json(store.feed(feed_id).await?))
+}
+
+async fn delete_feed(
+    Path(feed_id): Path<FeedId>,
+    store: TaskStore,
+) -> Result<StatusCode, TaskStoreError> {
+    store.delete_feed(feed_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Serves a feed to calendar apps, which only know the feed's secret, as
+/// `<secret>.ics`.
+async fn calendar_feed(
+    Path(file_name): Path<String>,
+    State(conn): State<SqlitePool>,
+) -> Result<impl IntoResponse, TaskStoreError> {
+    let secret = file_name
+        .strip_suffix(".ics")
+        .and_then(|secret| secret.parse().ok())
+        .ok_or(TaskStoreError::UnknownFeedSecret)?;
+    let calendar = TaskStore::calendar(&conn, secret).await?;
+    Ok((
+        [(header::CONTENT_TYPE, "text/calendar; charset=utf-8")],
+        calendar,
+    ))
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct StatsQuery {
+    #[serde(default)]
+    period: StatsPeriod,
+}
+
+async fn household_stats(
+    Query(query): Query<StatsQuery>,
+    store: TaskStore,
+    ExtractLanguage(language): ExtractLanguage,
+) -> Result<Json<Stats>, TaskStoreError> {
+    store.stats(query.period, &language).await.map(Json)
+}
+
 pub fn routes(conn: SqlitePool) -> Router {
     Router::new()
         .route("/", get(list_all_tasks))
+        .route("/forecast", get(forecast))
         .route("/people/:person", get(tasks_for_person))
-        .route("/actions/mark_task_done/:task", post(mark_task_done))
-        .with_state(TaskStore::new(conn))
+        .route(
+            "/people/:person/absences",
+            get(list_absences).post(add_absence),
+        )
+        .route(
+            "/people/:person/timezone",
+            get(person_timezone).put(set_person_timezone),
+        )
+        .route(
+            "/timezone",
+            get(household_timezone).put(set_household_timezone),
+        )
+        .route(
+            "/backdate_limit",
+            get(backdate_limit).put(set_backdate_limit),
+        )
+        .route("/people/:person/points/adjustments", post(adjust_points))
+        .route("/people/:person/points/redemptions", post(redeem_points))
+        .route("/absences/:absence", delete(remove_absence))
+        .route("/points", get(points_balances))
+        .route("/points/ledger", get(points_ledger))
+        .route("/points/ledger.csv", get(points_ledger_csv))
+        .route(
+            "/actions/mark_task_done/:task",
+            post(mark_task_done).layer(DefaultBodyLimit::max(MAX_UPLOAD_SIZE)),
+        )
+        .route("/history/:task", get(task_history))
+        .route("/attachments/:attachment", get(fetch_attachment))
+        .route("/actions/claim_task/:task", post(claim_task))
+        .route("/actions/add_participant/:task", post(add_participant))
+        .route(
+            "/actions/remove_participant/:task",
+            post(remove_participant),
+        )
+        .route(
+            "/actions/reorder_participants/:task",
+            post(reorder_participants),
+        )
+        .route("/actions/set_active_window/:task", post(set_active_window))
+        .route("/actions/set_urgency/:task", post(set_urgency))
+        .route("/actions/set_points/:task", post(set_points))
+        .route("/actions/set_catch_up/:task", post(set_catch_up))
+        .route("/actions/tag_task/:task", post(tag_task))
+        .route("/actions/untag_task/:task", post(untag_task))
+        .route("/templates", get(list_templates))
+        .route(
+            "/templates/:template",
+            get(fetch_template).post(use_template),
+        )
+        .route("/tags", get(list_tags).post(create_tag))
+        .route(
+            "/tags/:tag",
+            get(fetch_tag).put(update_tag).delete(delete_tag),
+        )
+        .route("/chains", get(list_chains).post(create_chain))
+        .route("/chains/:chain", get(fetch_chain).delete(delete_chain))
+        .route("/feeds", get(list_feeds).post(create_feed))
+        .route("/feeds/:feed", delete(delete_feed))
+        .with_state(conn)
+}
+
+pub fn stats_routes(conn: SqlitePool) -> Router {
+    Router::new()
+        .route("/", get(household_stats))
+        .with_state(conn)
+}
+
+/// Calendar feeds, which are protected by their secret instead of a login.
+pub fn calendar_routes(conn: SqlitePool) -> Router {
+    Router::new()
+        .route("/:feed", get(calendar_feed))
+        .with_state(conn)
 }
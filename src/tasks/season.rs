@@ -0,0 +1,277 @@
+// SPDX-FileCopyrightText: 2023 Jonathan Frere
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! Seasonal tasks, which are only done during part of the year, such as
+//! mowing the lawn from April to October.  The active window is written as
+//! two days of the year, e.g. `04-01/10-31`, and can wrap around the end of
+//! the year (e.g. `11-01/02-28` for winter).
+
+use std::{fmt::Display, str::FromStr};
+
+use chrono::{Datelike, NaiveDate};
+use sqlx::Sqlite;
+
+#[derive(thiserror::Error, Debug, PartialEq, Eq)]
+pub enum ActiveWindowError {
+    #[error("active window should be two days of the year, e.g. 04-01/10-31")]
+    InvalidFormat,
+    #[error("{0} is not a day of the year")]
+    InvalidDay(String),
+}
+
+/// Gives up looking for an active date after this many inactive dates in a row,
+/// so that routines which never fall inside the window don't loop forever.
+const MAX_SKIPPED_DATES: u32 = 1000;
+
+/// The part of each year that a task is active in, including both end days.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ActiveWindow {
+    /// First active day, as (month, day)
+    from: (u32, u32),
+    /// Last active day, as (month, day)
+    until: (u32, u32),
+}
+
+impl ActiveWindow {
+    pub fn contains(&self, date: NaiveDate) -> bool {
+        let day = (date.month(), date.day());
+        if self.from <= self.until {
+            self.from <= day && day <= self.until
+        } else {
+            day >= self.from || day <= self.until
+        }
+    }
+
+    /// The given date if the window contains it, otherwise the day the window
+    /// next opens.
+    pub fn next_active_day(&self, date: NaiveDate) -> NaiveDate {
+        if self.contains(date) {
+            return date;
+        }
+
+        let (month, day) = self.from;
+        // outside of leap years, a window from the 29th of February opens on the
+        // 1st of March instead (if that is in the window), otherwise the next
+        // 29th of February may be up to eight years away
+        (date.year()..=date.year() + 8)
+            .filter_map(|year| {
+                NaiveDate::from_ymd_opt(year, month, day)
+                    .or_else(|| NaiveDate::from_ymd_opt(year, 3, 1))
+            })
+            .find(|start| *start > date && self.contains(*start))
+            .unwrap_or(date)
+    }
+
+    /// When a task that was due on `due` is due by `today`.  A due date that
+    /// is missed until the window closes moves to the day it opens again, so
+    /// that the task isn't overdue all through the inactive part of the year.
+    pub fn carry_over(&self, due: NaiveDate, today: NaiveDate) -> NaiveDate {
+        let mut due = due;
+        while let Some(closed) = due
+            .iter_days()
+            .take(366)
+            .find(|date| !self.contains(*date))
+            .filter(|closed| *closed <= today)
+        {
+            let opens = self.next_active_day(closed);
+            if opens == closed {
+                break;
+            }
+            due = opens;
+        }
+        due
+    }
+
+    /// The `n`th of the given dates (in order) that falls inside the window.
+    pub fn nth_active(
+        &self,
+        dates: impl Iterator<Item = NaiveDate>,
+        n: usize,
+    ) -> Option<NaiveDate> {
+        let mut skipped = 0;
+        dates
+            .take_while(|date| {
+                if self.contains(*date) {
+                    skipped = 0;
+                } else {
+                    skipped += 1;
+                }
+                skipped <= MAX_SKIPPED_DATES
+            })
+            .filter(|date| self.contains(*date))
+            .nth(n)
+    }
+}
+
+fn parse_day(text: &str) -> Result<(u32, u32), ActiveWindowError> {
+    let invalid = || ActiveWindowError::InvalidDay(text.to_owned());
+    let (month, day) = text.trim().split_once('-').ok_or_else(invalid)?;
+    let month = month.parse().map_err(|_| invalid())?;
+    let day = day.parse().map_err(|_| invalid())?;
+
+    // checked against a leap year so that the 29th of February is allowed
+    NaiveDate::from_ymd_opt(2000, month, day).ok_or_else(invalid)?;
+    Ok((month, day))
+}
+
+impl FromStr for ActiveWindow {
+    type Err = ActiveWindowError;
+
+    fn from_str(window: &str) -> Result<Self, Self::Err> {
+        let (from, until) = window
+            .split_once('/')
+            .ok_or(ActiveWindowError::InvalidFormat)?;
+        Ok(Self {
+            from: parse_day(from)?,
+            until: parse_day(until)?,
+        })
+    }
+}
+
+impl Display for ActiveWindow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:02}-{:02}/{:02}-{:02}",
+            self.from.0, self.from.1, self.until.0, self.until.1
+        )
+    }
+}
+
+impl serde::Serialize for ActiveWindow {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for ActiveWindow {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+impl sqlx::Type<Sqlite> for ActiveWindow {
+    fn type_info() -> <Sqlite as sqlx::Database>::TypeInfo {
+        <String as sqlx::Type<Sqlite>>::type_info()
+    }
+}
+
+impl<'q> sqlx::Encode<'q, Sqlite> for ActiveWindow {
+    fn encode_by_ref(
+        &self,
+        buf: &mut <Sqlite as sqlx::database::HasArguments<'q>>::ArgumentBuffer,
+    ) -> sqlx::encode::IsNull {
+        <String as sqlx::Encode<Sqlite>>::encode(self.to_string(), buf)
+    }
+}
+
+impl<'r> sqlx::Decode<'r, Sqlite> for ActiveWindow {
+    fn decode(
+        value: <Sqlite as sqlx::database::HasValueRef<'r>>::ValueRef,
+    ) -> Result<Self, sqlx::error::BoxDynError> {
+        Ok(<&str as sqlx::Decode<Sqlite>>::decode(value)?.parse()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn window(text: &str) -> ActiveWindow {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn parses_and_formats_windows() {
+        assert_eq!(window("4-1/10-31").to_string(), "04-01/10-31");
+        assert_eq!(window("02-29/02-29").to_string(), "02-29/02-29");
+
+        assert_eq!(
+            "04-01".parse::<ActiveWindow>(),
+            Err(ActiveWindowError::InvalidFormat)
+        );
+        assert_eq!(
+            "04-31/10-31".parse::<ActiveWindow>(),
+            Err(ActiveWindowError::InvalidDay("04-31".to_owned()))
+        );
+        assert_eq!(
+            "13-01/10-31".parse::<ActiveWindow>(),
+            Err(ActiveWindowError::InvalidDay("13-01".to_owned()))
+        );
+    }
+
+    #[test]
+    fn windows_can_wrap_around_the_end_of_the_year() {
+        let summer = window("04-01/10-31");
+        assert!(summer.contains(date(2020, 4, 1)));
+        assert!(summer.contains(date(2020, 10, 31)));
+        assert!(!summer.contains(date(2020, 11, 1)));
+        assert!(!summer.contains(date(2020, 3, 31)));
+
+        let winter = window("11-01/02-28");
+        assert!(winter.contains(date(2020, 12, 31)));
+        assert!(winter.contains(date(2021, 1, 1)));
+        assert!(!winter.contains(date(2020, 6, 1)));
+    }
+
+    #[test]
+    fn finds_the_next_active_day() {
+        let summer = window("04-01/10-31");
+        assert_eq!(summer.next_active_day(date(2020, 6, 1)), date(2020, 6, 1));
+        assert_eq!(summer.next_active_day(date(2020, 2, 1)), date(2020, 4, 1));
+        assert_eq!(summer.next_active_day(date(2020, 11, 1)), date(2021, 4, 1));
+
+        let leap_day = window("02-29/03-01");
+        assert_eq!(leap_day.next_active_day(date(2021, 3, 2)), date(2022, 3, 1));
+        let leap_day = window("02-29/02-29");
+        assert_eq!(
+            leap_day.next_active_day(date(2021, 3, 2)),
+            date(2024, 2, 29)
+        );
+    }
+
+    #[test]
+    fn missed_due_dates_carry_over_to_the_next_season() {
+        let summer = window("04-01/10-31");
+        assert_eq!(
+            summer.carry_over(date(2020, 10, 20), date(2020, 10, 31)),
+            date(2020, 10, 20)
+        );
+        assert_eq!(
+            summer.carry_over(date(2020, 10, 20), date(2020, 11, 15)),
+            date(2021, 4, 1)
+        );
+        assert_eq!(
+            summer.carry_over(date(2020, 10, 20), date(2022, 5, 1)),
+            date(2022, 4, 1)
+        );
+
+        let all_year = window("01-01/12-31");
+        assert_eq!(
+            all_year.carry_over(date(2020, 10, 20), date(2022, 5, 1)),
+            date(2020, 10, 20)
+        );
+    }
+
+    #[test]
+    fn skips_inactive_dates() {
+        let november = window("11-01/11-30");
+        let weekly = (0..).map(|week| date(2020, 10, 18) + chrono::Duration::weeks(week));
+        assert_eq!(
+            november.nth_active(weekly.clone(), 0),
+            Some(date(2020, 11, 1))
+        );
+        assert_eq!(november.nth_active(weekly, 5), Some(date(2021, 11, 7)));
+
+        // every 1st of March never falls in November
+        let yearly = (2020..).map(|year| date(year, 3, 1));
+        assert_eq!(november.nth_active(yearly, 0), None);
+    }
+}
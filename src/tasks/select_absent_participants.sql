@@ -0,0 +1,13 @@
+-- SPDX-FileCopyrightText: 2023 Jonathan Frere
+--
+-- SPDX-License-Identifier: MPL-2.0
+SELECT
+  users.username
+FROM
+  absences
+  INNER JOIN users ON users.id = absences.user_id
+  INNER JOIN task_participant_link ON task_participant_link.user_id = absences.user_id
+WHERE
+  task_participant_link.task_id = ?1
+  AND absences.starts_on <= ?2
+  AND absences.ends_on >= ?2
\ No newline at end of file
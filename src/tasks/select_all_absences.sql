@@ -0,0 +1,17 @@
+-- SPDX-FileCopyrightText: 2023 Jonathan Frere
+--
+-- SPDX-License-Identifier: MPL-2.0
+SELECT
+  absences.id,
+  users.username as person,
+  absences.starts_on,
+  absences.ends_on
+FROM
+  absences
+  INNER JOIN users ON users.id = absences.user_id
+  INNER JOIN household_members ON household_members.user_id = absences.user_id
+WHERE
+  household_members.household_id = ?
+ORDER BY
+  absences.starts_on,
+  absences.id
\ No newline at end of file
@@ -0,0 +1,9 @@
+-- SPDX-FileCopyrightText: 2023 Jonathan Frere
+--
+-- SPDX-License-Identifier: MPL-2.0
+SELECT
+  id
+FROM
+  tasks
+WHERE
+  household_id = ?
\ No newline at end of file
@@ -0,0 +1,28 @@
+-- SPDX-FileCopyrightText: 2023 Jonathan Frere
+--
+-- SPDX-License-Identifier: MPL-2.0
+SELECT
+  grouped_tasks.id,
+  task_translations.task_name as name,
+  grouped_tasks.kind,
+  grouped_tasks.duration,
+  tasks.rotation,
+  tasks.catch_up,
+  tasks.recurrence,
+  tasks.active_window,
+  tasks.due_on,
+  claimant.username as claimed_by,
+  participants,
+  last_completed_by,
+  started_on,
+  tasks.archived_on IS NOT NULL AS archived
+FROM
+  grouped_tasks
+  LEFT JOIN task_translations ON task_translations.task_id = grouped_tasks.id
+  AND task_translations.lang = ?
+  INNER JOIN tasks ON tasks.id = grouped_tasks.id
+  LEFT JOIN users claimant ON claimant.id = tasks.claimed_by
+WHERE
+  tasks.household_id = ?
+ORDER BY
+  grouped_tasks.id
\ No newline at end of file
@@ -2,14 +2,229 @@
 --
 -- SPDX-License-Identifier: MPL-2.0
 SELECT
-  id,
-  task_translations.task_name as name,
-  kind,
-  duration,
-  participants,
-  last_completed,
-  last_completed_by
+  *
 FROM
-  grouped_tasks
-  INNER JOIN task_translations ON task_translations.task_id = grouped_tasks.id
-  AND task_translations.lang = ?
\ No newline at end of file
+  (
+    SELECT
+      grouped_tasks.id,
+      task_translations.task_name as name,
+      task_translations.description,
+      grouped_tasks.kind,
+      grouped_tasks.duration,
+      tasks.rotation,
+      tasks.catch_up,
+      tasks.recurrence,
+      tasks.active_window,
+      tasks.due_soon_days,
+      tasks.grace_days,
+      tasks.points,
+      participants,
+      last_completed,
+      tasks.next_due,
+      assignee.username as assigned_to,
+      (
+        SELECT
+          json_group_array (json_object ('id', id, 'name', name))
+        FROM
+          (
+            SELECT
+              tags.id,
+              coalesce(
+                (
+                  SELECT
+                    tag_name
+                  FROM
+                    tag_translations
+                  WHERE
+                    tag_translations.tag_id = tags.id
+                    AND tag_translations.lang = ?1
+                ),
+                (
+                  SELECT
+                    tag_name
+                  FROM
+                    tag_translations
+                  WHERE
+                    tag_translations.tag_id = tags.id
+                  ORDER BY
+                    lang
+                  LIMIT
+                    1
+                )
+              ) as name
+            FROM
+              task_tag_link
+              INNER JOIN tags ON tags.id = task_tag_link.tag_id
+            WHERE
+              task_tag_link.task_id = tasks.id
+            ORDER BY
+              name
+          )
+      ) as tags,
+      (
+        SELECT
+          json_group_array (json_object ('id', id, 'name', name))
+        FROM
+          (
+            SELECT
+              checklist_steps.id,
+              coalesce(
+                (
+                  SELECT
+                    step_name
+                  FROM
+                    checklist_step_translations
+                  WHERE
+                    checklist_step_translations.step_id = checklist_steps.id
+                    AND checklist_step_translations.lang = ?1
+                ),
+                (
+                  SELECT
+                    step_name
+                  FROM
+                    checklist_step_translations
+                  WHERE
+                    checklist_step_translations.step_id = checklist_steps.id
+                  ORDER BY
+                    lang
+                  LIMIT
+                    1
+                )
+              ) as name
+            FROM
+              checklist_steps
+            WHERE
+              checklist_steps.task_id = tasks.id
+            ORDER BY
+              checklist_steps.position
+          )
+      ) as checklist,
+      (
+        SELECT
+          steps_done
+        FROM
+          completions
+        WHERE
+          completions.task_id = tasks.id
+          AND completions.initial = FALSE
+        ORDER BY
+          completions.completed_on DESC,
+          completions.id DESC
+        LIMIT
+          1
+      ) as last_steps_done,
+      CASE ?10
+        WHEN 'Deadline' THEN coalesce(tasks.next_due, '9999-12-31')
+        WHEN 'Name' THEN lower(task_translations.task_name)
+        WHEN 'LastCompleted' THEN last_completed
+      END as sort_key
+    FROM
+      grouped_tasks
+      INNER JOIN task_translations ON task_translations.task_id = grouped_tasks.id
+      AND task_translations.lang = ?1
+      INNER JOIN tasks ON tasks.id = grouped_tasks.id
+      LEFT JOIN users assignee ON assignee.id = tasks.assigned_to
+    WHERE
+      tasks.household_id = ?13
+      AND tasks.archived_on IS NULL
+      -- one-off tasks are finished once they have been done
+      AND NOT (
+        grouped_tasks.kind = 'OneOff'
+        AND EXISTS (
+          SELECT
+            1
+          FROM
+            completions
+          WHERE
+            completions.task_id = grouped_tasks.id
+            AND completions.initial = FALSE
+        )
+      )
+      -- triggered tasks are hidden until the task before them has been done
+      AND NOT (
+        grouped_tasks.kind = 'Triggered'
+        AND tasks.due_on IS NULL
+      )
+      AND (
+        ?2 IS NULL
+        OR assignee.username = ?2 COLLATE NOCASE
+      )
+      AND (
+        ?3 IS NULL
+        OR EXISTS (
+          SELECT
+            1
+          FROM
+            task_participant_link
+            INNER JOIN users ON users.id = task_participant_link.user_id
+          WHERE
+            task_participant_link.task_id = tasks.id
+            AND users.username = ?3 COLLATE NOCASE
+        )
+      )
+      AND (
+        ?4 IS NULL
+        OR grouped_tasks.kind = ?4
+      )
+      -- due dates are only ever moved later when tasks are read, so tasks
+      -- that skip missed cycles or are seasonal may turn out to be upcoming
+      -- after all (which is checked once they have been read)
+      AND (
+        ?5 IS NULL
+        OR (
+          ?5 = 'Overdue'
+          AND tasks.next_due < ?6
+        )
+        OR (
+          ?5 = 'Upcoming'
+          AND (
+            tasks.next_due >= ?6
+            OR tasks.catch_up = 'Skip'
+            OR tasks.active_window IS NOT NULL
+          )
+        )
+      )
+      AND (
+        ?7 IS NULL
+        OR tasks.next_due >= ?7
+        OR tasks.catch_up = 'Skip'
+        OR tasks.active_window IS NOT NULL
+      )
+      AND (
+        ?8 IS NULL
+        OR tasks.next_due <= ?8
+      )
+      AND (
+        ?9 IS NULL
+        OR EXISTS (
+          SELECT
+            1
+          FROM
+            task_translations search
+          WHERE
+            search.task_id = tasks.id
+            AND search.task_name LIKE '%' || ?9 || '%' ESCAPE '\'
+        )
+      )
+      AND (
+        ?12 IS NULL
+        OR EXISTS (
+          SELECT
+            1
+          FROM
+            task_tag_link
+          WHERE
+            task_tag_link.task_id = tasks.id
+            AND task_tag_link.tag_id = ?12
+        )
+      )
+  )
+ORDER BY
+  CASE
+    WHEN ?11 = 'Descending' THEN NULL
+    ELSE sort_key
+  END ASC,
+  CASE
+    WHEN ?11 = 'Descending' THEN sort_key
+  END DESC,
+  id
\ No newline at end of file
@@ -0,0 +1,9 @@
+-- SPDX-FileCopyrightText: 2023 Jonathan Frere
+--
+-- SPDX-License-Identifier: MPL-2.0
+SELECT
+  households.backdate_days
+FROM
+  households
+WHERE
+  households.id = ?
\ No newline at end of file
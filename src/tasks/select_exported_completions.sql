@@ -0,0 +1,28 @@
+-- SPDX-FileCopyrightText: 2023 Jonathan Frere
+--
+-- SPDX-License-Identifier: MPL-2.0
+SELECT
+  completions.task_id,
+  users.username AS completed_by,
+  (
+    SELECT
+      json_group_array (helper.username)
+    FROM
+      completion_helpers
+      INNER JOIN users helper ON helper.id = completion_helpers.user_id
+    WHERE
+      completion_helpers.completion_id = completions.id
+  ) AS helpers,
+  completions.completed_on,
+  completions.note,
+  completions.initial
+FROM
+  completions
+  INNER JOIN users ON users.id = completions.completed_by
+  INNER JOIN tasks ON tasks.id = completions.task_id
+WHERE
+  tasks.household_id = ?
+ORDER BY
+  completions.task_id,
+  completions.completed_on,
+  completions.id
\ No newline at end of file
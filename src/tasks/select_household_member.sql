@@ -0,0 +1,11 @@
+-- SPDX-FileCopyrightText: 2023 Jonathan Frere
+--
+-- SPDX-License-Identifier: MPL-2.0
+SELECT
+  users.username
+FROM
+  users
+  INNER JOIN household_members ON household_members.user_id = users.id
+WHERE
+  users.username = ? COLLATE NOCASE
+  AND household_members.household_id = ?
\ No newline at end of file
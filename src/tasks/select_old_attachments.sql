@@ -0,0 +1,13 @@
+-- SPDX-FileCopyrightText: 2023 Jonathan Frere
+--
+-- SPDX-License-Identifier: MPL-2.0
+SELECT
+  completion_attachments.id,
+  completion_attachments.file_name
+FROM
+  completion_attachments
+  INNER JOIN completions ON completions.id = completion_attachments.completion_id
+  INNER JOIN tasks ON tasks.id = completions.task_id
+WHERE
+  completions.completed_on < ?
+  AND tasks.household_id = ?
\ No newline at end of file
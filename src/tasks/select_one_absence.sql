@@ -0,0 +1,21 @@
+-- SPDX-FileCopyrightText: 2023 Jonathan Frere
+--
+-- SPDX-License-Identifier: MPL-2.0
+SELECT
+  absences.id,
+  users.username as person,
+  absences.starts_on,
+  absences.ends_on
+FROM
+  absences
+  INNER JOIN users ON users.id = absences.user_id
+WHERE
+  absences.id = ?
+  AND absences.user_id IN (
+    SELECT
+      user_id
+    FROM
+      household_members
+    WHERE
+      household_id = ?
+  )
\ No newline at end of file
@@ -0,0 +1,14 @@
+-- SPDX-FileCopyrightText: 2023 Jonathan Frere
+--
+-- SPDX-License-Identifier: MPL-2.0
+SELECT
+  calendar_feeds.id,
+  users.username as person,
+  calendar_feeds.lang as language,
+  calendar_feeds.secret
+FROM
+  calendar_feeds
+  LEFT JOIN users ON users.id = calendar_feeds.user_id
+WHERE
+  calendar_feeds.id = ?
+  AND calendar_feeds.household_id = ?
\ No newline at end of file
@@ -0,0 +1,17 @@
+-- SPDX-FileCopyrightText: 2023 Jonathan Frere
+--
+-- SPDX-License-Identifier: MPL-2.0
+SELECT
+  points_ledger.id,
+  users.username AS person,
+  points_ledger.entry_on,
+  points_ledger.kind,
+  points_ledger.points,
+  points_ledger.task_id,
+  points_ledger.description
+FROM
+  points_ledger
+  INNER JOIN users ON users.id = points_ledger.user_id
+WHERE
+  points_ledger.id = ?
+  AND points_ledger.household_id = ?
\ No newline at end of file
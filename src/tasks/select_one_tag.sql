@@ -0,0 +1,34 @@
+-- SPDX-FileCopyrightText: 2023 Jonathan Frere
+--
+-- SPDX-License-Identifier: MPL-2.0
+SELECT
+  tags.id,
+  coalesce(
+    (
+      SELECT
+        tag_name
+      FROM
+        tag_translations
+      WHERE
+        tag_translations.tag_id = tags.id
+        AND tag_translations.lang = ?1
+    ),
+    -- fall back to any name rather than hiding the tag
+    (
+      SELECT
+        tag_name
+      FROM
+        tag_translations
+      WHERE
+        tag_translations.tag_id = tags.id
+      ORDER BY
+        lang
+      LIMIT
+        1
+    )
+  ) as name
+FROM
+  tags
+WHERE
+  tags.id = ?2
+  AND tags.household_id = ?3
\ No newline at end of file
@@ -2,16 +2,119 @@
 --
 -- SPDX-License-Identifier: MPL-2.0
 SELECT
-  id,
+  grouped_tasks.id,
   task_translations.task_name as name,
-  kind,
-  duration,
+  task_translations.description,
+  grouped_tasks.kind,
+  grouped_tasks.duration,
+  tasks.rotation,
+  tasks.catch_up,
+  tasks.recurrence,
+  tasks.active_window,
+  tasks.due_soon_days,
+  tasks.grace_days,
+  tasks.points,
   participants,
   last_completed,
-  last_completed_by
+  tasks.next_due,
+  assignee.username as assigned_to,
+  (
+    SELECT
+      json_group_array (json_object ('id', id, 'name', name))
+    FROM
+      (
+        SELECT
+          tags.id,
+          coalesce(
+            (
+              SELECT
+                tag_name
+              FROM
+                tag_translations
+              WHERE
+                tag_translations.tag_id = tags.id
+                AND tag_translations.lang = ?1
+            ),
+            (
+              SELECT
+                tag_name
+              FROM
+                tag_translations
+              WHERE
+                tag_translations.tag_id = tags.id
+              ORDER BY
+                lang
+              LIMIT
+                1
+            )
+          ) as name
+        FROM
+          task_tag_link
+          INNER JOIN tags ON tags.id = task_tag_link.tag_id
+        WHERE
+          task_tag_link.task_id = tasks.id
+        ORDER BY
+          name
+      )
+  ) as tags,
+  (
+    SELECT
+      json_group_array (json_object ('id', id, 'name', name))
+    FROM
+      (
+        SELECT
+          checklist_steps.id,
+          coalesce(
+            (
+              SELECT
+                step_name
+              FROM
+                checklist_step_translations
+              WHERE
+                checklist_step_translations.step_id = checklist_steps.id
+                AND checklist_step_translations.lang = ?1
+            ),
+            (
+              SELECT
+                step_name
+              FROM
+                checklist_step_translations
+              WHERE
+                checklist_step_translations.step_id = checklist_steps.id
+              ORDER BY
+                lang
+              LIMIT
+                1
+            )
+          ) as name
+        FROM
+          checklist_steps
+        WHERE
+          checklist_steps.task_id = tasks.id
+        ORDER BY
+          checklist_steps.position
+      )
+  ) as checklist,
+  (
+    SELECT
+      steps_done
+    FROM
+      completions
+    WHERE
+      completions.task_id = tasks.id
+      AND completions.initial = FALSE
+    ORDER BY
+      completions.completed_on DESC,
+      completions.id DESC
+    LIMIT
+      1
+  ) as last_steps_done
 FROM
   grouped_tasks
   INNER JOIN task_translations ON task_translations.task_id = grouped_tasks.id
-  AND task_translations.lang = ?
+  AND task_translations.lang = ?1
+  INNER JOIN tasks ON tasks.id = grouped_tasks.id
+  LEFT JOIN users assignee ON assignee.id = tasks.assigned_to
 WHERE
-  grouped_tasks.id = ?
\ No newline at end of file
+  grouped_tasks.id = ?2
+  AND tasks.household_id = ?3
\ No newline at end of file
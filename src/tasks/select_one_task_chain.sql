@@ -0,0 +1,15 @@
+-- SPDX-FileCopyrightText: 2023 Jonathan Frere
+--
+-- SPDX-License-Identifier: MPL-2.0
+SELECT
+  task_chains.id,
+  task_chains.from_task_id as from_task,
+  task_chains.to_task_id as to_task,
+  task_chains.offset_days,
+  task_chains.assignee
+FROM
+  task_chains
+  INNER JOIN tasks ON tasks.id = task_chains.from_task_id
+WHERE
+  task_chains.id = ?
+  AND tasks.household_id = ?
\ No newline at end of file
@@ -0,0 +1,21 @@
+-- SPDX-FileCopyrightText: 2023 Jonathan Frere
+--
+-- SPDX-License-Identifier: MPL-2.0
+SELECT
+  users.username AS person,
+  coalesce(sum(points_ledger.points), 0) AS points
+FROM
+  household_members
+  INNER JOIN users ON users.id = household_members.user_id
+  LEFT JOIN points_ledger ON points_ledger.user_id = users.id
+  AND points_ledger.household_id = household_members.household_id
+WHERE
+  household_members.household_id = ?1
+  AND (
+    ?2 IS NULL
+    OR users.username = ?2 COLLATE NOCASE
+  )
+GROUP BY
+  users.id
+ORDER BY
+  users.username
\ No newline at end of file
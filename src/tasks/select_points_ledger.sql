@@ -0,0 +1,23 @@
+-- SPDX-FileCopyrightText: 2023 Jonathan Frere
+--
+-- SPDX-License-Identifier: MPL-2.0
+SELECT
+  points_ledger.id,
+  users.username AS person,
+  points_ledger.entry_on,
+  points_ledger.kind,
+  points_ledger.points,
+  points_ledger.task_id,
+  points_ledger.description
+FROM
+  points_ledger
+  INNER JOIN users ON users.id = points_ledger.user_id
+WHERE
+  points_ledger.household_id = ?1
+  AND (
+    ?2 IS NULL
+    OR users.username = ?2 COLLATE NOCASE
+  )
+ORDER BY
+  points_ledger.entry_on,
+  points_ledger.id
\ No newline at end of file
@@ -0,0 +1,15 @@
+-- SPDX-FileCopyrightText: 2023 Jonathan Frere
+--
+-- SPDX-License-Identifier: MPL-2.0
+SELECT
+  completion_attachments.completion_id,
+  completion_attachments.id,
+  completion_attachments.content_type,
+  completion_attachments.size
+FROM
+  completion_attachments
+  INNER JOIN completions ON completions.id = completion_attachments.completion_id
+WHERE
+  completions.task_id = ?
+ORDER BY
+  completion_attachments.id
\ No newline at end of file
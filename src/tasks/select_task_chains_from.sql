@@ -0,0 +1,15 @@
+-- SPDX-FileCopyrightText: 2023 Jonathan Frere
+--
+-- SPDX-License-Identifier: MPL-2.0
+SELECT
+  id,
+  from_task_id as from_task,
+  to_task_id as to_task,
+  offset_days,
+  assignee
+FROM
+  task_chains
+WHERE
+  from_task_id = ?
+ORDER BY
+  id
\ No newline at end of file
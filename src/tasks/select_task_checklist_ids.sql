@@ -0,0 +1,11 @@
+-- SPDX-FileCopyrightText: 2023 Jonathan Frere
+--
+-- SPDX-License-Identifier: MPL-2.0
+SELECT
+  id
+FROM
+  checklist_steps
+WHERE
+  task_id = ?
+ORDER BY
+  position
\ No newline at end of file
@@ -0,0 +1,26 @@
+-- SPDX-FileCopyrightText: 2023 Jonathan Frere
+--
+-- SPDX-License-Identifier: MPL-2.0
+SELECT
+  completions.id AS id,
+  users.username AS completed_by,
+  (
+    SELECT
+      json_group_array (helper.username)
+    FROM
+      completion_helpers
+      INNER JOIN users helper ON helper.id = completion_helpers.user_id
+    WHERE
+      completion_helpers.completion_id = completions.id
+  ) AS helpers,
+  completions.completed_on,
+  completions.note
+FROM
+  completions
+  INNER JOIN users ON users.id = completions.completed_by
+WHERE
+  completions.initial = FALSE
+  AND completions.task_id = ?
+ORDER BY
+  completions.completed_on DESC,
+  completions.id DESC
\ No newline at end of file
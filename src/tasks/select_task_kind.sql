@@ -0,0 +1,10 @@
+-- SPDX-FileCopyrightText: 2023 Jonathan Frere
+--
+-- SPDX-License-Identifier: MPL-2.0
+SELECT
+  kind
+FROM
+  tasks
+WHERE
+  tasks.id = ?
+  AND tasks.household_id = ?
\ No newline at end of file
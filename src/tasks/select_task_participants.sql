@@ -0,0 +1,14 @@
+-- SPDX-FileCopyrightText: 2023 Jonathan Frere
+--
+-- SPDX-License-Identifier: MPL-2.0
+SELECT
+  users.username
+FROM
+  task_participant_link
+  INNER JOIN users ON users.id = task_participant_link.user_id
+  INNER JOIN tasks ON tasks.id = task_participant_link.task_id
+WHERE
+  task_participant_link.task_id = ?
+  AND tasks.household_id = ?
+ORDER BY
+  task_participant_link.position
\ No newline at end of file
@@ -0,0 +1,44 @@
+-- SPDX-FileCopyrightText: 2023 Jonathan Frere
+--
+-- SPDX-License-Identifier: MPL-2.0
+SELECT
+  grouped_tasks.id,
+  tasks.config_key,
+  tasks.archived_on,
+  (
+    SELECT
+      json_group_object (lang, task_name)
+    FROM
+      task_translations
+    WHERE
+      task_translations.task_id = tasks.id
+  ) AS names,
+  (
+    SELECT
+      json_group_object (lang, description)
+    FROM
+      task_translations
+    WHERE
+      task_translations.task_id = tasks.id
+      AND task_translations.description IS NOT NULL
+  ) AS descriptions,
+  grouped_tasks.kind,
+  grouped_tasks.duration,
+  tasks.rotation,
+  tasks.catch_up,
+  tasks.recurrence,
+  tasks.active_window,
+  tasks.due_soon_days,
+  tasks.grace_days,
+  tasks.points,
+  tasks.due_on,
+  claimant.username AS claimed_by,
+  participants
+FROM
+  grouped_tasks
+  INNER JOIN tasks ON tasks.id = grouped_tasks.id
+  LEFT JOIN users claimant ON claimant.id = tasks.claimed_by
+WHERE
+  tasks.household_id = ?
+ORDER BY
+  grouped_tasks.id
\ No newline at end of file
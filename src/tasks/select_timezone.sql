@@ -0,0 +1,21 @@
+-- SPDX-FileCopyrightText: 2023 Jonathan Frere
+--
+-- SPDX-License-Identifier: MPL-2.0
+SELECT
+  coalesce(
+    (
+      SELECT
+        users.timezone
+      FROM
+        users
+        INNER JOIN household_members ON household_members.user_id = users.id
+      WHERE
+        users.username = ?1 COLLATE NOCASE
+        AND household_members.household_id = ?2
+    ),
+    households.timezone
+  )
+FROM
+  households
+WHERE
+  households.id = ?2
\ No newline at end of file
@@ -0,0 +1,342 @@
+// SPDX-FileCopyrightText: 2023 Jonathan Frere
+//
+// SPDX-License-Identifier: MPL-2.0
+
+use std::collections::BTreeMap;
+
+use chrono::{Datelike, Duration, NaiveDate};
+
+use super::types::{Deadline, TaskId, Urgency};
+
+/// How completions are grouped when counting them per person.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Deserialize)]
+pub enum StatsPeriod {
+    /// Weeks starting on Monday
+    #[default]
+    Week,
+    Month,
+}
+
+impl StatsPeriod {
+    fn start_of(self, date: NaiveDate) -> NaiveDate {
+        match self {
+            StatsPeriod::Week => {
+                date - Duration::days(date.weekday().num_days_from_monday().into())
+            }
+            StatsPeriod::Month => date.with_day(1).unwrap(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct Stats {
+    pub people: Vec<PersonStats>,
+    pub tasks: Vec<TaskStats>,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct PersonStats {
+    pub person: String,
+    /// Number of completions in each period the person did anything in
+    pub completions: Vec<PeriodCompletions>,
+    #[serde(flatten)]
+    pub punctuality: Punctuality,
+    /// Number of on-time completions since the person was last late
+    pub current_streak: u32,
+    pub longest_streak: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct TaskStats {
+    pub id: TaskId,
+    pub name: String,
+    #[serde(flatten)]
+    pub punctuality: Punctuality,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct PeriodCompletions {
+    pub period_start: NaiveDate,
+    pub count: u32,
+}
+
+/// Completions without a due date (e.g. one-off tasks that could be done
+/// whenever) are neither on time nor late, so are not counted here.
+#[derive(Debug, Clone, PartialEq, Default, serde::Deserialize, serde::Serialize)]
+pub struct Punctuality {
+    pub on_time: u32,
+    pub late: u32,
+    pub on_time_ratio: Option<f64>,
+    /// How many days late the late completions were, on average
+    pub average_days_late: Option<f64>,
+}
+
+impl Punctuality {
+    fn from_lateness(lateness: impl Iterator<Item = Option<u16>>) -> Self {
+        let mut punctuality = Self::default();
+        let mut total_days_late = 0u32;
+        for days_late in lateness.flatten() {
+            if days_late == 0 {
+                punctuality.on_time += 1;
+            } else {
+                punctuality.late += 1;
+                total_days_late += u32::from(days_late);
+            }
+        }
+
+        let scored = punctuality.on_time + punctuality.late;
+        if scored > 0 {
+            punctuality.on_time_ratio = Some(f64::from(punctuality.on_time) / f64::from(scored));
+        }
+        if punctuality.late > 0 {
+            punctuality.average_days_late =
+                Some(f64::from(total_days_late) / f64::from(punctuality.late));
+        }
+        punctuality
+    }
+}
+
+/// A completion along with the date it was due on when it was done.
+pub(super) struct ScoredCompletion {
+    pub task_id: TaskId,
+    pub completed_by: String,
+    pub helpers: Vec<String>,
+    pub completed_on: NaiveDate,
+    pub due: Option<NaiveDate>,
+}
+
+impl ScoredCompletion {
+    /// Days late, using the same rules as a task's deadline, or `None` if the
+    /// completion had no due date.
+    fn days_late(&self) -> Option<u16> {
+        self.due.map(
+            |due| match Deadline::new(due - self.completed_on, Urgency::default()) {
+                Deadline::Overdue(days) | Deadline::BadlyOverdue(days) => days,
+                _ => 0,
+            },
+        )
+    }
+}
+
+/// Works out the statistics for the given completions, which must be in date
+/// order.  Everyone who did a task together gets the credit for it, but it
+/// only counts once towards the task's own statistics.
+pub(super) fn calculate(
+    completions: &[ScoredCompletion],
+    tasks: Vec<(TaskId, String)>,
+    period: StatsPeriod,
+) -> Stats {
+    let mut by_person = BTreeMap::<String, (&str, Vec<&ScoredCompletion>)>::new();
+    for completion in completions {
+        for person in std::iter::once(&completion.completed_by).chain(&completion.helpers) {
+            by_person
+                .entry(person.to_lowercase())
+                .or_insert_with(|| (person, Vec::new()))
+                .1
+                .push(completion);
+        }
+    }
+
+    let people = by_person
+        .into_values()
+        .map(|(person, completions)| person_stats(person, &completions, period))
+        .collect();
+
+    let tasks = tasks
+        .into_iter()
+        .map(|(id, name)| TaskStats {
+            id,
+            name,
+            punctuality: Punctuality::from_lateness(
+                completions
+                    .iter()
+                    .filter(|completion| completion.task_id == id)
+                    .map(ScoredCompletion::days_late),
+            ),
+        })
+        .collect();
+
+    Stats { people, tasks }
+}
+
+fn person_stats(
+    person: &str,
+    completions: &[&ScoredCompletion],
+    period: StatsPeriod,
+) -> PersonStats {
+    let mut periods = BTreeMap::<NaiveDate, u32>::new();
+    for completion in completions {
+        *periods
+            .entry(period.start_of(completion.completed_on))
+            .or_default() += 1;
+    }
+
+    let mut current_streak = 0;
+    let mut longest_streak = 0;
+    for days_late in completions.iter().filter_map(|c| c.days_late()) {
+        if days_late == 0 {
+            current_streak += 1;
+            longest_streak = longest_streak.max(current_streak);
+        } else {
+            current_streak = 0;
+        }
+    }
+
+    PersonStats {
+        person: person.to_owned(),
+        completions: periods
+            .into_iter()
+            .map(|(period_start, count)| PeriodCompletions {
+                period_start,
+                count,
+            })
+            .collect(),
+        punctuality: Punctuality::from_lateness(
+            completions.iter().map(|completion| completion.days_late()),
+        ),
+        current_streak,
+        longest_streak,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2020, 1, day).unwrap()
+    }
+
+    fn completion(task_id: i32, by: &str, on: u32, due: Option<u32>) -> ScoredCompletion {
+        ScoredCompletion {
+            task_id: task_id.into(),
+            completed_by: by.into(),
+            helpers: Vec::new(),
+            completed_on: date(on),
+            due: due.map(date),
+        }
+    }
+
+    #[test]
+    fn empty_history_gives_empty_stats() {
+        let stats = calculate(&[], vec![(1.into(), "Task".into())], StatsPeriod::Week);
+        assert_eq!(stats.people, vec![]);
+        assert_eq!(stats.tasks[0].punctuality, Punctuality::default());
+    }
+
+    #[test]
+    fn counts_completions_per_week_and_month() {
+        let completions = [
+            completion(1, "arthur", 5, None),
+            completion(1, "arthur", 6, None),
+            completion(1, "arthur", 7, None),
+            completion(1, "arthur", 13, None),
+        ];
+
+        let stats = calculate(&completions, vec![], StatsPeriod::Week);
+        assert_eq!(stats.people.len(), 1);
+        assert_eq!(
+            stats.people[0].completions,
+            vec![
+                PeriodCompletions {
+                    period_start: NaiveDate::from_ymd_opt(2019, 12, 30).unwrap(),
+                    count: 1
+                },
+                PeriodCompletions {
+                    period_start: date(6),
+                    count: 2
+                },
+                PeriodCompletions {
+                    period_start: date(13),
+                    count: 1
+                },
+            ]
+        );
+
+        let stats = calculate(&completions, vec![], StatsPeriod::Month);
+        assert_eq!(
+            stats.people[0].completions,
+            vec![PeriodCompletions {
+                period_start: date(1),
+                count: 4
+            }]
+        );
+    }
+
+    #[test]
+    fn scores_punctuality_per_person_and_task() {
+        let completions = [
+            completion(1, "arthur", 5, Some(5)),
+            completion(2, "arthur", 6, Some(3)),
+            completion(1, "bob", 8, Some(7)),
+            completion(2, "bob", 9, Some(12)),
+            completion(3, "bob", 10, None),
+        ];
+
+        let stats = calculate(
+            &completions,
+            vec![(1.into(), "Task 1".into()), (2.into(), "Task 2".into())],
+            StatsPeriod::Week,
+        );
+
+        let arthur = &stats.people[0].punctuality;
+        assert_eq!((arthur.on_time, arthur.late), (1, 1));
+        assert_eq!(arthur.on_time_ratio, Some(0.5));
+        assert_eq!(arthur.average_days_late, Some(3.0));
+
+        let bob = &stats.people[1].punctuality;
+        assert_eq!((bob.on_time, bob.late), (1, 1));
+        assert_eq!(bob.average_days_late, Some(1.0));
+
+        let task_1 = &stats.tasks[0].punctuality;
+        assert_eq!((task_1.on_time, task_1.late), (1, 1));
+        assert_eq!(task_1.average_days_late, Some(1.0));
+        let task_2 = &stats.tasks[1].punctuality;
+        assert_eq!(task_2.average_days_late, Some(3.0));
+    }
+
+    #[test]
+    fn credits_everyone_who_did_a_task_together() {
+        let completions = [
+            completion(1, "arthur", 5, Some(5)),
+            ScoredCompletion {
+                helpers: vec!["bob".into(), "claire".into()],
+                ..completion(1, "arthur", 8, Some(7))
+            },
+        ];
+
+        let stats = calculate(
+            &completions,
+            vec![(1.into(), "Task".into())],
+            StatsPeriod::Week,
+        );
+        let people = stats
+            .people
+            .iter()
+            .map(|person| (person.person.as_str(), person.completions[0].count))
+            .collect::<Vec<_>>();
+        assert_eq!(people, vec![("arthur", 1), ("bob", 1), ("claire", 1)]);
+        assert_eq!(stats.people[0].punctuality.late, 1);
+        assert_eq!(stats.people[2].punctuality.late, 1);
+
+        // the task itself was only done twice
+        let task = &stats.tasks[0].punctuality;
+        assert_eq!((task.on_time, task.late), (1, 1));
+    }
+
+    #[test]
+    fn tracks_current_and_longest_streaks() {
+        let completions = [
+            completion(1, "arthur", 1, Some(1)),
+            completion(1, "arthur", 2, Some(2)),
+            completion(1, "arthur", 3, Some(3)),
+            completion(1, "arthur", 5, Some(4)),
+            completion(1, "arthur", 6, None),
+            completion(1, "arthur", 7, Some(8)),
+        ];
+
+        let stats = calculate(&completions, vec![], StatsPeriod::Week);
+        assert_eq!(stats.people[0].current_streak, 1);
+        assert_eq!(stats.people[0].longest_streak, 3);
+    }
+}
@@ -5,13 +5,24 @@
 use std::collections::HashMap;
 
 use chrono::{Duration, NaiveDate};
-use sqlx::{types::Json, SqlitePool};
+use sqlx::{types::Json, SqliteConnection, SqlitePool};
 
-use crate::translations::Language;
+use crate::{auth::HouseholdId, db, translations::Language};
 
 use super::{
-    time::today,
-    types::{Deadline, Routine, Task, TaskId},
+    attachments::{self, NewAttachment, MAX_ATTACHMENTS, MAX_ATTACHMENT_SIZE},
+    calendar, points,
+    recurrence::Recurrence,
+    rotation::{furthest_along, next_assignee, round_robin},
+    season::ActiveWindow,
+    stats::{self, ScoredCompletion, Stats, StatsPeriod},
+    time::{now, today_in, Timezone},
+    types::{
+        Absence, AbsenceId, Attachment, AttachmentId, Balance, CatchUp, Chain, ChainAssignee,
+        ChainId, ChecklistStep, Completion, CompletionId, Deadline, DueStatus, Feed, FeedId,
+        ForecastEntry, HistoryEntry, LedgerEntry, LedgerEntryId, LedgerKind, Rotation, Routine,
+        SortOrder, StepId, Tag, TagId, Task, TaskId, TaskSort, Urgency,
+    },
 };
 
 #[derive(thiserror::Error, Debug)]
@@ -19,6 +30,8 @@ pub enum TaskStoreError {
     // 500 type errors (it's probably our fault)
     #[error("underlying data could not be accessed or saved")]
     DbError(#[from] sqlx::Error),
+    #[error("attachment could not be read or saved")]
+    FileError(#[from] std::io::Error),
 
     // 400 type errors (it's probably your fault)
     #[error("unknown task name was used")]
@@ -27,40 +40,171 @@ pub enum TaskStoreError {
     UnknownTaskId(TaskId),
     #[error("person does not exist")]
     PersonDoesNotExist(String),
+    #[error("person is not a participant in this task")]
+    PersonNotParticipant(String),
+    #[error("tasks cannot be marked as done in the future")]
+    CompletionInFuture(NaiveDate),
+    #[error("tasks cannot be marked as done before they started")]
+    CompletionBeforeStart(NaiveDate),
+    #[error("tasks can be marked as done at most {0} days ago")]
+    CompletionTooOld(u16),
+    #[error("calendar routines need a recurrence rule, other routines cannot have one")]
+    RecurrenceMismatch,
+    #[error("recurrence rule never falls on any date")]
+    RecurrenceNeverOccurs,
+    #[error("task needs at least one participant")]
+    NoParticipants,
+    #[error("only one-off tasks can be claimed")]
+    TaskNotClaimable(TaskId),
+    #[error("unknown tag was used")]
+    UnknownTagId(TagId),
+    #[error("tag needs a name in at least one language")]
+    NoTagNames,
+    #[error("checklist step does not belong to this task")]
+    UnknownStep(StepId),
+    #[error("unknown absence was used")]
+    UnknownAbsenceId(AbsenceId),
+    #[error("absence ends before it starts")]
+    AbsenceEndsBeforeStart,
+    #[error("new order must contain each of the task's participants exactly once")]
+    ParticipantOrderMismatch,
+    #[error("only recurring tasks can be limited to part of the year")]
+    ActiveWindowMismatch,
+    #[error("unknown chain was used")]
+    UnknownChainId(ChainId),
+    #[error("only triggered tasks can follow on from another task")]
+    ChainTargetNotTriggered(TaskId),
+    #[error("forecasts can cover at most 52 weeks")]
+    ForecastTooLong,
+    #[error("unknown calendar feed was used")]
+    UnknownFeedId(FeedId),
+    #[error("unknown calendar feed was used")]
+    UnknownFeedSecret,
+    #[error("unknown attachment was used")]
+    UnknownAttachmentId(AttachmentId),
+    #[error("only images can be attached")]
+    NotAnImage(String),
+    #[error("attached images can be at most 5 MB")]
+    AttachmentTooLarge,
+    #[error("at most 4 images can be attached")]
+    TooManyAttachments,
+    #[error("person does not have enough points")]
+    NotEnoughPoints(String),
+    #[error("at least one point has to be redeemed")]
+    InvalidRedemption(i32),
+    #[error("unknown points ledger entry was used")]
+    UnknownLedgerEntryId(LedgerEntryId),
+    #[error("unknown task template was used: {0}")]
+    UnknownTemplate(String),
+    #[error("imported tasks need an initial completion to say when they started")]
+    NoInitialCompletion,
 }
 
+/// How far ahead calendar feeds show tasks.
+const FEED_WEEKS: u16 = 8;
+
+/// Reads and changes the tasks of a single household.  Nothing belonging to
+/// another household can be seen or changed through it.
 #[derive(Clone)]
 pub struct TaskStore {
     conn: SqlitePool,
+    household: HouseholdId,
 }
 
 impl TaskStore {
-    pub fn new(conn: SqlitePool) -> Self {
-        Self { conn }
+    pub fn new(conn: SqlitePool, household: HouseholdId) -> Self {
+        Self { conn, household }
     }
 
-    pub async fn add_task(&self, mut new_task: NewTask) -> Result<(), TaskStoreError> {
+    pub async fn add_task(&self, mut new_task: NewTask) -> Result<TaskId, TaskStoreError> {
+        if new_task.participants.is_empty() {
+            Err(TaskStoreError::NoParticipants)?;
+        }
+
+        let is_participant = |name: &str| {
+            new_task
+                .participants
+                .iter()
+                .any(|person| person.to_lowercase() == name.to_lowercase())
+        };
+
+        // one-off tasks with no assignee can be claimed by any participant
+        let unassigned = new_task.routine == Routine::OneOff && new_task.starts_with.is_empty();
+        if !unassigned && !is_participant(&new_task.starts_with) {
+            Err(TaskStoreError::PersonNotParticipant(
+                new_task.starts_with.to_owned(),
+            ))?;
+        }
+
+        if let Rotation::FixedOwner(owner) = &new_task.rotation {
+            if !is_participant(owner) {
+                Err(TaskStoreError::PersonNotParticipant(owner.to_owned()))?;
+            }
+        }
+
+        check_routine(
+            new_task.routine,
+            new_task.recurrence.as_ref(),
+            new_task.active_window.as_ref(),
+            new_task.starts_on,
+        )?;
+
         let mut transaction = self.conn.begin().await?;
 
-        let (task_id,) = sqlx::query_as::<_, (TaskId,)>(include_str!("./insert_new_task.sql"))
-            .bind(new_task.routine)
-            .bind(new_task.duration)
-            .fetch_one(&mut transaction)
-            .await?;
+        let task_id: TaskId = db::insert_returning_id(
+            &mut transaction,
+            sqlx::query_as(include_str!("./insert_new_task.sql"))
+                .bind(new_task.routine)
+                .bind(new_task.duration)
+                .bind(Json(&new_task.rotation))
+                .bind(new_task.catch_up)
+                .bind(&new_task.recurrence)
+                .bind(new_task.due_on)
+                .bind(new_task.active_window)
+                .bind(new_task.urgency.due_soon_days)
+                .bind(new_task.urgency.grace_days)
+                .bind(new_task.points)
+                .bind(&new_task.config_key)
+                .bind(self.household),
+        )
+        .await?
+        .ok_or(sqlx::Error::RowNotFound)?;
 
         for (lang, name) in new_task.names.drain() {
+            let description = new_task.descriptions.remove(&lang);
             sqlx::query(include_str!("./insert_new_task_name.sql"))
                 .bind(task_id)
                 .bind(lang)
                 .bind(name)
+                .bind(description)
                 .execute(&mut transaction)
                 .await?;
         }
 
+        for (position, step_names) in new_task.checklist.drain(..).enumerate() {
+            let step_id: StepId = db::insert_returning_id(
+                &mut transaction,
+                sqlx::query_as(include_str!("./insert_new_checklist_step.sql"))
+                    .bind(task_id)
+                    .bind(position as u32),
+            )
+            .await?
+            .ok_or(sqlx::Error::RowNotFound)?;
+            for (lang, name) in step_names {
+                sqlx::query(include_str!("./insert_new_checklist_step_name.sql"))
+                    .bind(step_id)
+                    .bind(lang)
+                    .bind(name)
+                    .execute(&mut transaction)
+                    .await?;
+            }
+        }
+
         for person in &new_task.participants {
             let result = sqlx::query(include_str!("./insert_new_task_participant.sql"))
                 .bind(task_id)
                 .bind(person)
+                .bind(self.household)
                 .execute(&mut transaction)
                 .await?;
 
@@ -69,9 +213,24 @@ impl TaskStore {
             }
         }
 
+        if new_task.routine == Routine::OneOff {
+            let claimed_by = match new_task.participants.as_slice() {
+                [only_person] => only_person,
+                _ => &new_task.starts_with,
+            };
+            sqlx::query(include_str!("./update_task_claim.sql"))
+                .bind(claimed_by)
+                .bind(task_id)
+                .execute(&mut transaction)
+                .await?;
+        }
+
         new_task.participants.reverse();
 
-        let prev_person = next_assignee(&new_task.participants, &new_task.starts_with);
+        let prev_person = match new_task.routine {
+            Routine::OneOff => &new_task.participants[0],
+            _ => round_robin(&new_task.participants, &new_task.starts_with),
+        };
         let started_time = new_task.starts_on - Duration::days(new_task.duration.into());
         sqlx::query(include_str!("./insert_new_task_first_completion.sql"))
             .bind(task_id)
@@ -80,40 +239,75 @@ impl TaskStore {
             .execute(&mut transaction)
             .await?;
 
+        refresh_schedule(&mut transaction, task_id).await?;
+
+        transaction.commit().await?;
+
+        Ok(task_id)
+    }
+
+    /// Recalculates the stored due date and assignee of every task.  These are
+    /// normally updated whenever a task changes, so this is only needed after
+    /// migrating an existing database.
+    pub async fn refresh_schedules(&self) -> Result<(), TaskStoreError> {
+        let mut transaction = self.conn.begin().await?;
+        refresh_all_schedules(&mut transaction, self.household).await?;
         transaction.commit().await?;
 
         Ok(())
     }
 
     pub async fn tasks(&self, language: &Language) -> Result<Vec<Task>, TaskStoreError> {
-        let rows = sqlx::query_as::<
-            _,
-            (
-                TaskId,
-                String,
-                Routine,
-                u16,
-                Json<Vec<String>>,
-                NaiveDate,
-                String,
-            ),
-        >(include_str!("./select_all_tasks.sql"))
-        .bind(language.to_string())
-        .fetch_all(&self.conn)
-        .await?;
+        self.find_tasks(&TaskQuery::default(), language).await
+    }
+
+    pub async fn find_tasks(
+        &self,
+        query: &TaskQuery,
+        language: &Language,
+    ) -> Result<Vec<Task>, TaskStoreError> {
+        // tasks for a particular person are shown as of their own today
+        let today = self.today_for(query.assignee.as_deref()).await?;
+        let rows = sqlx::query_as::<_, TaskRow>(include_str!("./select_all_tasks.sql"))
+            .bind(language.to_string())
+            .bind(&query.assignee)
+            .bind(&query.participant)
+            .bind(query.routine)
+            .bind(query.status)
+            .bind(today)
+            .bind(query.due_from)
+            .bind(query.due_until)
+            .bind(query.search.as_deref().map(escape_like))
+            .bind(query.sort)
+            .bind(query.order)
+            .bind(query.tag)
+            .bind(self.household)
+            .fetch_all(&self.conn)
+            .await?;
+
+        // due dates can move on when tasks are read, so the ones that matter
+        // to the query are checked again
+        let mut rows = rows
+            .into_iter()
+            .map(|row| (current_due(&row, today), row))
+            .filter(|(due, _)| query.matches_due(*due, today))
+            .collect::<Vec<_>>();
+        if query.sort == Some(TaskSort::Deadline) {
+            rows.sort_by(|(left_due, left), (right_due, right)| {
+                let by_due = left_due
+                    .unwrap_or(NaiveDate::MAX)
+                    .cmp(&right_due.unwrap_or(NaiveDate::MAX));
+                match query.order {
+                    SortOrder::Ascending => by_due,
+                    SortOrder::Descending => by_due.reverse(),
+                }
+                .then(left.id.cmp(&right.id))
+            });
+        }
 
         Ok(rows
             .into_iter()
-            .map(|row| Task {
-                id: row.0,
-                name: row.1,
-                kind: row.2,
-                length_days: row.3,
-                assigned_to: next_assignee(&row.4, &row.6).into(),
-                participants: row.4 .0,
-                last_completed: row.5,
-                deadline: next_deadline(row.3, row.5),
-            })
+            .map(|(_, row)| task_from_row(row, today))
             .collect())
     }
 
@@ -122,503 +316,4720 @@ impl TaskStore {
         person: &str,
         language: &Language,
     ) -> Result<Vec<Task>, TaskStoreError> {
-        let person = person.to_lowercase();
-        Ok(self
-            .tasks(language)
-            .await?
-            .into_iter()
-            .filter(|task| task.assigned_to.to_lowercase() == person)
-            .collect())
+        let query = TaskQuery {
+            assignee: Some(person.to_owned()),
+            ..Default::default()
+        };
+        self.find_tasks(&query, language).await
     }
 
     pub async fn task(&self, task_id: TaskId, language: &Language) -> Result<Task, TaskStoreError> {
-        let row = sqlx::query_as::<
-            _,
-            (
-                TaskId,
-                String,
-                Routine,
-                u16,
-                Json<Vec<String>>,
-                NaiveDate,
-                String,
-            ),
-        >(include_str!("./select_one_task.sql"))
-        .bind(language.to_string())
-        .bind(task_id)
-        .fetch_optional(&self.conn)
-        .await?;
+        let today = self.today_for(None).await?;
+        let row = sqlx::query_as::<_, TaskRow>(include_str!("./select_one_task.sql"))
+            .bind(language.to_string())
+            .bind(task_id)
+            .bind(self.household)
+            .fetch_optional(&self.conn)
+            .await?;
 
-        let row = match row {
-            Some(row) => row,
-            None => Err(TaskStoreError::UnknownTaskId(task_id))?,
-        };
+        match row {
+            Some(row) => Ok(task_from_row(row, today)),
+            None => Err(TaskStoreError::UnknownTaskId(task_id)),
+        }
+    }
 
-        Ok(Task {
-            id: row.0,
-            name: row.1,
-            kind: row.2,
-            length_days: row.3,
-            assigned_to: next_assignee(&row.4, &row.6).into(),
-            participants: row.4 .0,
-            last_completed: row.5,
-            deadline: next_deadline(row.3, row.5),
-        })
+    /// The timezone that decides when each day starts, unless a person has set
+    /// their own.  `None` means the server's timezone.
+    pub async fn household_timezone(&self) -> Result<Option<Timezone>, TaskStoreError> {
+        self.timezone_for(None).await
     }
 
-    pub async fn mark_task_done(
+    pub async fn set_household_timezone(
+        &self,
+        timezone: Option<Timezone>,
+    ) -> Result<(), TaskStoreError> {
+        sqlx::query(include_str!("./update_household_timezone.sql"))
+            .bind(timezone)
+            .bind(self.household)
+            .execute(&self.conn)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Sets the timezone that decides when a person's day starts, or makes
+    /// them use the household's timezone again if `timezone` is `None`.
+    pub async fn set_person_timezone(
         &self,
-        task_id: TaskId,
         person: &str,
-        date: &NaiveDate,
+        timezone: Option<Timezone>,
     ) -> Result<(), TaskStoreError> {
-        sqlx::query(include_str!("./insert_completion.sql"))
-            .bind(task_id)
-            .bind(date)
+        let result = sqlx::query(include_str!("./update_person_timezone.sql"))
+            .bind(timezone)
             .bind(person)
+            .bind(self.household)
             .execute(&self.conn)
             .await?;
+        if result.rows_affected() == 0 {
+            Err(TaskStoreError::PersonDoesNotExist(person.to_owned()))?;
+        }
+
         Ok(())
     }
-}
 
-fn next_assignee<'a>(participants: &'a [String], last_completed_by: &str) -> &'a str {
-    let mut participants_iter = participants.iter();
-    while let Some(person) = participants_iter.next() {
-        if person == last_completed_by {
-            return participants_iter.next().unwrap_or(&participants[0]);
+    /// The timezone used for a person (or the household, if `person` is
+    /// `None`), falling back to the household's timezone if they haven't set
+    /// one.
+    pub async fn timezone_for(
+        &self,
+        person: Option<&str>,
+    ) -> Result<Option<Timezone>, TaskStoreError> {
+        let (timezone,) =
+            sqlx::query_as::<_, (Option<Timezone>,)>(include_str!("./select_timezone.sql"))
+                .bind(person)
+                .bind(self.household)
+                .fetch_one(&self.conn)
+                .await?;
+
+        Ok(timezone)
+    }
+
+    /// Today's date for a person (or the household, if `person` is `None`).
+    pub async fn today_for(&self, person: Option<&str>) -> Result<NaiveDate, TaskStoreError> {
+        Ok(today_in(self.timezone_for(person).await?))
+    }
+
+    /// How many days ago a task can be marked as done, or `None` if there is
+    /// no limit.
+    pub async fn backdate_limit(&self) -> Result<Option<u16>, TaskStoreError> {
+        let (days,) =
+            sqlx::query_as::<_, (Option<u16>,)>(include_str!("./select_backdate_limit.sql"))
+                .bind(self.household)
+                .fetch_one(&self.conn)
+                .await?;
+
+        Ok(days)
+    }
+
+    pub async fn set_backdate_limit(&self, days: Option<u16>) -> Result<(), TaskStoreError> {
+        sqlx::query(include_str!("./update_backdate_limit.sql"))
+            .bind(days)
+            .bind(self.household)
+            .execute(&self.conn)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn add_tag(&self, new_tag: NewTag) -> Result<TagId, TaskStoreError> {
+        if new_tag.names.is_empty() {
+            Err(TaskStoreError::NoTagNames)?;
         }
+
+        let mut transaction = self.conn.begin().await?;
+
+        let tag_id: TagId = db::insert_returning_id(
+            &mut transaction,
+            sqlx::query_as(include_str!("./insert_new_tag.sql")).bind(self.household),
+        )
+        .await?
+        .ok_or(sqlx::Error::RowNotFound)?;
+        insert_tag_names(&mut transaction, tag_id, new_tag.names).await?;
+
+        transaction.commit().await?;
+
+        Ok(tag_id)
     }
 
-    unreachable!("Invalid Data")
-}
+    pub async fn tags(&self, language: &Language) -> Result<Vec<Tag>, TaskStoreError> {
+        Ok(
+            sqlx::query_as::<_, Tag>(include_str!("./select_all_tags.sql"))
+                .bind(language.to_string())
+                .bind(self.household)
+                .fetch_all(&self.conn)
+                .await?,
+        )
+    }
 
-fn next_deadline(task_length: u16, last_completed: NaiveDate) -> Deadline {
-    (last_completed + Duration::days(task_length.into()) - today()).into()
-}
+    pub async fn tag(&self, tag_id: TagId, language: &Language) -> Result<Tag, TaskStoreError> {
+        sqlx::query_as::<_, Tag>(include_str!("./select_one_tag.sql"))
+            .bind(language.to_string())
+            .bind(tag_id)
+            .bind(self.household)
+            .fetch_optional(&self.conn)
+            .await?
+            .ok_or(TaskStoreError::UnknownTagId(tag_id))
+    }
 
-pub struct NewTask {
-    pub names: HashMap<String, String>,
-    pub routine: Routine,
-    pub duration: u16,
-    pub participants: Vec<String>,
-    pub starts_on: NaiveDate,
-    pub starts_with: String,
-}
+    /// Replaces all of a tag's names with the given ones.
+    pub async fn update_tag(&self, tag_id: TagId, new_tag: NewTag) -> Result<(), TaskStoreError> {
+        if new_tag.names.is_empty() {
+            Err(TaskStoreError::NoTagNames)?;
+        }
+        self.tag(tag_id, &Language::default()).await?;
 
-#[cfg(test)]
-mod tests {
-    use crate::{auth::AuthStore, tasks::time};
+        let mut transaction = self.conn.begin().await?;
 
-    use super::*;
+        sqlx::query(include_str!("./delete_tag_names.sql"))
+            .bind(tag_id)
+            .execute(&mut transaction)
+            .await?;
+        insert_tag_names(&mut transaction, tag_id, new_tag.names).await?;
 
-    fn names(names: &[(&str, &str)]) -> HashMap<String, String> {
-        names
-            .iter()
-            .map(|(l, r)| (l.to_string(), r.to_string()))
-            .collect()
+        transaction.commit().await?;
+
+        Ok(())
     }
 
-    #[sqlx::test]
-    async fn listing_tasks_for_empty_store_gives_no_results(conn: sqlx::SqlitePool) {
-        let task_store = TaskStore::new(conn);
-        assert_eq!(task_store.tasks(&"en".into()).await.unwrap(), vec![]);
+    pub async fn delete_tag(&self, tag_id: TagId) -> Result<(), TaskStoreError> {
+        self.tag(tag_id, &Language::default()).await?;
+
+        let mut transaction = self.conn.begin().await?;
+
+        for statement in [
+            include_str!("./delete_tag_links.sql"),
+            include_str!("./delete_tag_names.sql"),
+        ] {
+            sqlx::query(statement)
+                .bind(tag_id)
+                .execute(&mut transaction)
+                .await?;
+        }
+        let result = sqlx::query(include_str!("./delete_tag.sql"))
+            .bind(tag_id)
+            .execute(&mut transaction)
+            .await?;
+        if result.rows_affected() == 0 {
+            Err(TaskStoreError::UnknownTagId(tag_id))?;
+        }
+
+        transaction.commit().await?;
+
+        Ok(())
     }
 
-    #[sqlx::test]
-    async fn listing_tasks_when_tasks_exist(conn: sqlx::SqlitePool) {
-        time::mock::set(NaiveDate::from_ymd_opt(2020, 1, 10).unwrap());
-        let task_store = TaskStore::new(conn.clone());
-        let auth_store = AuthStore::new(conn);
-        auth_store.create_test_user("arthur").await.unwrap();
-        auth_store.create_test_user("bob").await.unwrap();
-        auth_store.create_test_user("claire").await.unwrap();
-        task_store
-            .add_task(NewTask {
-                names: names(&[("en", "Test Task 1")]),
-                starts_with: "bob".into(),
-                routine: Routine::Interval,
-                duration: 7,
-                starts_on: NaiveDate::from_ymd_opt(2020, 1, 10).unwrap(),
-                participants: vec!["arthur".into(), "bob".into(), "claire".into()],
-            })
-            .await
-            .unwrap();
-        task_store
-            .add_task(NewTask {
-                names: names(&[("en", "Test Task 2")]),
-                starts_with: "claire".into(),
-                routine: Routine::Interval,
-                duration: 7,
-                starts_on: NaiveDate::from_ymd_opt(2020, 1, 14).unwrap(),
-                participants: vec!["arthur".into(), "bob".into(), "claire".into()],
-            })
-            .await
-            .unwrap();
+    pub async fn tag_task(&self, task_id: TaskId, tag_id: TagId) -> Result<(), TaskStoreError> {
+        self.ensure_task_exists(task_id).await?;
+        self.tag(tag_id, &Language::default()).await?;
 
-        let tasks = task_store.tasks(&"en".into()).await.unwrap();
-        assert_eq!(tasks.len(), 2);
-        assert_eq!(tasks[0].name, "Test Task 1".to_owned());
-        assert_eq!(tasks[1].name, "Test Task 2".to_owned());
+        sqlx::query(include_str!("./insert_task_tag.sql"))
+            .bind(task_id)
+            .bind(tag_id)
+            .execute(&self.conn)
+            .await?;
+        Ok(())
     }
 
-    #[sqlx::test]
-    async fn returns_tasks_for_a_particular_person(conn: sqlx::SqlitePool) {
+    pub async fn untag_task(&self, task_id: TaskId, tag_id: TagId) -> Result<(), TaskStoreError> {
+        self.ensure_task_exists(task_id).await?;
+
+        sqlx::query(include_str!("./delete_task_tag.sql"))
+            .bind(task_id)
+            .bind(tag_id)
+            .execute(&self.conn)
+            .await?;
+        Ok(())
+    }
+
+    async fn ensure_task_exists(&self, task_id: TaskId) -> Result<(), TaskStoreError> {
+        sqlx::query(include_str!("./select_task_kind.sql"))
+            .bind(task_id)
+            .bind(self.household)
+            .fetch_optional(&self.conn)
+            .await?
+            .ok_or(TaskStoreError::UnknownTaskId(task_id))?;
+        Ok(())
+    }
+
+    pub async fn stats(
+        &self,
+        period: StatsPeriod,
+        language: &Language,
+    ) -> Result<Stats, TaskStoreError> {
+        let rows =
+            sqlx::query_as::<_, StatsTaskRow>(include_str!("./select_all_task_schedules.sql"))
+                .bind(language.to_string())
+                .bind(self.household)
+                .fetch_all(&self.conn)
+                .await?;
+
+        let mut histories = HashMap::<TaskId, Vec<Completion>>::new();
+        for (task_id, completed_by, completed_on, helpers) in
+            sqlx::query_as::<_, CompletionRow>(include_str!("./select_all_completions.sql"))
+                .bind(self.household)
+                .fetch_all(&self.conn)
+                .await?
+        {
+            histories.entry(task_id).or_default().push(Completion {
+                completed_by,
+                helpers: helpers.0,
+                completed_on,
+            });
+        }
+
+        // replay each task's history to find out when each completion was due
+        let mut completions = Vec::new();
+        for row in &rows {
+            let history = histories.remove(&row.id).unwrap_or_default();
+            for (index, completion) in history.iter().enumerate() {
+                completions.push(ScoredCompletion {
+                    task_id: row.id,
+                    completed_by: completion.completed_by.clone(),
+                    helpers: completion.helpers.clone(),
+                    completed_on: completion.completed_on,
+                    due: due_date(&row.schedule, &history[..index], completion.completed_on),
+                });
+            }
+        }
+        completions.sort_by_key(|completion| completion.completed_on);
+
+        let tasks = rows
+            .into_iter()
+            .filter_map(|row| Some((row.id, row.name?)))
+            .collect();
+        Ok(stats::calculate(&completions, tasks, period))
+    }
+
+    /// Works out when each task will be due over the next `weeks` weeks, and
+    /// who it will be assigned to each time, assuming that everyone does their
+    /// tasks on time.  Tasks that are already overdue are included at their
+    /// current due date.  Sorted by due date.
+    pub async fn forecast(
+        &self,
+        weeks: u16,
+        language: &Language,
+    ) -> Result<Vec<ForecastEntry>, TaskStoreError> {
+        if weeks > 52 {
+            Err(TaskStoreError::ForecastTooLong)?;
+        }
+        let today = self.today_for(None).await?;
+        let until = today + Duration::weeks(weeks.into());
+
+        let rows =
+            sqlx::query_as::<_, StatsTaskRow>(include_str!("./select_all_task_schedules.sql"))
+                .bind(language.to_string())
+                .bind(self.household)
+                .fetch_all(&self.conn)
+                .await?;
+        let absences = sqlx::query_as::<_, Absence>(include_str!("./select_all_absences.sql"))
+            .bind(self.household)
+            .fetch_all(&self.conn)
+            .await?;
+
+        let mut histories = HashMap::<TaskId, Vec<Completion>>::new();
+        for (task_id, completed_by, completed_on, helpers) in
+            sqlx::query_as::<_, CompletionRow>(include_str!("./select_all_completions.sql"))
+                .bind(self.household)
+                .fetch_all(&self.conn)
+                .await?
+        {
+            histories.entry(task_id).or_default().push(Completion {
+                completed_by,
+                helpers: helpers.0,
+                completed_on,
+            });
+        }
+
+        let mut forecast = Vec::new();
+        for row in rows {
+            // tasks without a name in this language aren't listed either
+            let Some(name) = row.name else {
+                continue;
+            };
+            let history = histories.remove(&row.id).unwrap_or_default();
+            for (due, assigned_to) in project(&row.schedule, history, &absences, today, until) {
+                forecast.push(ForecastEntry {
+                    task_id: row.id,
+                    name: name.clone(),
+                    due,
+                    assigned_to,
+                });
+            }
+        }
+        forecast.sort_by_key(|entry| entry.due);
+
+        Ok(forecast)
+    }
+
+    /// Creates a calendar feed of a person's upcoming tasks, or the whole
+    /// household's if `person` is `None`.
+    pub async fn add_feed(
+        &self,
+        person: Option<&str>,
+        language: &Language,
+    ) -> Result<FeedId, TaskStoreError> {
+        let mut transaction = self.conn.begin().await?;
+
+        let feed_id = db::insert_returning_id(
+            &mut transaction,
+            sqlx::query_as(include_str!("./insert_calendar_feed.sql"))
+                .bind(uuid::Uuid::new_v4())
+                .bind(person)
+                .bind(language.to_string())
+                .bind(self.household),
+        )
+        .await?
+        .ok_or_else(|| TaskStoreError::PersonDoesNotExist(person.unwrap_or_default().to_owned()))?;
+
+        transaction.commit().await?;
+        Ok(feed_id)
+    }
+
+    pub async fn feeds(&self) -> Result<Vec<Feed>, TaskStoreError> {
+        Ok(
+            sqlx::query_as::<_, Feed>(include_str!("./select_all_calendar_feeds.sql"))
+                .bind(self.household)
+                .fetch_all(&self.conn)
+                .await?,
+        )
+    }
+
+    pub async fn feed(&self, feed_id: FeedId) -> Result<Feed, TaskStoreError> {
+        sqlx::query_as::<_, Feed>(include_str!("./select_one_calendar_feed.sql"))
+            .bind(feed_id)
+            .bind(self.household)
+            .fetch_optional(&self.conn)
+            .await?
+            .ok_or(TaskStoreError::UnknownFeedId(feed_id))
+    }
+
+    pub async fn delete_feed(&self, feed_id: FeedId) -> Result<(), TaskStoreError> {
+        let result = sqlx::query(include_str!("./delete_calendar_feed.sql"))
+            .bind(feed_id)
+            .bind(self.household)
+            .execute(&self.conn)
+            .await?;
+        if result.rows_affected() == 0 {
+            Err(TaskStoreError::UnknownFeedId(feed_id))?;
+        }
+
+        Ok(())
+    }
+
+    /// Renders the calendar feed with the given secret as an iCalendar
+    /// document, covering the next [`FEED_WEEKS`] weeks.  Feeds are found by
+    /// their secret alone, so this isn't limited to any one household.
+    pub async fn calendar(conn: &SqlitePool, secret: uuid::Uuid) -> Result<String, TaskStoreError> {
+        let SecretFeedRow { feed, household_id } = sqlx::query_as::<_, SecretFeedRow>(
+            include_str!("./select_calendar_feed_by_secret.sql"),
+        )
+        .bind(secret)
+        .fetch_optional(conn)
+        .await?
+        .ok_or(TaskStoreError::UnknownFeedSecret)?;
+
+        let store = Self::new(conn.clone(), household_id);
+        let mut forecast = store.forecast(FEED_WEEKS, &feed.language.into()).await?;
+        let name = match &feed.person {
+            Some(person) => {
+                forecast.retain(|entry| {
+                    entry
+                        .assigned_to
+                        .as_ref()
+                        .is_some_and(|assignee| assignee.to_lowercase() == person.to_lowercase())
+                });
+                person.as_str()
+            }
+            None => "Homie",
+        };
+
+        Ok(calendar::render(
+            name,
+            &forecast,
+            feed.person.is_none(),
+            now(),
+        ))
+    }
+
+    /// Adds someone to the end of a task's rotation.  Adding an existing
+    /// participant does nothing.
+    pub async fn add_participant(
+        &self,
+        task_id: TaskId,
+        person: &str,
+    ) -> Result<(), TaskStoreError> {
+        let mut transaction = self.conn.begin().await?;
+
+        let participants = task_participants(&mut transaction, self.household, task_id).await?;
+        if participants
+            .iter()
+            .any(|participant| participant.to_lowercase() == person.to_lowercase())
+        {
+            return Ok(());
+        }
+
+        let result = sqlx::query(include_str!("./insert_new_task_participant.sql"))
+            .bind(task_id)
+            .bind(person)
+            .bind(self.household)
+            .execute(&mut transaction)
+            .await?;
+        if result.rows_affected() == 0 {
+            Err(TaskStoreError::PersonDoesNotExist(person.to_owned()))?;
+        }
+
+        refresh_schedule(&mut transaction, task_id).await?;
+        transaction.commit().await?;
+
+        Ok(())
+    }
+
+    /// Takes someone out of a task's rotation.  If they had claimed a one-off
+    /// task, the task becomes unclaimed.  If they did the task last, the
+    /// rotation starts again from the first participant.
+    pub async fn remove_participant(
+        &self,
+        task_id: TaskId,
+        person: &str,
+    ) -> Result<(), TaskStoreError> {
+        let mut transaction = self.conn.begin().await?;
+
+        let participants = task_participants(&mut transaction, self.household, task_id).await?;
+        if !participants
+            .iter()
+            .any(|participant| participant.to_lowercase() == person.to_lowercase())
+        {
+            Err(TaskStoreError::PersonNotParticipant(person.to_owned()))?;
+        }
+        if participants.len() == 1 {
+            Err(TaskStoreError::NoParticipants)?;
+        }
+
+        for statement in [
+            include_str!("./delete_task_participant.sql"),
+            include_str!("./clear_task_claim.sql"),
+        ] {
+            sqlx::query(statement)
+                .bind(task_id)
+                .bind(person)
+                .execute(&mut transaction)
+                .await?;
+        }
+
+        refresh_schedule(&mut transaction, task_id).await?;
+        transaction.commit().await?;
+
+        Ok(())
+    }
+
+    /// Changes the order people take turns in.  `order` must contain every
+    /// participant of the task exactly once.
+    pub async fn reorder_participants(
+        &self,
+        task_id: TaskId,
+        order: &[String],
+    ) -> Result<(), TaskStoreError> {
+        let mut transaction = self.conn.begin().await?;
+
+        let sorted_lowercase = |people: &[String]| {
+            let mut people = people
+                .iter()
+                .map(|person| person.to_lowercase())
+                .collect::<Vec<_>>();
+            people.sort();
+            people
+        };
+        let participants = task_participants(&mut transaction, self.household, task_id).await?;
+        if sorted_lowercase(&participants) != sorted_lowercase(order) {
+            Err(TaskStoreError::ParticipantOrderMismatch)?;
+        }
+
+        for (position, person) in order.iter().enumerate() {
+            sqlx::query(include_str!("./update_participant_position.sql"))
+                .bind(position as u32)
+                .bind(task_id)
+                .bind(person)
+                .execute(&mut transaction)
+                .await?;
+        }
+
+        refresh_schedule(&mut transaction, task_id).await?;
+        transaction.commit().await?;
+
+        Ok(())
+    }
+
+    pub async fn add_absence(
+        &self,
+        person: &str,
+        starts_on: NaiveDate,
+        ends_on: NaiveDate,
+    ) -> Result<AbsenceId, TaskStoreError> {
+        if ends_on < starts_on {
+            Err(TaskStoreError::AbsenceEndsBeforeStart)?;
+        }
+
+        let mut transaction = self.conn.begin().await?;
+
+        let absence_id = db::insert_returning_id(
+            &mut transaction,
+            sqlx::query_as(include_str!("./insert_absence.sql"))
+                .bind(starts_on)
+                .bind(ends_on)
+                .bind(person)
+                .bind(self.household),
+        )
+        .await?
+        .ok_or_else(|| TaskStoreError::PersonDoesNotExist(person.to_owned()))?;
+
+        refresh_all_schedules(&mut transaction, self.household).await?;
+        transaction.commit().await?;
+
+        Ok(absence_id)
+    }
+
+    pub async fn absences(&self, person: &str) -> Result<Vec<Absence>, TaskStoreError> {
+        Ok(
+            sqlx::query_as::<_, Absence>(include_str!("./select_person_absences.sql"))
+                .bind(person)
+                .bind(self.household)
+                .fetch_all(&self.conn)
+                .await?,
+        )
+    }
+
+    pub async fn absence(&self, absence_id: AbsenceId) -> Result<Absence, TaskStoreError> {
+        sqlx::query_as::<_, Absence>(include_str!("./select_one_absence.sql"))
+            .bind(absence_id)
+            .bind(self.household)
+            .fetch_optional(&self.conn)
+            .await?
+            .ok_or(TaskStoreError::UnknownAbsenceId(absence_id))
+    }
+
+    pub async fn remove_absence(&self, absence_id: AbsenceId) -> Result<(), TaskStoreError> {
+        let mut transaction = self.conn.begin().await?;
+
+        let result = sqlx::query(include_str!("./delete_absence.sql"))
+            .bind(absence_id)
+            .bind(self.household)
+            .execute(&mut transaction)
+            .await?;
+        if result.rows_affected() == 0 {
+            Err(TaskStoreError::UnknownAbsenceId(absence_id))?;
+        }
+
+        refresh_all_schedules(&mut transaction, self.household).await?;
+        transaction.commit().await?;
+
+        Ok(())
+    }
+
+    /// Limits a recurring task to part of the year, or makes it active all year
+    /// round again if `active_window` is `None`.
+    pub async fn set_active_window(
+        &self,
+        task_id: TaskId,
+        active_window: Option<ActiveWindow>,
+    ) -> Result<(), TaskStoreError> {
+        let kind = sqlx::query_as::<_, (Routine,)>(include_str!("./select_task_kind.sql"))
+            .bind(task_id)
+            .bind(self.household)
+            .fetch_optional(&self.conn)
+            .await?;
+
+        match kind {
+            None => Err(TaskStoreError::UnknownTaskId(task_id))?,
+            Some((kind,)) if active_window.is_some() && !is_recurring(kind) => {
+                Err(TaskStoreError::ActiveWindowMismatch)?
+            }
+            Some(_) => {}
+        }
+
+        let mut transaction = self.conn.begin().await?;
+
+        sqlx::query(include_str!("./update_task_active_window.sql"))
+            .bind(active_window)
+            .bind(task_id)
+            .execute(&mut transaction)
+            .await?;
+
+        refresh_schedule(&mut transaction, task_id).await?;
+        transaction.commit().await?;
+
+        Ok(())
+    }
+
+    /// Changes when a task counts as due soon and how long it can be overdue
+    /// before it counts as badly overdue.
+    pub async fn set_urgency(
+        &self,
+        task_id: TaskId,
+        urgency: Urgency,
+    ) -> Result<(), TaskStoreError> {
+        let result = sqlx::query(include_str!("./update_task_urgency.sql"))
+            .bind(urgency.due_soon_days)
+            .bind(urgency.grace_days)
+            .bind(task_id)
+            .bind(self.household)
+            .execute(&self.conn)
+            .await?;
+        if result.rows_affected() == 0 {
+            Err(TaskStoreError::UnknownTaskId(task_id))?;
+        }
+
+        Ok(())
+    }
+
+    /// Changes how many points each person who does a task earns.  Points that
+    /// were already earned are left as they are.
+    pub async fn set_points(&self, task_id: TaskId, points: u32) -> Result<(), TaskStoreError> {
+        let result = sqlx::query(include_str!("./update_task_points.sql"))
+            .bind(points)
+            .bind(task_id)
+            .bind(self.household)
+            .execute(&self.conn)
+            .await?;
+        if result.rows_affected() == 0 {
+            Err(TaskStoreError::UnknownTaskId(task_id))?;
+        }
+
+        Ok(())
+    }
+
+    /// Changes what happens to the cycles of a schedule task that are missed.
+    pub async fn set_catch_up(
+        &self,
+        task_id: TaskId,
+        catch_up: CatchUp,
+    ) -> Result<(), TaskStoreError> {
+        let mut transaction = self.conn.begin().await?;
+
+        let result = sqlx::query(include_str!("./update_task_catch_up.sql"))
+            .bind(catch_up)
+            .bind(task_id)
+            .bind(self.household)
+            .execute(&mut transaction)
+            .await?;
+        if result.rows_affected() == 0 {
+            Err(TaskStoreError::UnknownTaskId(task_id))?;
+        }
+
+        refresh_schedule(&mut transaction, task_id).await?;
+        transaction.commit().await?;
+
+        Ok(())
+    }
+
+    /// Lists the tasks that came from a household config file, including
+    /// archived ones.
+    pub async fn configured_tasks(&self) -> Result<Vec<ConfiguredTask>, TaskStoreError> {
+        let rows = sqlx::query_as::<_, TaskSettingsRow>(include_str!("./select_task_settings.sql"))
+            .bind(self.household)
+            .fetch_all(&self.conn)
+            .await?;
+        Ok(rows
+            .into_iter()
+            .filter_map(|row| {
+                Some(ConfiguredTask {
+                    id: row.id,
+                    settings: row.settings(),
+                    key: row.config_key?,
+                    archived: row.archived_on.is_some(),
+                    participants: row.participants.0,
+                })
+            })
+            .collect())
+    }
+
+    /// Lists every task in the household along with its whole history, for
+    /// moving them to another database.
+    pub async fn export_tasks(&self) -> Result<Vec<(TaskId, ExportedTask)>, TaskStoreError> {
+        let rows = sqlx::query_as::<_, TaskSettingsRow>(include_str!("./select_task_settings.sql"))
+            .bind(self.household)
+            .fetch_all(&self.conn)
+            .await?;
+
+        let mut histories = HashMap::<TaskId, Vec<ExportedCompletion>>::new();
+        for row in sqlx::query_as::<_, ExportedCompletionRow>(include_str!(
+            "./select_exported_completions.sql"
+        ))
+        .bind(self.household)
+        .fetch_all(&self.conn)
+        .await?
+        {
+            histories
+                .entry(row.task_id)
+                .or_default()
+                .push(ExportedCompletion {
+                    completed_by: row.completed_by,
+                    helpers: row.helpers.0,
+                    completed_on: row.completed_on,
+                    note: row.note,
+                    initial: row.initial,
+                });
+        }
+
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                let task = ExportedTask {
+                    settings: row.settings(),
+                    key: row.config_key,
+                    participants: row.participants.0,
+                    due_on: row.due_on,
+                    claimed_by: row.claimed_by,
+                    archived_on: row.archived_on,
+                    completions: histories.remove(&row.id).unwrap_or_default(),
+                };
+                (row.id, task)
+            })
+            .collect())
+    }
+
+    /// Recreates an exported task, along with its whole history.
+    pub async fn import_task(&self, task: ExportedTask) -> Result<TaskId, TaskStoreError> {
+        task.check()?;
+        let settings = task.settings;
+
+        let mut transaction = self.conn.begin().await?;
+
+        let task_id: TaskId = db::insert_returning_id(
+            &mut transaction,
+            sqlx::query_as(include_str!("./insert_new_task.sql"))
+                .bind(settings.routine)
+                .bind(settings.duration)
+                .bind(Json(&settings.rotation))
+                .bind(settings.catch_up)
+                .bind(&settings.recurrence)
+                .bind(task.due_on)
+                .bind(settings.active_window)
+                .bind(settings.urgency.due_soon_days)
+                .bind(settings.urgency.grace_days)
+                .bind(settings.points)
+                .bind(&task.key)
+                .bind(self.household),
+        )
+        .await?
+        .ok_or(sqlx::Error::RowNotFound)?;
+
+        let mut descriptions = settings.descriptions;
+        for (lang, name) in settings.names {
+            let description = descriptions.remove(&lang);
+            sqlx::query(include_str!("./insert_new_task_name.sql"))
+                .bind(task_id)
+                .bind(lang)
+                .bind(name)
+                .bind(description)
+                .execute(&mut transaction)
+                .await?;
+        }
+
+        for person in &task.participants {
+            let result = sqlx::query(include_str!("./insert_new_task_participant.sql"))
+                .bind(task_id)
+                .bind(person)
+                .bind(self.household)
+                .execute(&mut transaction)
+                .await?;
+            if result.rows_affected() == 0 {
+                Err(TaskStoreError::PersonDoesNotExist(person.to_owned()))?;
+            }
+        }
+
+        if let Some(claimed_by) = &task.claimed_by {
+            sqlx::query(include_str!("./update_task_claim.sql"))
+                .bind(claimed_by)
+                .bind(task_id)
+                .execute(&mut transaction)
+                .await?;
+        }
+        if task.archived_on.is_some() {
+            sqlx::query(include_str!("./update_task_archived.sql"))
+                .bind(task.archived_on)
+                .bind(task_id)
+                .bind(self.household)
+                .execute(&mut transaction)
+                .await?;
+        }
+
+        insert_imported_completions(&mut transaction, self.household, task_id, &task.completions)
+            .await?;
+        refresh_schedule(&mut transaction, task_id).await?;
+
+        transaction.commit().await?;
+
+        Ok(task_id)
+    }
+
+    /// Adds exported completions to an existing task, skipping any that it
+    /// already has (done by the same person on the same day) and any initial
+    /// ones.  Returns how many were added.
+    pub async fn import_completions(
+        &self,
+        task_id: TaskId,
+        completions: &[ExportedCompletion],
+    ) -> Result<usize, TaskStoreError> {
+        self.ensure_task_exists(task_id).await?;
+
+        let mut transaction = self.conn.begin().await?;
+
+        let existing =
+            sqlx::query_as::<_, CompletionRow>(include_str!("./select_task_completions.sql"))
+                .bind(task_id)
+                .fetch_all(&mut transaction)
+                .await?;
+        let missing = completions
+            .iter()
+            .filter(|completion| {
+                !completion.initial
+                    && !existing.iter().any(|(_, completed_by, completed_on, _)| {
+                        *completed_on == completion.completed_on
+                            && completed_by.to_lowercase() == completion.completed_by.to_lowercase()
+                    })
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+
+        insert_imported_completions(&mut transaction, self.household, task_id, &missing).await?;
+        refresh_schedule(&mut transaction, task_id).await?;
+
+        transaction.commit().await?;
+
+        Ok(missing.len())
+    }
+
+    /// Changes everything about a task apart from who does it and its history.
+    pub async fn update_task(
+        &self,
+        task_id: TaskId,
+        settings: TaskSettings,
+    ) -> Result<(), TaskStoreError> {
+        let today = self.today_for(None).await?;
+        let mut transaction = self.conn.begin().await?;
+
+        let participants = task_participants(&mut transaction, self.household, task_id).await?;
+        settings.check(&participants, today)?;
+
+        sqlx::query(include_str!("./update_task_settings.sql"))
+            .bind(settings.routine)
+            .bind(settings.duration)
+            .bind(Json(&settings.rotation))
+            .bind(settings.catch_up)
+            .bind(&settings.recurrence)
+            .bind(settings.active_window)
+            .bind(settings.urgency.due_soon_days)
+            .bind(settings.urgency.grace_days)
+            .bind(settings.points)
+            .bind(task_id)
+            .bind(self.household)
+            .execute(&mut transaction)
+            .await?;
+
+        let mut descriptions = settings.descriptions;
+        sqlx::query(include_str!("./delete_task_names.sql"))
+            .bind(task_id)
+            .execute(&mut transaction)
+            .await?;
+        for (lang, name) in settings.names {
+            let description = descriptions.remove(&lang);
+            sqlx::query(include_str!("./insert_new_task_name.sql"))
+                .bind(task_id)
+                .bind(lang)
+                .bind(name)
+                .bind(description)
+                .execute(&mut transaction)
+                .await?;
+        }
+
+        refresh_schedule(&mut transaction, task_id).await?;
+
+        transaction.commit().await?;
+
+        Ok(())
+    }
+
+    /// Hides a task from everything apart from its history and stats, or
+    /// brings it back again.
+    pub async fn set_archived(
+        &self,
+        task_id: TaskId,
+        archived: bool,
+    ) -> Result<(), TaskStoreError> {
+        let archived_on = match archived {
+            true => Some(self.today_for(None).await?),
+            false => None,
+        };
+
+        let mut transaction = self.conn.begin().await?;
+
+        let result = sqlx::query(include_str!("./update_task_archived.sql"))
+            .bind(archived_on)
+            .bind(task_id)
+            .bind(self.household)
+            .execute(&mut transaction)
+            .await?;
+        if result.rows_affected() == 0 {
+            Err(TaskStoreError::UnknownTaskId(task_id))?;
+        }
+        refresh_schedule(&mut transaction, task_id).await?;
+
+        transaction.commit().await?;
+
+        Ok(())
+    }
+
+    /// How many points each member of the household has, or just the given
+    /// person.
+    pub async fn balances(&self, person: Option<&str>) -> Result<Vec<Balance>, TaskStoreError> {
+        let balances = sqlx::query_as::<_, Balance>(include_str!("./select_points_balances.sql"))
+            .bind(self.household)
+            .bind(person)
+            .fetch_all(&self.conn)
+            .await?;
+        if let (Some(person), true) = (person, balances.is_empty()) {
+            Err(TaskStoreError::PersonDoesNotExist(person.to_owned()))?;
+        }
+        Ok(balances)
+    }
+
+    /// Lists every change to the household's points in date order, or just the
+    /// changes to one person's points.
+    pub async fn ledger(&self, person: Option<&str>) -> Result<Vec<LedgerEntry>, TaskStoreError> {
+        Ok(
+            sqlx::query_as::<_, LedgerEntry>(include_str!("./select_points_ledger.sql"))
+                .bind(self.household)
+                .bind(person)
+                .fetch_all(&self.conn)
+                .await?,
+        )
+    }
+
+    /// The same as [`TaskStore::ledger`], but as a CSV document.
+    pub async fn ledger_csv(&self, person: Option<&str>) -> Result<String, TaskStoreError> {
+        Ok(points::render_csv(&self.ledger(person).await?))
+    }
+
+    pub async fn ledger_entry(
+        &self,
+        entry_id: LedgerEntryId,
+    ) -> Result<LedgerEntry, TaskStoreError> {
+        sqlx::query_as::<_, LedgerEntry>(include_str!("./select_one_points_entry.sql"))
+            .bind(entry_id)
+            .bind(self.household)
+            .fetch_optional(&self.conn)
+            .await?
+            .ok_or(TaskStoreError::UnknownLedgerEntryId(entry_id))
+    }
+
+    /// Gives somebody extra points, or takes some away if `points` is negative.
+    pub async fn adjust_points(
+        &self,
+        person: &str,
+        points: i32,
+        description: Option<&str>,
+    ) -> Result<LedgerEntryId, TaskStoreError> {
+        self.add_ledger_entry(person, LedgerKind::Adjustment, points, description)
+            .await
+    }
+
+    /// Spends some of somebody's points on a reward, as long as they have
+    /// enough.
+    pub async fn redeem_points(
+        &self,
+        person: &str,
+        points: i32,
+        description: Option<&str>,
+    ) -> Result<LedgerEntryId, TaskStoreError> {
+        if points <= 0 {
+            Err(TaskStoreError::InvalidRedemption(points))?;
+        }
+        self.add_ledger_entry(person, LedgerKind::Redemption, -points, description)
+            .await
+    }
+
+    async fn add_ledger_entry(
+        &self,
+        person: &str,
+        kind: LedgerKind,
+        points: i32,
+        description: Option<&str>,
+    ) -> Result<LedgerEntryId, TaskStoreError> {
+        let today = self.today_for(Some(person)).await?;
+        let mut transaction = self.conn.begin().await?;
+
+        // checked in the same transaction as the insert, so that two
+        // redemptions can't both spend the same points
+        if kind == LedgerKind::Redemption {
+            let balance =
+                sqlx::query_as::<_, Balance>(include_str!("./select_points_balances.sql"))
+                    .bind(self.household)
+                    .bind(person)
+                    .fetch_optional(&mut transaction)
+                    .await?;
+            if balance.is_some_and(|balance| balance.points < -points) {
+                Err(TaskStoreError::NotEnoughPoints(person.to_owned()))?;
+            }
+        }
+
+        let entry_id = db::insert_returning_id(
+            &mut transaction,
+            sqlx::query_as(include_str!("./insert_points_entry.sql"))
+                .bind(today)
+                .bind(kind)
+                .bind(points)
+                .bind(description)
+                .bind(person)
+                .bind(self.household),
+        )
+        .await?
+        .ok_or_else(|| TaskStoreError::PersonDoesNotExist(person.to_owned()))?;
+
+        transaction.commit().await?;
+        Ok(entry_id)
+    }
+
+    /// Makes completing `from_task` trigger `to_task`, which must be a triggered task.
+    pub async fn add_chain(
+        &self,
+        from_task: TaskId,
+        to_task: TaskId,
+        offset_days: u16,
+        assignee: ChainAssignee,
+    ) -> Result<ChainId, TaskStoreError> {
+        for task_id in [from_task, to_task] {
+            let kind = sqlx::query_as::<_, (Routine,)>(include_str!("./select_task_kind.sql"))
+                .bind(task_id)
+                .bind(self.household)
+                .fetch_optional(&self.conn)
+                .await?;
+
+            match kind {
+                None => Err(TaskStoreError::UnknownTaskId(task_id))?,
+                Some((kind,)) if task_id == to_task && kind != Routine::Triggered => {
+                    Err(TaskStoreError::ChainTargetNotTriggered(task_id))?
+                }
+                Some(_) => {}
+            }
+        }
+
+        let mut transaction = self.conn.begin().await?;
+
+        let chain_id: ChainId = db::insert_returning_id(
+            &mut transaction,
+            sqlx::query_as(include_str!("./insert_task_chain.sql"))
+                .bind(from_task)
+                .bind(to_task)
+                .bind(offset_days)
+                .bind(assignee),
+        )
+        .await?
+        .ok_or(sqlx::Error::RowNotFound)?;
+
+        transaction.commit().await?;
+        Ok(chain_id)
+    }
+
+    pub async fn chains(&self) -> Result<Vec<Chain>, TaskStoreError> {
+        Ok(
+            sqlx::query_as::<_, Chain>(include_str!("./select_all_task_chains.sql"))
+                .bind(self.household)
+                .fetch_all(&self.conn)
+                .await?,
+        )
+    }
+
+    pub async fn chain(&self, chain_id: ChainId) -> Result<Chain, TaskStoreError> {
+        sqlx::query_as::<_, Chain>(include_str!("./select_one_task_chain.sql"))
+            .bind(chain_id)
+            .bind(self.household)
+            .fetch_optional(&self.conn)
+            .await?
+            .ok_or(TaskStoreError::UnknownChainId(chain_id))
+    }
+
+    pub async fn delete_chain(&self, chain_id: ChainId) -> Result<(), TaskStoreError> {
+        let result = sqlx::query(include_str!("./delete_task_chain.sql"))
+            .bind(chain_id)
+            .bind(self.household)
+            .execute(&self.conn)
+            .await?;
+        if result.rows_affected() == 0 {
+            Err(TaskStoreError::UnknownChainId(chain_id))?;
+        }
+
+        Ok(())
+    }
+
+    /// Records that a task was done, and triggers any tasks chained to it.  If
+    /// `steps_done` is given, it lists which of the task's checklist steps were
+    /// ticked off.
+    pub async fn mark_task_done(
+        &self,
+        task_id: TaskId,
+        person: &str,
+        date: &NaiveDate,
+        steps_done: Option<&[StepId]>,
+    ) -> Result<(), TaskStoreError> {
+        self.mark_task_done_with(
+            task_id,
+            person,
+            date,
+            steps_done,
+            CompletionDetails::default(),
+        )
+        .await
+    }
+
+    /// Like [`TaskStore::mark_task_done`], but also records who helped, and
+    /// keeps a note and any images that came with the completion, which then
+    /// show up in the task's history.
+    ///
+    /// Only participants can mark a task as done, for a date between when the
+    /// task started and today (as far back as the household's back-dating
+    /// limit allows).  Helpers only need to be in the household.
+    pub async fn mark_task_done_with(
+        &self,
+        task_id: TaskId,
+        person: &str,
+        date: &NaiveDate,
+        steps_done: Option<&[StepId]>,
+        details: CompletionDetails,
+    ) -> Result<(), TaskStoreError> {
+        self.ensure_task_exists(task_id).await?;
+        let row = sqlx::query_as::<_, ScheduleRow>(include_str!("./select_task_schedule.sql"))
+            .bind(task_id)
+            .fetch_one(&self.conn)
+            .await?;
+
+        let is_participant = row
+            .participants
+            .iter()
+            .any(|participant| participant.to_lowercase() == person.to_lowercase());
+        if !is_participant {
+            let member =
+                sqlx::query_as::<_, (String,)>(include_str!("./select_household_member.sql"))
+                    .bind(person)
+                    .bind(self.household)
+                    .fetch_optional(&self.conn)
+                    .await?;
+            match member {
+                Some(_) => Err(TaskStoreError::PersonNotParticipant(person.to_owned()))?,
+                None => Err(TaskStoreError::PersonDoesNotExist(person.to_owned()))?,
+            }
+        }
+
+        let today = self.today_for(Some(person)).await?;
+        if *date > today {
+            Err(TaskStoreError::CompletionInFuture(*date))?;
+        }
+        if *date < row.started_on {
+            Err(TaskStoreError::CompletionBeforeStart(*date))?;
+        }
+        if let Some(days) = self.backdate_limit().await? {
+            if today - *date > Duration::days(days.into()) {
+                Err(TaskStoreError::CompletionTooOld(days))?;
+            }
+        }
+
+        if details.attachments.len() > MAX_ATTACHMENTS {
+            Err(TaskStoreError::TooManyAttachments)?;
+        }
+        for attachment in &details.attachments {
+            if !attachments::is_image_type(&attachment.content_type) {
+                Err(TaskStoreError::NotAnImage(attachment.content_type.clone()))?;
+            }
+            if attachment.data.len() > MAX_ATTACHMENT_SIZE {
+                Err(TaskStoreError::AttachmentTooLarge)?;
+            }
+        }
+        let note = details
+            .note
+            .map(|note| note.trim().to_owned())
+            .filter(|note| !note.is_empty());
+
+        let mut people = vec![person.to_owned()];
+        for helper in details.helpers {
+            if !people
+                .iter()
+                .any(|person| person.to_lowercase() == helper.to_lowercase())
+            {
+                people.push(helper);
+            }
+        }
+
+        let steps_done = match steps_done {
+            Some(steps_done) => {
+                let checklist =
+                    sqlx::query_as::<_, (StepId,)>(include_str!("./select_task_checklist_ids.sql"))
+                        .bind(task_id)
+                        .fetch_all(&self.conn)
+                        .await?;
+
+                if let Some(step) = steps_done
+                    .iter()
+                    .find(|step| !checklist.contains(&(**step,)))
+                {
+                    Err(TaskStoreError::UnknownStep(*step))?;
+                }
+
+                // store the steps in checklist order, without duplicates
+                let steps_done = checklist
+                    .into_iter()
+                    .map(|(step,)| step)
+                    .filter(|step| steps_done.contains(step))
+                    .collect::<Vec<_>>();
+                Some(Json(steps_done))
+            }
+            None => None,
+        };
+
+        let mut transaction = self.conn.begin().await?;
+
+        // a task done together is recorded against whoever is furthest along
+        // in the rotation, so that the rotation carries on after all of them
+        let completed_by =
+            furthest_along(&row.participants, &row.last_completed_by, &people).unwrap_or(person);
+
+        let completion_id: CompletionId = db::insert_returning_id(
+            &mut transaction,
+            sqlx::query_as(include_str!("./insert_completion.sql"))
+                .bind(task_id)
+                .bind(date)
+                .bind(completed_by)
+                .bind(self.household)
+                .bind(steps_done)
+                .bind(note),
+        )
+        .await?
+        .ok_or(sqlx::Error::RowNotFound)?;
+
+        for helper in people.iter().filter(|helper| *helper != completed_by) {
+            let result = sqlx::query(include_str!("./insert_completion_helper.sql"))
+                .bind(completion_id)
+                .bind(helper)
+                .bind(self.household)
+                .execute(&mut transaction)
+                .await?;
+            if result.rows_affected() == 0 {
+                Err(TaskStoreError::PersonDoesNotExist(helper.to_owned()))?;
+            }
+        }
+
+        // everyone who did the task earns its points
+        for person in &people {
+            sqlx::query(include_str!("./insert_completion_points.sql"))
+                .bind(date)
+                .bind(completion_id)
+                .bind(task_id)
+                .bind(person)
+                .execute(&mut transaction)
+                .await?;
+        }
+
+        let mut file_names = Vec::new();
+        for attachment in &details.attachments {
+            let file_name = uuid::Uuid::new_v4().to_string();
+            sqlx::query(include_str!("./insert_completion_attachment.sql"))
+                .bind(completion_id)
+                .bind(&file_name)
+                .bind(attachment.content_type.to_lowercase())
+                .bind(attachment.data.len() as u32)
+                .execute(&mut transaction)
+                .await?;
+            file_names.push(file_name);
+        }
+
+        // a triggered task is done until something triggers it again
+        let kind = sqlx::query_as::<_, (Routine,)>(include_str!("./select_task_kind.sql"))
+            .bind(task_id)
+            .bind(self.household)
+            .fetch_optional(&mut transaction)
+            .await?;
+        if let Some((Routine::Triggered,)) = kind {
+            sqlx::query(include_str!("./update_task_activation.sql"))
+                .bind(None::<NaiveDate>)
+                .bind(None::<&str>)
+                .bind(task_id)
+                .execute(&mut transaction)
+                .await?;
+        }
+        refresh_schedule(&mut transaction, task_id).await?;
+
+        let chains = sqlx::query_as::<_, Chain>(include_str!("./select_task_chains_from.sql"))
+            .bind(task_id)
+            .fetch_all(&mut transaction)
+            .await?;
+        for chain in chains {
+            let claimed_by = match chain.assignee {
+                ChainAssignee::SamePerson => Some(person),
+                ChainAssignee::NextInRotation => None,
+            };
+            sqlx::query(include_str!("./update_task_activation.sql"))
+                .bind(*date + Duration::days(chain.offset_days.into()))
+                .bind(claimed_by)
+                .bind(chain.to_task)
+                .execute(&mut transaction)
+                .await?;
+            refresh_schedule(&mut transaction, chain.to_task).await?;
+        }
+
+        if details.attachments.is_empty() {
+            transaction.commit().await?;
+            return Ok(());
+        }
+
+        // the files are written just before committing, and removed again if
+        // the completion can't be saved after all, so that they aren't left
+        // behind without anything referring to them
+        let directory = attachments::directory(&self.conn).await?;
+        let result = async {
+            for (attachment, file_name) in details.attachments.iter().zip(&file_names) {
+                attachments::write(&directory, file_name, &attachment.data).await?;
+            }
+            transaction.commit().await?;
+            Ok(())
+        }
+        .await;
+        if result.is_err() {
+            for file_name in &file_names {
+                if let Err(error) = attachments::remove(&directory, file_name).await {
+                    tracing::warn!(
+                        { details = &error.to_string() },
+                        "Attachment could not be removed"
+                    );
+                }
+            }
+        }
+        result
+    }
+
+    /// Lists the times a task was done, most recent first, along with any notes
+    /// and images that came with them.
+    pub async fn history(&self, task_id: TaskId) -> Result<Vec<HistoryEntry>, TaskStoreError> {
+        self.ensure_task_exists(task_id).await?;
+
+        let completions =
+            sqlx::query_as::<_, HistoryRow>(include_str!("./select_task_history.sql"))
+                .bind(task_id)
+                .fetch_all(&self.conn)
+                .await?;
+        let mut attachments =
+            sqlx::query_as::<_, AttachmentRow>(include_str!("./select_task_attachments.sql"))
+                .bind(task_id)
+                .fetch_all(&self.conn)
+                .await?
+                .into_iter()
+                .fold(HashMap::<_, Vec<_>>::new(), |mut attachments, row| {
+                    attachments
+                        .entry(row.completion_id)
+                        .or_default()
+                        .push(row.attachment);
+                    attachments
+                });
+
+        Ok(completions
+            .into_iter()
+            .map(|row| HistoryEntry {
+                attachments: attachments.remove(&row.id).unwrap_or_default(),
+                id: row.id,
+                completed_by: row.completed_by,
+                helpers: row.helpers.0,
+                completed_on: row.completed_on,
+                note: row.note,
+            })
+            .collect())
+    }
+
+    /// Fetches an attached image, along with its details.
+    pub async fn attachment(
+        &self,
+        attachment_id: AttachmentId,
+    ) -> Result<(Attachment, Vec<u8>), TaskStoreError> {
+        let row =
+            sqlx::query_as::<_, AttachmentFileRow>(include_str!("./select_one_attachment.sql"))
+                .bind(attachment_id)
+                .bind(self.household)
+                .fetch_optional(&self.conn)
+                .await?
+                .ok_or(TaskStoreError::UnknownAttachmentId(attachment_id))?;
+
+        let directory = attachments::directory(&self.conn).await?;
+        let data = attachments::read(&directory, &row.file_name).await?;
+        Ok((row.attachment, data))
+    }
+
+    /// Deletes the images attached to completions from before the given date,
+    /// returning how many there were.  The completions and their notes are
+    /// kept.
+    pub async fn prune_attachments(&self, before: NaiveDate) -> Result<usize, TaskStoreError> {
+        let old = sqlx::query_as::<_, (AttachmentId, String)>(include_str!(
+            "./select_old_attachments.sql"
+        ))
+        .bind(before)
+        .bind(self.household)
+        .fetch_all(&self.conn)
+        .await?;
+
+        let directory = attachments::directory(&self.conn).await?;
+        for (attachment_id, file_name) in &old {
+            sqlx::query(include_str!("./delete_completion_attachment.sql"))
+                .bind(attachment_id)
+                .execute(&self.conn)
+                .await?;
+            attachments::remove(&directory, file_name).await?;
+        }
+        Ok(old.len())
+    }
+
+    pub async fn claim_task(&self, task_id: TaskId, person: &str) -> Result<(), TaskStoreError> {
+        let kind = sqlx::query_as::<_, (Routine,)>(include_str!("./select_task_kind.sql"))
+            .bind(task_id)
+            .bind(self.household)
+            .fetch_optional(&self.conn)
+            .await?;
+
+        match kind {
+            Some((Routine::OneOff,)) => {}
+            Some(_) => Err(TaskStoreError::TaskNotClaimable(task_id))?,
+            None => Err(TaskStoreError::UnknownTaskId(task_id))?,
+        }
+
+        let mut transaction = self.conn.begin().await?;
+
+        let result = sqlx::query(include_str!("./update_task_claim.sql"))
+            .bind(person)
+            .bind(task_id)
+            .execute(&mut transaction)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            Err(TaskStoreError::PersonNotParticipant(person.to_owned()))?;
+        }
+
+        refresh_schedule(&mut transaction, task_id).await?;
+        transaction.commit().await?;
+
+        Ok(())
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct TaskRow {
+    id: TaskId,
+    name: String,
+    description: Option<String>,
+    kind: Routine,
+    duration: u16,
+    rotation: Json<Rotation>,
+    catch_up: CatchUp,
+    recurrence: Option<Recurrence>,
+    active_window: Option<ActiveWindow>,
+    due_soon_days: u16,
+    grace_days: Option<u16>,
+    points: u32,
+    participants: Json<Vec<String>>,
+    last_completed: NaiveDate,
+    next_due: Option<NaiveDate>,
+    assigned_to: Option<String>,
+    tags: Json<Vec<Tag>>,
+    checklist: Json<Vec<ChecklistStep>>,
+    last_steps_done: Option<Json<Vec<StepId>>>,
+}
+
+fn task_from_row(row: TaskRow, today: NaiveDate) -> Task {
+    let urgency = Urgency {
+        due_soon_days: row.due_soon_days,
+        grace_days: row.grace_days,
+    };
+    let next_due = current_due(&row, today);
+    // the current cycle of a schedule task starts one cycle before it is due
+    let last_completed = match skipped_due(&row, today) {
+        Some(due) => due - Duration::days(row.duration.into()),
+        None => row.last_completed,
+    };
+    Task {
+        id: row.id,
+        name: row.name,
+        description: row.description,
+        kind: row.kind,
+        length_days: row.duration,
+        assigned_to: row.assigned_to,
+        rotation: row.rotation.0,
+        catch_up: row.catch_up,
+        recurrence: row.recurrence,
+        active_window: row.active_window,
+        active: row
+            .active_window
+            .is_none_or(|window| window.contains(today)),
+        participants: row.participants.0,
+        last_completed,
+        deadline: next_due.map_or(Deadline::Unscheduled, |due| {
+            Deadline::new(due - today, urgency)
+        }),
+        urgency,
+        points: row.points,
+        tags: row.tags.0,
+        checklist: row.checklist.0,
+        last_steps_done: row.last_steps_done.map(|steps| steps.0),
+    }
+}
+
+/// When a task is due as of `today`, which can be later than the due date that
+/// was stored when the task last changed.
+fn current_due(row: &TaskRow, today: NaiveDate) -> Option<NaiveDate> {
+    let due = skipped_due(row, today).or(row.next_due)?;
+    Some(match row.active_window {
+        Some(window) => window.carry_over(due, today),
+        None => due,
+    })
+}
+
+/// The end of the current cycle of a schedule task that skips the cycles it
+/// misses, if it has missed any since it was last done.
+fn skipped_due(row: &TaskRow, today: NaiveDate) -> Option<NaiveDate> {
+    let due = row.next_due?;
+    let skips = row.kind == Routine::Schedule && row.catch_up == CatchUp::Skip;
+    // a cycle of zero days would never end
+    if !skips || due >= today || row.duration == 0 {
+        return None;
+    }
+
+    let duration = Duration::days(row.duration.into());
+    let mut cycle_ends = (1..)
+        .map(|cycle| due + duration * cycle)
+        .skip_while(|end| *end < today);
+    match row.active_window {
+        Some(window) => window.nth_active(cycle_ends, 0),
+        None => cycle_ends.next(),
+    }
+}
+
+/// Everything needed to work out when a task is next due and who should do it.
+#[derive(sqlx::FromRow)]
+struct ScheduleRow {
+    kind: Routine,
+    duration: u16,
+    rotation: Json<Rotation>,
+    catch_up: CatchUp,
+    recurrence: Option<Recurrence>,
+    active_window: Option<ActiveWindow>,
+    due_on: Option<NaiveDate>,
+    claimed_by: Option<String>,
+    participants: Json<Vec<String>>,
+    last_completed_by: String,
+    started_on: NaiveDate,
+    archived: bool,
+}
+
+#[derive(sqlx::FromRow)]
+struct TaskSettingsRow {
+    id: TaskId,
+    config_key: Option<String>,
+    archived_on: Option<NaiveDate>,
+    names: Json<HashMap<String, String>>,
+    descriptions: Json<HashMap<String, String>>,
+    kind: Routine,
+    duration: u16,
+    rotation: Json<Rotation>,
+    catch_up: CatchUp,
+    recurrence: Option<Recurrence>,
+    active_window: Option<ActiveWindow>,
+    due_soon_days: u16,
+    grace_days: Option<u16>,
+    points: u32,
+    due_on: Option<NaiveDate>,
+    claimed_by: Option<String>,
+    participants: Json<Vec<String>>,
+}
+
+impl TaskSettingsRow {
+    fn settings(&self) -> TaskSettings {
+        TaskSettings {
+            names: self.names.0.clone(),
+            descriptions: self.descriptions.0.clone(),
+            routine: self.kind,
+            duration: self.duration,
+            rotation: self.rotation.0.clone(),
+            catch_up: self.catch_up,
+            recurrence: self.recurrence.clone(),
+            active_window: self.active_window,
+            urgency: Urgency {
+                due_soon_days: self.due_soon_days,
+                grace_days: self.grace_days,
+            },
+            points: self.points,
+        }
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct ExportedCompletionRow {
+    task_id: TaskId,
+    completed_by: String,
+    helpers: Json<Vec<String>>,
+    completed_on: NaiveDate,
+    note: Option<String>,
+    initial: bool,
+}
+
+#[derive(sqlx::FromRow)]
+struct StatsTaskRow {
+    id: TaskId,
+    /// Missing if the task has no name in the requested language
+    name: Option<String>,
+    #[sqlx(flatten)]
+    schedule: ScheduleRow,
+}
+
+type CompletionRow = (TaskId, String, NaiveDate, Json<Vec<String>>);
+
+#[derive(sqlx::FromRow)]
+struct HistoryRow {
+    id: CompletionId,
+    completed_by: String,
+    helpers: Json<Vec<String>>,
+    completed_on: NaiveDate,
+    note: Option<String>,
+}
+
+#[derive(sqlx::FromRow)]
+struct AttachmentRow {
+    completion_id: CompletionId,
+    #[sqlx(flatten)]
+    attachment: Attachment,
+}
+
+#[derive(sqlx::FromRow)]
+struct AttachmentFileRow {
+    #[sqlx(flatten)]
+    attachment: Attachment,
+    file_name: String,
+}
+
+#[derive(sqlx::FromRow)]
+struct SecretFeedRow {
+    #[sqlx(flatten)]
+    feed: Feed,
+    household_id: HouseholdId,
+}
+
+async fn insert_imported_completions(
+    conn: &mut SqliteConnection,
+    household: HouseholdId,
+    task_id: TaskId,
+    completions: &[ExportedCompletion],
+) -> Result<(), TaskStoreError> {
+    for completion in completions {
+        let completion_id: CompletionId = db::insert_returning_id(
+            &mut *conn,
+            sqlx::query_as(include_str!("./insert_imported_completion.sql"))
+                .bind(task_id)
+                .bind(completion.completed_on)
+                .bind(&completion.note)
+                .bind(completion.initial)
+                .bind(&completion.completed_by)
+                .bind(household),
+        )
+        .await?
+        .ok_or_else(|| TaskStoreError::PersonDoesNotExist(completion.completed_by.to_owned()))?;
+        for helper in &completion.helpers {
+            let result = sqlx::query(include_str!("./insert_completion_helper.sql"))
+                .bind(completion_id)
+                .bind(helper)
+                .bind(household)
+                .execute(&mut *conn)
+                .await?;
+            if result.rows_affected() == 0 {
+                Err(TaskStoreError::PersonDoesNotExist(helper.to_owned()))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn insert_tag_names(
+    conn: &mut SqliteConnection,
+    tag_id: TagId,
+    names: HashMap<String, String>,
+) -> Result<(), TaskStoreError> {
+    for (lang, name) in names {
+        sqlx::query(include_str!("./insert_new_tag_name.sql"))
+            .bind(tag_id)
+            .bind(lang)
+            .bind(name)
+            .execute(&mut *conn)
+            .await?;
+    }
+    Ok(())
+}
+
+/// Works out the due date and assignee for a task and stores them alongside the
+/// task, so that they can be filtered and sorted on.  Needs to be called whenever
+/// anything these depend on changes.
+async fn refresh_schedule(
+    conn: &mut SqliteConnection,
+    task_id: TaskId,
+) -> Result<(), TaskStoreError> {
+    let row = sqlx::query_as::<_, ScheduleRow>(include_str!("./select_task_schedule.sql"))
+        .bind(task_id)
+        .fetch_optional(&mut *conn)
+        .await?;
+
+    let Some(row) = row else {
+        return Ok(());
+    };
+
+    let history = sqlx::query_as::<_, CompletionRow>(include_str!("./select_task_completions.sql"))
+        .bind(task_id)
+        .fetch_all(&mut *conn)
+        .await?
+        .into_iter()
+        .map(|(_, completed_by, completed_on, helpers)| Completion {
+            completed_by,
+            helpers: helpers.0,
+            completed_on,
+        })
+        .collect::<Vec<_>>();
+
+    // the stored due date doesn't depend on what day it is, so any cycles that
+    // have been missed since the last completion are only skipped when the
+    // task is read (see current_due)
+    let last_completed = history
+        .last()
+        .map_or(row.started_on, |completion| completion.completed_on);
+
+    // archived tasks are never due
+    let due = match row.archived {
+        true => None,
+        false => due_date(&row, &history, last_completed),
+    };
+
+    // people who are away when the task is due shouldn't be given it
+    let absent = match due {
+        Some(due) => {
+            sqlx::query_as::<_, (String,)>(include_str!("./select_absent_participants.sql"))
+                .bind(task_id)
+                .bind(due)
+                .fetch_all(&mut *conn)
+                .await?
+        }
+        None => Vec::new(),
+    };
+
+    let assignee = match row.kind {
+        _ if row.archived => None,
+        Routine::OneOff => row.claimed_by.as_deref(),
+        // a triggered task may have been handed to whoever triggered it
+        _ => row.claimed_by.as_deref().or_else(|| {
+            next_assignee(
+                &row.rotation,
+                &row.participants,
+                &row.last_completed_by,
+                &history,
+                due,
+                |person| !absent.iter().any(|(absent,)| absent == person),
+            )
+        }),
+    };
+
+    sqlx::query(include_str!("./update_task_schedule.sql"))
+        .bind(due)
+        .bind(assignee)
+        .bind(task_id)
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(())
+}
+
+/// Lists a task's participants in rotation order, or fails if there is no such task.
+async fn task_participants(
+    conn: &mut SqliteConnection,
+    household: HouseholdId,
+    task_id: TaskId,
+) -> Result<Vec<String>, TaskStoreError> {
+    let participants =
+        sqlx::query_as::<_, (String,)>(include_str!("./select_task_participants.sql"))
+            .bind(task_id)
+            .bind(household)
+            .fetch_all(&mut *conn)
+            .await?;
+
+    // every task has at least one participant
+    if participants.is_empty() {
+        Err(TaskStoreError::UnknownTaskId(task_id))?;
+    }
+
+    Ok(participants
+        .into_iter()
+        .map(|(participant,)| participant)
+        .collect())
+}
+
+async fn refresh_all_schedules(
+    conn: &mut SqliteConnection,
+    household: HouseholdId,
+) -> Result<(), TaskStoreError> {
+    let task_ids = sqlx::query_as::<_, (TaskId,)>(include_str!("./select_all_task_ids.sql"))
+        .bind(household)
+        .fetch_all(&mut *conn)
+        .await?;
+    for (task_id,) in task_ids {
+        refresh_schedule(conn, task_id).await?;
+    }
+    Ok(())
+}
+
+/// When a task is next due, given the completions so far (in date order) and
+/// today's date.
+fn due_date(row: &ScheduleRow, history: &[Completion], today: NaiveDate) -> Option<NaiveDate> {
+    let duration = Duration::days(row.duration.into());
+    let last_completed = history.last().map_or(row.started_on, |completion| {
+        completion.completed_on.max(row.started_on)
+    });
+
+    // seasonal tasks skip any due dates outside of their active window
+    match (row.kind, &row.recurrence, &row.active_window) {
+        (Routine::OneOff | Routine::Triggered, _, _) => row.due_on,
+        (Routine::Calendar, Some(recurrence), window) => {
+            let mut occurrences = recurrence.occurrences(row.started_on + duration);
+            let due = match window {
+                Some(window) => window.nth_active(occurrences, history.len()),
+                None => occurrences.nth(history.len()),
+            };
+            Some(due.unwrap_or(last_completed))
+        }
+        (Routine::Schedule, _, window) => {
+            let cycles = cycles_done(row, history, today);
+            match window {
+                Some(window) => window.nth_active(cycle_ends(row), cycles),
+                None => Some(row.started_on + duration * (cycles as i32 + 1)),
+            }
+        }
+        (_, _, window) => {
+            let due = last_completed + duration;
+            Some(window.map_or(due, |window| window.next_active_day(due)))
+        }
+    }
+}
+
+/// The dates that each cycle of a schedule task ends on, i.e. when it is due.
+fn cycle_ends(row: &ScheduleRow) -> impl Iterator<Item = NaiveDate> + '_ {
+    let duration = Duration::days(row.duration.into());
+    (1..).map(move |period| row.started_on + duration * period)
+}
+
+/// How many cycles of a schedule task ended (in its active window) before the
+/// given date.
+fn cycles_before(row: &ScheduleRow, date: NaiveDate) -> usize {
+    // a cycle of zero days would never end
+    if row.duration == 0 {
+        return 0;
+    }
+    let ended = cycle_ends(row).take_while(|end| *end < date);
+    match row.active_window {
+        Some(window) => ended.filter(|end| window.contains(*end)).count(),
+        None => ended.count(),
+    }
+}
+
+/// How many cycles of a schedule task have been dealt with, depending on what
+/// its catch-up policy does with the ones that were missed.
+fn cycles_done(row: &ScheduleRow, history: &[Completion], today: NaiveDate) -> usize {
+    if row.catch_up == CatchUp::Accumulate {
+        return history.len();
+    }
+
+    let done = history.iter().fold(0, |done, completion| {
+        let missed = cycles_before(row, completion.completed_on);
+        match row.catch_up {
+            // the completion makes up for the missed cycles
+            CatchUp::Collapse => (done + 1).max(missed),
+            // the missed cycles were already dropped, so the completion does
+            // the current one
+            _ => done.max(missed) + 1,
+        }
+    });
+    match row.catch_up {
+        CatchUp::Skip => done.max(cycles_before(row, today)),
+        _ => done,
+    }
+}
+
+/// Replays a task into the future, pretending that each time it is due it gets
+/// done by whoever it is assigned to (or today, if it is already overdue).
+/// Lists every due date up to and including `until`, along with the assignee.
+fn project(
+    row: &ScheduleRow,
+    mut history: Vec<Completion>,
+    absences: &[Absence],
+    today: NaiveDate,
+    until: NaiveDate,
+) -> Vec<(NaiveDate, Option<String>)> {
+    // one-off and triggered tasks are gone once they have been done, and
+    // archived tasks are gone for good
+    let recurring = is_recurring(row.kind);
+    if row.archived || (row.kind == Routine::OneOff && !history.is_empty()) {
+        return Vec::new();
+    }
+
+    let mut last_completed_by = row.last_completed_by.clone();
+    let mut projected = Vec::new();
+    while let Some(due) = due_date(row, &history, today) {
+        // guards against routines that never move forward, e.g. a finished
+        // calendar rule or an interval of zero days
+        let stuck = projected.last().is_some_and(|(last, _)| due <= *last);
+        if due > until || stuck {
+            break;
+        }
+
+        let is_available = |person: &str| {
+            !absences.iter().any(|absence| {
+                absence.person == person && absence.starts_on <= due && due <= absence.ends_on
+            })
+        };
+        let assignee = match row.kind {
+            Routine::OneOff => row.claimed_by.clone(),
+            // a triggered task may have been handed to whoever triggered it
+            _ => row.claimed_by.clone().or_else(|| {
+                next_assignee(
+                    &row.rotation,
+                    &row.participants,
+                    &last_completed_by,
+                    &history,
+                    Some(due),
+                    is_available,
+                )
+                .map(str::to_owned)
+            }),
+        };
+        projected.push((due, assignee.clone()));
+        if !recurring {
+            break;
+        }
+
+        let completed_by = assignee.unwrap_or_else(|| last_completed_by.clone());
+        history.push(Completion {
+            completed_by: completed_by.clone(),
+            helpers: Vec::new(),
+            completed_on: due.max(today),
+        });
+        last_completed_by = completed_by;
+    }
+
+    projected
+}
+
+/// Checks that a task's recurrence rule and active window make sense for its
+/// routine.
+fn check_routine(
+    routine: Routine,
+    recurrence: Option<&Recurrence>,
+    active_window: Option<&ActiveWindow>,
+    starts_on: NaiveDate,
+) -> Result<(), TaskStoreError> {
+    match (routine, recurrence) {
+        (Routine::Calendar, Some(recurrence)) => {
+            if recurrence.occurrences(starts_on).next().is_none() {
+                Err(TaskStoreError::RecurrenceNeverOccurs)?;
+            }
+        }
+        (Routine::Calendar, None) | (_, Some(_)) => Err(TaskStoreError::RecurrenceMismatch)?,
+        (_, None) => {}
+    }
+
+    if active_window.is_some() && !is_recurring(routine) {
+        Err(TaskStoreError::ActiveWindowMismatch)?;
+    }
+
+    Ok(())
+}
+
+/// Whether a task keeps coming back once it has been done.
+fn is_recurring(routine: Routine) -> bool {
+    matches!(
+        routine,
+        Routine::Schedule | Routine::Interval | Routine::Calendar
+    )
+}
+
+/// Makes `%`, `_` and `\` match themselves in a `LIKE` pattern escaped with `\`.
+fn escape_like(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+#[derive(Default)]
+pub struct NewTask {
+    pub names: HashMap<String, String>,
+    /// Markdown descriptions, keyed by language (ignored for languages without a name)
+    pub descriptions: HashMap<String, String>,
+    /// Checklist steps in order, each with names keyed by language
+    pub checklist: Vec<HashMap<String, String>>,
+    pub routine: Routine,
+    pub duration: u16,
+    pub participants: Vec<String>,
+    pub starts_on: NaiveDate,
+    pub starts_with: String,
+    pub rotation: Rotation,
+    /// Only used by schedule tasks
+    pub catch_up: CatchUp,
+    pub recurrence: Option<Recurrence>,
+    /// Only used by one-off tasks
+    pub due_on: Option<NaiveDate>,
+    /// Limits a recurring task to part of the year
+    pub active_window: Option<ActiveWindow>,
+    pub urgency: Urgency,
+    /// Points earned by each person who does the task
+    pub points: u32,
+    /// Matches the task up with a household config file
+    pub config_key: Option<String>,
+}
+
+/// The parts of a task that can be changed after it has been created, as set
+/// by a household config file.
+#[derive(Debug, Clone, PartialEq, Eq, Default, serde::Deserialize, serde::Serialize)]
+pub struct TaskSettings {
+    pub names: HashMap<String, String>,
+    /// Markdown descriptions, keyed by language (ignored for languages without a name)
+    #[serde(default)]
+    pub descriptions: HashMap<String, String>,
+    pub routine: Routine,
+    pub duration: u16,
+    #[serde(default)]
+    pub rotation: Rotation,
+    #[serde(default)]
+    pub catch_up: CatchUp,
+    pub recurrence: Option<Recurrence>,
+    pub active_window: Option<ActiveWindow>,
+    #[serde(default)]
+    pub urgency: Urgency,
+    #[serde(default)]
+    pub points: u32,
+}
+
+impl TaskSettings {
+    /// Checks that the settings make sense for a task with the given
+    /// participants, which starts on `starts_on`.
+    pub fn check(
+        &self,
+        participants: &[String],
+        starts_on: NaiveDate,
+    ) -> Result<(), TaskStoreError> {
+        check_routine(
+            self.routine,
+            self.recurrence.as_ref(),
+            self.active_window.as_ref(),
+            starts_on,
+        )?;
+
+        if let Rotation::FixedOwner(owner) = &self.rotation {
+            if !participants
+                .iter()
+                .any(|person| person.to_lowercase() == owner.to_lowercase())
+            {
+                Err(TaskStoreError::PersonNotParticipant(owner.to_owned()))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A task that came from a household config file, as it is now.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfiguredTask {
+    pub id: TaskId,
+    pub key: String,
+    pub archived: bool,
+    pub settings: TaskSettings,
+    /// In rotation order
+    pub participants: Vec<String>,
+}
+
+/// Everything about a task apart from its checklist, tags and chains, for
+/// moving it to another database.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct ExportedTask {
+    /// Matches the task up with a household config file
+    pub key: Option<String>,
+    #[serde(flatten)]
+    pub settings: TaskSettings,
+    /// In rotation order
+    pub participants: Vec<String>,
+    /// Only used by one-off and triggered tasks
+    pub due_on: Option<NaiveDate>,
+    pub claimed_by: Option<String>,
+    pub archived_on: Option<NaiveDate>,
+    /// In date order, starting with the initial ones
+    pub completions: Vec<ExportedCompletion>,
+}
+
+impl ExportedTask {
+    /// Checks that the task can be imported as a new task, apart from whether
+    /// the people in it exist.
+    pub fn check(&self) -> Result<(), TaskStoreError> {
+        if self.participants.is_empty() {
+            Err(TaskStoreError::NoParticipants)?;
+        }
+        // the initial completions mark when the task started
+        let Some(start) = self
+            .completions
+            .iter()
+            .find(|completion| completion.initial)
+        else {
+            Err(TaskStoreError::NoInitialCompletion)?
+        };
+        check_routine(
+            self.settings.routine,
+            self.settings.recurrence.as_ref(),
+            self.settings.active_window.as_ref(),
+            start.completed_on,
+        )
+    }
+
+    /// Everyone the task mentions, as participants, claiming it or completing it.
+    pub fn people(&self) -> impl Iterator<Item = &str> {
+        self.participants
+            .iter()
+            .chain(&self.claimed_by)
+            .chain(self.completions.iter().flat_map(|completion| {
+                std::iter::once(&completion.completed_by).chain(&completion.helpers)
+            }))
+            .map(String::as_str)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct ExportedCompletion {
+    pub completed_by: String,
+    #[serde(default)]
+    pub helpers: Vec<String>,
+    pub completed_on: NaiveDate,
+    #[serde(default)]
+    pub note: Option<String>,
+    /// Marks when the task started, rather than a time it was done
+    #[serde(default)]
+    pub initial: bool,
+}
+
+/// Filters and sorting for [`TaskStore::find_tasks`].  Anything left unset
+/// matches every task.
+#[derive(Debug, Default, serde::Deserialize)]
+pub struct TaskQuery {
+    pub assignee: Option<String>,
+    pub participant: Option<String>,
+    pub routine: Option<Routine>,
+    pub status: Option<DueStatus>,
+    /// Earliest due date to include
+    pub due_from: Option<NaiveDate>,
+    /// Latest due date to include
+    pub due_until: Option<NaiveDate>,
+    /// Matches against the task's name in any language
+    pub search: Option<String>,
+    /// Tasks are listed in the order they were created if this is not set
+    pub sort: Option<TaskSort>,
+    #[serde(default)]
+    pub order: SortOrder,
+    pub tag: Option<TagId>,
+}
+
+impl TaskQuery {
+    /// Whether a task that is due on `due` matches the query's due status and
+    /// dates.
+    fn matches_due(&self, due: Option<NaiveDate>, today: NaiveDate) -> bool {
+        let filtered = self.status.is_some() || self.due_from.is_some() || self.due_until.is_some();
+        let Some(due) = due else {
+            return !filtered;
+        };
+        let status_matches = match self.status {
+            Some(DueStatus::Overdue) => due < today,
+            Some(DueStatus::Upcoming) => due >= today,
+            None => true,
+        };
+        status_matches
+            && self.due_from.is_none_or(|from| due >= from)
+            && self.due_until.is_none_or(|until| due <= until)
+    }
+}
+
+#[derive(Debug, Default, serde::Deserialize)]
+pub struct NewTag {
+    /// Tag names, keyed by language
+    pub names: HashMap<String, String>,
+}
+
+/// Everything else that can be recorded about a task being done.
+#[derive(Debug, Default)]
+pub struct CompletionDetails {
+    /// Everyone else who did the task at the same time
+    pub helpers: Vec<String>,
+    pub note: Option<String>,
+    /// Images such as a photo of the finished job
+    pub attachments: Vec<NewAttachment>,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{auth::AuthStore, tasks::time};
+
+    use super::*;
+
+    fn today() -> NaiveDate {
+        time::today_in(None)
+    }
+
+    fn names(names: &[(&str, &str)]) -> HashMap<String, String> {
+        names
+            .iter()
+            .map(|(l, r)| (l.to_string(), r.to_string()))
+            .collect()
+    }
+
+    #[sqlx::test]
+    async fn listing_tasks_for_empty_store_gives_no_results(conn: sqlx::SqlitePool) {
+        time::mock::set(NaiveDate::from_ymd_opt(2020, 1, 10).unwrap());
+        let task_store = TaskStore::new(conn, HouseholdId::default());
+        assert_eq!(task_store.tasks(&"en".into()).await.unwrap(), vec![]);
+    }
+
+    #[sqlx::test]
+    async fn listing_tasks_when_tasks_exist(conn: sqlx::SqlitePool) {
+        time::mock::set(NaiveDate::from_ymd_opt(2020, 1, 10).unwrap());
+        let task_store = TaskStore::new(conn.clone(), HouseholdId::default());
+        let auth_store = AuthStore::new(conn);
+        auth_store.create_test_user("arthur").await.unwrap();
+        auth_store.create_test_user("bob").await.unwrap();
+        auth_store.create_test_user("claire").await.unwrap();
+        task_store
+            .add_task(NewTask {
+                names: names(&[("en", "Test Task 1")]),
+                starts_with: "bob".into(),
+                routine: Routine::Interval,
+                duration: 7,
+                starts_on: NaiveDate::from_ymd_opt(2020, 1, 10).unwrap(),
+                participants: vec!["arthur".into(), "bob".into(), "claire".into()],
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        task_store
+            .add_task(NewTask {
+                names: names(&[("en", "Test Task 2")]),
+                starts_with: "claire".into(),
+                routine: Routine::Interval,
+                duration: 7,
+                starts_on: NaiveDate::from_ymd_opt(2020, 1, 14).unwrap(),
+                participants: vec!["arthur".into(), "bob".into(), "claire".into()],
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        let tasks = task_store.tasks(&"en".into()).await.unwrap();
+        assert_eq!(tasks.len(), 2);
+        assert_eq!(tasks[0].name, "Test Task 1".to_owned());
+        assert_eq!(tasks[1].name, "Test Task 2".to_owned());
+    }
+
+    #[sqlx::test]
+    async fn returns_tasks_for_a_particular_person(conn: sqlx::SqlitePool) {
+        time::mock::set(NaiveDate::from_ymd_opt(2020, 1, 10).unwrap());
+        let task_store = TaskStore::new(conn.clone(), HouseholdId::default());
+        let auth_store = AuthStore::new(conn);
+        auth_store.create_test_user("arthur").await.unwrap();
+        auth_store.create_test_user("claire").await.unwrap();
+        auth_store.create_test_user("bob").await.unwrap();
+        task_store
+            .add_task(NewTask {
+                names: names(&[("en", "Test Task 1")]),
+                starts_with: "bob".into(),
+                routine: Routine::Interval,
+                duration: 7,
+                starts_on: NaiveDate::from_ymd_opt(2020, 1, 15).unwrap(),
+                participants: vec!["arthur".into(), "bob".into(), "claire".into()],
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        task_store
+            .add_task(NewTask {
+                names: names(&[("en", "Test Task 2")]),
+                starts_with: "claire".into(),
+                routine: Routine::Interval,
+                duration: 7,
+                starts_on: NaiveDate::from_ymd_opt(2020, 1, 15).unwrap(),
+                participants: vec!["claire".into(), "bob".into(), "arthur".into()],
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        task_store
+            .add_task(NewTask {
+                names: names(&[("en", "Test Task 3")]),
+                starts_with: "bob".into(),
+                routine: Routine::Interval,
+                duration: 7,
+                starts_on: NaiveDate::from_ymd_opt(2020, 1, 15).unwrap(),
+                participants: vec!["claire".into(), "bob".into(), "arthur".into()],
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        let bobs_tasks = task_store.tasks_for("bob", &"en".into()).await.unwrap();
+        assert_eq!(bobs_tasks.len(), 2);
+        assert_eq!(bobs_tasks[0].name, "Test Task 1");
+        assert_eq!(bobs_tasks[1].name, "Test Task 3");
+
+        let claires_tasks = task_store.tasks_for("claire", &"en".into()).await.unwrap();
+        assert_eq!(claires_tasks.len(), 1);
+        assert_eq!(claires_tasks[0].name, "Test Task 2");
+
+        let arthurs_tasks = task_store.tasks_for("arthur", &"en".into()).await.unwrap();
+        assert_eq!(arthurs_tasks.len(), 0);
+
+        assert_eq!(
+            bobs_tasks[0].participants,
+            vec!["arthur".to_owned(), "bob".to_owned(), "claire".to_owned()]
+        );
+        assert_eq!(
+            bobs_tasks[1].participants,
+            vec!["claire".to_owned(), "bob".to_owned(), "arthur".to_owned()]
+        );
+    }
+
+    #[sqlx::test]
+    async fn returns_created_task(conn: sqlx::SqlitePool) {
+        time::mock::set(NaiveDate::from_ymd_opt(2020, 1, 10).unwrap());
+        let task_store = TaskStore::new(conn.clone(), HouseholdId::default());
+        let auth_store = AuthStore::new(conn);
+        auth_store.create_test_user("arthur").await.unwrap();
+        auth_store.create_test_user("bob").await.unwrap();
+        task_store
+            .add_task(NewTask {
+                names: names(&[("en", "Test Task 1")]),
+                starts_with: "bob".into(),
+                routine: Routine::Interval,
+                duration: 7,
+                starts_on: NaiveDate::from_ymd_opt(2020, 1, 10).unwrap(),
+                participants: vec!["arthur".into(), "bob".into()],
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        task_store
+            .add_task(NewTask {
+                names: names(&[("en", "Test Task 2")]),
+                starts_with: "bob".into(),
+                routine: Routine::Interval,
+                duration: 7,
+                starts_on: NaiveDate::from_ymd_opt(2020, 1, 15).unwrap(),
+                participants: vec!["arthur".into(), "bob".into()],
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        let task = task_store.task(1.into(), &"en".into()).await.unwrap();
+        assert_eq!(task.name, "Test Task 1".to_owned());
+        assert_eq!(
+            task.last_completed,
+            NaiveDate::from_ymd_opt(2020, 1, 3).unwrap()
+        );
+
+        let task = task_store.task(2.into(), &"en".into()).await.unwrap();
+        assert_eq!(task.name, "Test Task 2".to_owned());
+        assert_eq!(
+            task.last_completed,
+            NaiveDate::from_ymd_opt(2020, 1, 8).unwrap()
+        );
+    }
+
+    #[sqlx::test]
+    async fn completing_interval_task_returns_updated_task(conn: sqlx::SqlitePool) {
+        time::mock::set(NaiveDate::from_ymd_opt(2020, 1, 10).unwrap());
+        let task_store = TaskStore::new(conn.clone(), HouseholdId::default());
+        let auth_store = AuthStore::new(conn);
+        auth_store.create_test_user("arthur").await.unwrap();
+        auth_store.create_test_user("bob").await.unwrap();
+        task_store
+            .add_task(NewTask {
+                names: names(&[("en", "Task")]),
+                starts_with: "arthur".into(),
+                routine: Routine::Interval,
+                duration: 7,
+                starts_on: NaiveDate::from_ymd_opt(2020, 1, 12).unwrap(),
+                participants: vec!["arthur".into(), "bob".into()],
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        task_store
+            .mark_task_done(1.into(), "arthur", &today(), None)
+            .await
+            .unwrap();
+        let task = task_store.task(1.into(), &"en".into()).await.unwrap();
+        assert_eq!(task.name, "Task".to_owned());
+        assert_eq!(task.assigned_to, Some("bob".to_owned()));
+        assert_eq!(
+            task.last_completed,
+            NaiveDate::from_ymd_opt(2020, 1, 10).unwrap()
+        );
+        assert_eq!(task.deadline, Deadline::Upcoming(7));
+    }
+
+    #[sqlx::test]
+    async fn completing_a_task_on_its_start_day_keeps_one_of_each_participant(
+        conn: sqlx::SqlitePool,
+    ) {
+        time::mock::set(NaiveDate::from_ymd_opt(2020, 1, 10).unwrap());
+        let task_store = TaskStore::new(conn.clone(), HouseholdId::default());
+        let auth_store = AuthStore::new(conn);
+        auth_store.create_test_user("arthur").await.unwrap();
+        auth_store.create_test_user("bob").await.unwrap();
+        // the initial completion is a cycle before it starts, i.e. today
+        task_store
+            .add_task(NewTask {
+                names: names(&[("en", "Task")]),
+                starts_with: "arthur".into(),
+                routine: Routine::Interval,
+                duration: 7,
+                starts_on: NaiveDate::from_ymd_opt(2020, 1, 17).unwrap(),
+                participants: vec!["arthur".into(), "bob".into()],
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        task_store
+            .mark_task_done(1.into(), "arthur", &today(), None)
+            .await
+            .unwrap();
+        let task = task_store.task(1.into(), &"en".into()).await.unwrap();
+        assert_eq!(task.participants, vec!["arthur", "bob"]);
+        assert_eq!(task.assigned_to.as_deref(), Some("bob"));
+        let tasks = task_store.tasks(&"en".into()).await.unwrap();
+        assert_eq!(tasks[0].participants, vec!["arthur", "bob"]);
+    }
+
+    #[sqlx::test]
+    async fn completing_schedule_task_returns_updated_task(conn: sqlx::SqlitePool) {
+        time::mock::set(NaiveDate::from_ymd_opt(2020, 1, 10).unwrap());
+        let task_store = TaskStore::new(conn.clone(), HouseholdId::default());
+        let auth_store = AuthStore::new(conn);
+        auth_store.create_test_user("arthur").await.unwrap();
+        auth_store.create_test_user("bob").await.unwrap();
+        task_store
+            .add_task(NewTask {
+                names: names(&[("en", "Task")]),
+                starts_with: "arthur".into(),
+                routine: Routine::Schedule,
+                duration: 7,
+                starts_on: NaiveDate::from_ymd_opt(2020, 1, 12).unwrap(),
+                participants: vec!["arthur".into(), "bob".into()],
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        task_store
+            .mark_task_done(1.into(), "arthur", &today(), None)
+            .await
+            .unwrap();
+        let task = task_store.task(1.into(), &"en".into()).await.unwrap();
+        assert_eq!(task.name, "Task".to_owned());
+        assert_eq!(task.assigned_to, Some("bob".to_owned()));
+        assert_eq!(
+            task.last_completed,
+            NaiveDate::from_ymd_opt(2020, 1, 12).unwrap()
+        );
+        assert_eq!(task.deadline, Deadline::Upcoming(9));
+    }
+
+    #[sqlx::test]
+    async fn completing_period_schedule_tasks_rolls_the_date_forward(conn: sqlx::SqlitePool) {
+        time::mock::set(NaiveDate::from_ymd_opt(2020, 1, 14).unwrap());
+        let task_store = TaskStore::new(conn.clone(), HouseholdId::default());
+        let auth_store = AuthStore::new(conn);
+        auth_store.create_test_user("arthur").await.unwrap();
+        auth_store.create_test_user("bob").await.unwrap();
+        task_store
+            .add_task(NewTask {
+                names: names(&[("en", "Task")]),
+                starts_with: "arthur".into(),
+                routine: Routine::Schedule,
+                duration: 7,
+                starts_on: NaiveDate::from_ymd_opt(2020, 1, 1).unwrap(),
+                participants: vec!["arthur".into(), "bob".into()],
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        // complete for period until 1st
+        task_store
+            .mark_task_done(1.into(), "arthur", &today(), None)
+            .await
+            .unwrap();
+        let task = task_store.task(1.into(), &"en".into()).await.unwrap();
+        assert_eq!(task.assigned_to, Some("bob".to_owned()));
+        assert_eq!(task.deadline, Deadline::Overdue(6));
+
+        // complete for period 8th - 14th
+        task_store
+            .mark_task_done(1.into(), "bob", &today(), None)
+            .await
+            .unwrap();
+        let task = task_store.task(1.into(), &"en".into()).await.unwrap();
+        assert_eq!(task.assigned_to, Some("arthur".to_owned())); // assignee updated
+        assert_eq!(task.deadline, Deadline::Upcoming(1)); // next period starts on 8th and continues for 7 days
+    }
+
+    #[sqlx::test]
+    async fn completing_schedule_task_multiple_times(conn: sqlx::SqlitePool) {
+        time::mock::set(NaiveDate::from_ymd_opt(2020, 1, 14).unwrap());
+        let task_store = TaskStore::new(conn.clone(), HouseholdId::default());
+        let auth_store = AuthStore::new(conn);
+        auth_store.create_test_user("arthur").await.unwrap();
+        auth_store.create_test_user("bob").await.unwrap();
+        task_store
+            .add_task(NewTask {
+                names: names(&[("en", "Task")]),
+                starts_with: "arthur".into(),
+                routine: Routine::Schedule,
+                duration: 7,
+                starts_on: NaiveDate::from_ymd_opt(2020, 1, 10).unwrap(),
+                participants: vec!["arthur".into(), "bob".into()],
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        let task = task_store.task(1.into(), &"en".into()).await.unwrap();
+        assert_eq!(task.assigned_to, Some("arthur".to_owned()));
+        assert_eq!(task.deadline, Deadline::Overdue(4));
+
+        task_store
+            .mark_task_done(1.into(), "arthur", &today(), None)
+            .await
+            .unwrap();
+        let task = task_store.task(1.into(), &"en".into()).await.unwrap();
+        assert_eq!(task.assigned_to, Some("bob".to_owned()));
+        assert_eq!(task.deadline, Deadline::Upcoming(3));
+
+        task_store
+            .mark_task_done(1.into(), "bob", &today(), None)
+            .await
+            .unwrap();
+        let task = task_store.task(1.into(), &"en".into()).await.unwrap();
+        assert_eq!(task.assigned_to, Some("arthur".to_owned()));
+        assert_eq!(task.deadline, Deadline::Upcoming(10));
+
+        // the same person does the task multiple times in a row
+        task_store
+            .mark_task_done(1.into(), "bob", &today(), None)
+            .await
+            .unwrap();
+        let task = task_store.task(1.into(), &"en".into()).await.unwrap();
+        assert_eq!(task.assigned_to, Some("arthur".to_owned()));
+        assert_eq!(task.deadline, Deadline::Upcoming(17));
+    }
+
+    #[sqlx::test]
+    async fn missed_schedule_cycles_accumulate_by_default(conn: sqlx::SqlitePool) {
+        time::mock::set(NaiveDate::from_ymd_opt(2020, 1, 20).unwrap());
+        let task_store = TaskStore::new(conn.clone(), HouseholdId::default());
+        let auth_store = AuthStore::new(conn);
+        auth_store.create_test_user("arthur").await.unwrap();
+        for name in ["Task", "Other task"] {
+            task_store
+                .add_task(NewTask {
+                    names: names(&[("en", name)]),
+                    starts_with: "arthur".into(),
+                    routine: Routine::Schedule,
+                    duration: 7,
+                    starts_on: NaiveDate::from_ymd_opt(2020, 1, 1).unwrap(),
+                    participants: vec!["arthur".into()],
+                    ..Default::default()
+                })
+                .await
+                .unwrap();
+        }
+
+        // three cycles have gone by, and each one needs doing
+        let task = task_store.task(1.into(), &"en".into()).await.unwrap();
+        assert_eq!(task.catch_up, CatchUp::Accumulate);
+        assert_eq!(task.deadline, Deadline::Overdue(19));
+        for _ in 0..2 {
+            task_store
+                .mark_task_done(2.into(), "arthur", &today(), None)
+                .await
+                .unwrap();
+        }
+        task_store
+            .mark_task_done(1.into(), "arthur", &today(), None)
+            .await
+            .unwrap();
+
+        // each task only counts its own completions
+        let task = task_store.task(1.into(), &"en".into()).await.unwrap();
+        assert_eq!(task.deadline, Deadline::Overdue(12));
+        assert_eq!(
+            task.last_completed,
+            NaiveDate::from_ymd_opt(2020, 1, 1).unwrap()
+        );
+        let task = task_store.task(2.into(), &"en".into()).await.unwrap();
+        assert_eq!(task.deadline, Deadline::Overdue(5));
+        assert_eq!(
+            task.last_completed,
+            NaiveDate::from_ymd_opt(2020, 1, 8).unwrap()
+        );
+    }
+
+    #[sqlx::test]
+    async fn missed_schedule_cycles_can_collapse_into_one(conn: sqlx::SqlitePool) {
+        time::mock::set(NaiveDate::from_ymd_opt(2020, 1, 20).unwrap());
+        let task_store = TaskStore::new(conn.clone(), HouseholdId::default());
+        let auth_store = AuthStore::new(conn);
+        auth_store.create_test_user("arthur").await.unwrap();
+        auth_store.create_test_user("bob").await.unwrap();
+        task_store
+            .add_task(NewTask {
+                names: names(&[("en", "Task")]),
+                starts_with: "arthur".into(),
+                routine: Routine::Schedule,
+                duration: 7,
+                starts_on: NaiveDate::from_ymd_opt(2020, 1, 1).unwrap(),
+                participants: vec!["arthur".into(), "bob".into()],
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        task_store
+            .set_catch_up(1.into(), CatchUp::Collapse)
+            .await
+            .unwrap();
+
+        // missed cycles are still overdue until the task is done
+        let task = task_store.task(1.into(), &"en".into()).await.unwrap();
+        assert_eq!(task.catch_up, CatchUp::Collapse);
+        assert_eq!(task.deadline, Deadline::Overdue(19));
+
+        // doing it once makes up for all of them, leaving the current cycle
+        task_store
+            .mark_task_done(1.into(), "arthur", &today(), None)
+            .await
+            .unwrap();
+        let task = task_store.task(1.into(), &"en".into()).await.unwrap();
+        assert_eq!(task.assigned_to, Some("bob".to_owned()));
+        assert_eq!(task.deadline, Deadline::Upcoming(2));
+        assert_eq!(
+            task.last_completed,
+            NaiveDate::from_ymd_opt(2020, 1, 15).unwrap()
+        );
+
+        // doing it on time moves on one cycle, as usual
+        task_store
+            .mark_task_done(1.into(), "bob", &today(), None)
+            .await
+            .unwrap();
+        let task = task_store.task(1.into(), &"en".into()).await.unwrap();
+        assert_eq!(task.deadline, Deadline::Upcoming(9));
+
+        assert!(matches!(
+            task_store.set_catch_up(99.into(), CatchUp::Skip).await,
+            Err(TaskStoreError::UnknownTaskId(_))
+        ));
+    }
+
+    #[sqlx::test]
+    async fn missed_schedule_cycles_can_be_skipped(conn: sqlx::SqlitePool) {
+        time::mock::set(NaiveDate::from_ymd_opt(2020, 1, 20).unwrap());
+        let task_store = TaskStore::new(conn.clone(), HouseholdId::default());
+        let auth_store = AuthStore::new(conn);
+        auth_store.create_test_user("arthur").await.unwrap();
+        auth_store.create_test_user("bob").await.unwrap();
+        task_store
+            .add_task(NewTask {
+                names: names(&[("en", "Task")]),
+                starts_with: "arthur".into(),
+                routine: Routine::Schedule,
+                duration: 7,
+                starts_on: NaiveDate::from_ymd_opt(2020, 1, 1).unwrap(),
+                participants: vec!["arthur".into(), "bob".into()],
+                catch_up: CatchUp::Skip,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        // cycles that are over have been dropped
+        let task = task_store.task(1.into(), &"en".into()).await.unwrap();
+        assert_eq!(task.assigned_to, Some("arthur".to_owned()));
+        assert_eq!(task.deadline, Deadline::Upcoming(2));
+
+        // and the task moves on as soon as the current cycle is over
+        time::mock::set(NaiveDate::from_ymd_opt(2020, 1, 23).unwrap());
+        let tasks = task_store.tasks(&"en".into()).await.unwrap();
+        assert_eq!(tasks[0].deadline, Deadline::Upcoming(6));
+        let query = |status| TaskQuery {
+            status: Some(status),
+            ..Default::default()
+        };
+        let upcoming = task_store
+            .find_tasks(&query(DueStatus::Upcoming), &"en".into())
+            .await
+            .unwrap();
+        assert_eq!(upcoming.len(), 1);
+        let overdue = task_store
+            .find_tasks(&query(DueStatus::Overdue), &"en".into())
+            .await
+            .unwrap();
+        assert!(overdue.is_empty());
+
+        // doing it does the current cycle
+        task_store
+            .mark_task_done(1.into(), "arthur", &today(), None)
+            .await
+            .unwrap();
+        let task = task_store.task(1.into(), &"en".into()).await.unwrap();
+        assert_eq!(task.assigned_to, Some("bob".to_owned()));
+        assert_eq!(task.deadline, Deadline::Upcoming(13));
+        assert_eq!(
+            task.last_completed,
+            NaiveDate::from_ymd_opt(2020, 1, 29).unwrap()
+        );
+    }
+
+    #[sqlx::test]
+    async fn completing_interval_task_multiple_times(conn: sqlx::SqlitePool) {
+        time::mock::set(NaiveDate::from_ymd_opt(2020, 1, 14).unwrap());
+        let task_store = TaskStore::new(conn.clone(), HouseholdId::default());
+        let auth_store = AuthStore::new(conn);
+        auth_store.create_test_user("arthur").await.unwrap();
+        auth_store.create_test_user("bob").await.unwrap();
+        task_store
+            .add_task(NewTask {
+                names: names(&[("en", "Task")]),
+                starts_with: "arthur".into(),
+                routine: Routine::Interval,
+                duration: 7,
+                starts_on: NaiveDate::from_ymd_opt(2020, 1, 10).unwrap(),
+                participants: vec!["arthur".into(), "bob".into()],
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        let task = task_store.task(1.into(), &"en".into()).await.unwrap();
+        assert_eq!(task.assigned_to, Some("arthur".to_owned()));
+        assert_eq!(task.deadline, Deadline::Overdue(4));
+
+        task_store
+            .mark_task_done(1.into(), "arthur", &today(), None)
+            .await
+            .unwrap();
+        let task = task_store.task(1.into(), &"en".into()).await.unwrap();
+        assert_eq!(task.assigned_to, Some("bob".to_owned()));
+        assert_eq!(task.deadline, Deadline::Upcoming(7));
+
+        task_store
+            .mark_task_done(1.into(), "bob", &today(), None)
+            .await
+            .unwrap();
+        let task = task_store.task(1.into(), &"en".into()).await.unwrap();
+        assert_eq!(task.assigned_to, Some("arthur".to_owned()));
+        assert_eq!(task.deadline, Deadline::Upcoming(7));
+
+        // the same person does the task multiple times in a row
+        task_store
+            .mark_task_done(1.into(), "bob", &today(), None)
+            .await
+            .unwrap();
+        let task = task_store.task(1.into(), &"en".into()).await.unwrap();
+        assert_eq!(task.assigned_to, Some("arthur".to_owned()));
+        assert_eq!(task.deadline, Deadline::Upcoming(7));
+    }
+
+    #[sqlx::test]
+    async fn handles_tasks_with_only_one_participant(conn: sqlx::SqlitePool) {
+        time::mock::set(NaiveDate::from_ymd_opt(2020, 1, 14).unwrap());
+        let task_store = TaskStore::new(conn.clone(), HouseholdId::default());
+        let auth_store = AuthStore::new(conn);
+        auth_store.create_test_user("arthur").await.unwrap();
+        task_store
+            .add_task(NewTask {
+                names: names(&[("en", "Interval Task")]),
+                starts_with: "arthur".into(),
+                routine: Routine::Interval,
+                duration: 7,
+                starts_on: NaiveDate::from_ymd_opt(2020, 1, 10).unwrap(),
+                participants: vec!["arthur".into()],
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        task_store
+            .add_task(NewTask {
+                names: names(&[("en", "Schedule Task")]),
+                starts_with: "arthur".into(),
+                routine: Routine::Schedule,
+                duration: 7,
+                starts_on: NaiveDate::from_ymd_opt(2020, 1, 10).unwrap(),
+                participants: vec!["arthur".into()],
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        let task = task_store.task(1.into(), &"en".into()).await.unwrap();
+        assert_eq!(task.assigned_to, Some("arthur".to_owned()));
+        assert_eq!(task.deadline, Deadline::Overdue(4));
+
+        task_store
+            .mark_task_done(2.into(), "arthur", &today(), None)
+            .await
+            .unwrap();
+        let task = task_store.task(2.into(), &"en".into()).await.unwrap();
+        assert_eq!(task.assigned_to, Some("arthur".to_owned()));
+        assert_eq!(task.deadline, Deadline::Upcoming(3));
+    }
+
+    #[sqlx::test]
+    async fn least_recently_done_rotation_rebalances_after_out_of_turn_completions(
+        conn: sqlx::SqlitePool,
+    ) {
+        time::mock::set(NaiveDate::from_ymd_opt(2020, 1, 14).unwrap());
+        let task_store = TaskStore::new(conn.clone(), HouseholdId::default());
+        let auth_store = AuthStore::new(conn);
+        auth_store.create_test_user("arthur").await.unwrap();
+        auth_store.create_test_user("bob").await.unwrap();
+        auth_store.create_test_user("claire").await.unwrap();
+        task_store
+            .add_task(NewTask {
+                names: names(&[("en", "Task")]),
+                starts_with: "arthur".into(),
+                routine: Routine::Interval,
+                duration: 7,
+                starts_on: NaiveDate::from_ymd_opt(2020, 1, 10).unwrap(),
+                participants: vec!["arthur".into(), "bob".into(), "claire".into()],
+                rotation: Rotation::LeastRecentlyDone,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        let task = task_store.task(1.into(), &"en".into()).await.unwrap();
+        assert_eq!(task.assigned_to, Some("arthur".to_owned()));
+        assert_eq!(task.rotation, Rotation::LeastRecentlyDone);
+
+        task_store
+            .mark_task_done(1.into(), "arthur", &today(), None)
+            .await
+            .unwrap();
+        // claire does the task instead of bob
+        task_store
+            .mark_task_done(1.into(), "claire", &today(), None)
+            .await
+            .unwrap();
+        let task = task_store.task(1.into(), &"en".into()).await.unwrap();
+        assert_eq!(task.assigned_to, Some("bob".to_owned()));
+    }
+
+    #[sqlx::test]
+    async fn fixed_owner_must_be_a_participant(conn: sqlx::SqlitePool) {
+        let task_store = TaskStore::new(conn.clone(), HouseholdId::default());
+        let auth_store = AuthStore::new(conn);
+        auth_store.create_test_user("arthur").await.unwrap();
+        auth_store.create_test_user("bob").await.unwrap();
+        let result = task_store
+            .add_task(NewTask {
+                names: names(&[("en", "Task")]),
+                starts_with: "arthur".into(),
+                routine: Routine::Interval,
+                duration: 7,
+                starts_on: NaiveDate::from_ymd_opt(2020, 1, 10).unwrap(),
+                participants: vec!["arthur".into()],
+                rotation: Rotation::FixedOwner("bob".into()),
+                ..Default::default()
+            })
+            .await
+            .unwrap_err();
+
+        match result {
+            TaskStoreError::PersonNotParticipant(name) => assert_eq!(name, "bob"),
+            _ => panic!("incorrect error response"),
+        }
+    }
+
+    #[sqlx::test]
+    async fn calendar_tasks_are_due_on_the_next_occurrence(conn: sqlx::SqlitePool) {
+        // 2020-01-14 is a Tuesday
+        time::mock::set(NaiveDate::from_ymd_opt(2020, 1, 14).unwrap());
+        let task_store = TaskStore::new(conn.clone(), HouseholdId::default());
+        let auth_store = AuthStore::new(conn);
+        auth_store.create_test_user("arthur").await.unwrap();
+        auth_store.create_test_user("bob").await.unwrap();
+        task_store
+            .add_task(NewTask {
+                names: names(&[("en", "Bins")]),
+                starts_with: "arthur".into(),
+                routine: Routine::Calendar,
+                duration: 3,
+                starts_on: NaiveDate::from_ymd_opt(2020, 1, 14).unwrap(),
+                participants: vec!["arthur".into(), "bob".into()],
+                recurrence: Some("FREQ=WEEKLY;BYDAY=MO,TH".parse().unwrap()),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        let task = task_store.task(1.into(), &"en".into()).await.unwrap();
+        assert_eq!(task.assigned_to, Some("arthur".to_owned()));
+        assert_eq!(task.deadline, Deadline::Upcoming(2)); // Thursday
+
+        task_store
+            .mark_task_done(1.into(), "arthur", &today(), None)
+            .await
+            .unwrap();
+        let task = task_store.task(1.into(), &"en".into()).await.unwrap();
+        assert_eq!(task.assigned_to, Some("bob".to_owned()));
+        assert_eq!(task.deadline, Deadline::Upcoming(6)); // Monday
+        assert_eq!(task.last_completed, today());
+    }
+
+    #[sqlx::test]
+    async fn calendar_routines_need_a_recurrence_rule(conn: sqlx::SqlitePool) {
+        let task_store = TaskStore::new(conn.clone(), HouseholdId::default());
+        let auth_store = AuthStore::new(conn);
+        auth_store.create_test_user("arthur").await.unwrap();
+        let result = task_store
+            .add_task(NewTask {
+                names: names(&[("en", "Bins")]),
+                starts_with: "arthur".into(),
+                routine: Routine::Calendar,
+                duration: 7,
+                starts_on: NaiveDate::from_ymd_opt(2020, 1, 14).unwrap(),
+                participants: vec!["arthur".into()],
+                ..Default::default()
+            })
+            .await
+            .unwrap_err();
+
+        assert!(matches!(result, TaskStoreError::RecurrenceMismatch));
+    }
+
+    #[sqlx::test]
+    async fn one_off_tasks_disappear_once_completed(conn: sqlx::SqlitePool) {
+        time::mock::set(NaiveDate::from_ymd_opt(2020, 1, 14).unwrap());
+        let task_store = TaskStore::new(conn.clone(), HouseholdId::default());
+        let auth_store = AuthStore::new(conn);
+        auth_store.create_test_user("arthur").await.unwrap();
+        auth_store.create_test_user("bob").await.unwrap();
+        task_store
+            .add_task(NewTask {
+                names: names(&[("en", "Fix the shelf")]),
+                starts_with: "bob".into(),
+                routine: Routine::OneOff,
+                starts_on: NaiveDate::from_ymd_opt(2020, 1, 10).unwrap(),
+                participants: vec!["arthur".into(), "bob".into()],
+                due_on: Some(NaiveDate::from_ymd_opt(2020, 1, 20).unwrap()),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        let tasks = task_store.tasks(&"en".into()).await.unwrap();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].assigned_to, Some("bob".to_owned()));
+        assert_eq!(tasks[0].deadline, Deadline::Upcoming(6));
+
+        task_store
+            .mark_task_done(1.into(), "bob", &today(), None)
+            .await
+            .unwrap();
+        assert_eq!(task_store.tasks(&"en".into()).await.unwrap(), vec![]);
+
+        // the task is still around if requested directly
+        let task = task_store.task(1.into(), &"en".into()).await.unwrap();
+        assert_eq!(task.last_completed, today());
+    }
+
+    #[sqlx::test]
+    async fn one_off_tasks_without_an_assignee_can_be_claimed(conn: sqlx::SqlitePool) {
+        time::mock::set(NaiveDate::from_ymd_opt(2020, 1, 14).unwrap());
+        let task_store = TaskStore::new(conn.clone(), HouseholdId::default());
+        let auth_store = AuthStore::new(conn);
+        auth_store.create_test_user("arthur").await.unwrap();
+        auth_store.create_test_user("bob").await.unwrap();
+        auth_store.create_test_user("claire").await.unwrap();
+        task_store
+            .add_task(NewTask {
+                names: names(&[("en", "Fix the shelf")]),
+                routine: Routine::OneOff,
+                starts_on: NaiveDate::from_ymd_opt(2020, 1, 10).unwrap(),
+                participants: vec!["arthur".into(), "bob".into()],
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        let task = task_store.task(1.into(), &"en".into()).await.unwrap();
+        assert_eq!(task.assigned_to, None);
+        assert_eq!(task.deadline, Deadline::Unscheduled);
+
+        let result = task_store.claim_task(1.into(), "claire").await.unwrap_err();
+        assert!(matches!(result, TaskStoreError::PersonNotParticipant(_)));
+
+        task_store.claim_task(1.into(), "Arthur").await.unwrap();
+        let task = task_store.task(1.into(), &"en".into()).await.unwrap();
+        assert_eq!(task.assigned_to, Some("arthur".to_owned()));
+
+        let arthurs_tasks = task_store.tasks_for("arthur", &"en".into()).await.unwrap();
+        assert_eq!(arthurs_tasks.len(), 1);
+    }
+
+    #[sqlx::test]
+    async fn recurring_tasks_cannot_be_claimed(conn: sqlx::SqlitePool) {
+        let task_store = TaskStore::new(conn.clone(), HouseholdId::default());
+        let auth_store = AuthStore::new(conn);
+        auth_store.create_test_user("arthur").await.unwrap();
+        task_store
+            .add_task(NewTask {
+                names: names(&[("en", "Task")]),
+                starts_with: "arthur".into(),
+                routine: Routine::Interval,
+                duration: 7,
+                starts_on: NaiveDate::from_ymd_opt(2020, 1, 10).unwrap(),
+                participants: vec!["arthur".into()],
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        let result = task_store.claim_task(1.into(), "arthur").await.unwrap_err();
+        assert!(matches!(result, TaskStoreError::TaskNotClaimable(_)));
+    }
+
+    async fn add_filtering_fixtures(conn: &sqlx::SqlitePool) -> TaskStore {
+        let task_store = TaskStore::new(conn.clone(), HouseholdId::default());
+        let auth_store = AuthStore::new(conn.clone());
+        auth_store.create_test_user("arthur").await.unwrap();
+        auth_store.create_test_user("bob").await.unwrap();
+        task_store
+            .add_task(NewTask {
+                names: names(&[("en", "Hoovering"), ("de", "Staubsaugen")]),
+                starts_with: "arthur".into(),
+                routine: Routine::Interval,
+                duration: 7,
+                starts_on: NaiveDate::from_ymd_opt(2020, 1, 12).unwrap(),
+                participants: vec!["arthur".into(), "bob".into()],
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        task_store
+            .add_task(NewTask {
+                names: names(&[("en", "Bins"), ("de", "Müll")]),
+                starts_with: "bob".into(),
+                routine: Routine::Schedule,
+                duration: 7,
+                starts_on: NaiveDate::from_ymd_opt(2020, 1, 8).unwrap(),
+                participants: vec!["bob".into()],
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        task_store
+            .add_task(NewTask {
+                names: names(&[("en", "Clean 100% of the oven")]),
+                starts_with: "bob".into(),
+                routine: Routine::Interval,
+                duration: 30,
+                starts_on: NaiveDate::from_ymd_opt(2020, 1, 25).unwrap(),
+                participants: vec!["arthur".into(), "bob".into()],
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        task_store
+    }
+
+    async fn find_names(task_store: &TaskStore, query: TaskQuery) -> Vec<String> {
+        task_store
+            .find_tasks(&query, &"en".into())
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|task| task.name)
+            .collect()
+    }
+
+    #[sqlx::test]
+    async fn filters_tasks_by_query(conn: sqlx::SqlitePool) {
+        time::mock::set(NaiveDate::from_ymd_opt(2020, 1, 10).unwrap());
+        let task_store = add_filtering_fixtures(&conn).await;
+
+        let query = TaskQuery {
+            assignee: Some("BOB".into()),
+            ..Default::default()
+        };
+        assert_eq!(
+            find_names(&task_store, query).await,
+            vec!["Bins", "Clean 100% of the oven"]
+        );
+
+        let query = TaskQuery {
+            participant: Some("arthur".into()),
+            ..Default::default()
+        };
+        assert_eq!(
+            find_names(&task_store, query).await,
+            vec!["Hoovering", "Clean 100% of the oven"]
+        );
+
+        let query = TaskQuery {
+            routine: Some(Routine::Schedule),
+            ..Default::default()
+        };
+        assert_eq!(find_names(&task_store, query).await, vec!["Bins"]);
+
+        let query = TaskQuery {
+            status: Some(DueStatus::Overdue),
+            ..Default::default()
+        };
+        assert_eq!(find_names(&task_store, query).await, vec!["Bins"]);
+
+        let query = TaskQuery {
+            status: Some(DueStatus::Upcoming),
+            due_until: NaiveDate::from_ymd_opt(2020, 1, 20),
+            ..Default::default()
+        };
+        assert_eq!(find_names(&task_store, query).await, vec!["Hoovering"]);
+
+        let query = TaskQuery {
+            due_from: NaiveDate::from_ymd_opt(2020, 1, 12),
+            due_until: NaiveDate::from_ymd_opt(2020, 1, 25),
+            ..Default::default()
+        };
+        assert_eq!(
+            find_names(&task_store, query).await,
+            vec!["Hoovering", "Clean 100% of the oven"]
+        );
+    }
+
+    #[sqlx::test]
+    async fn searches_task_names_in_every_language(conn: sqlx::SqlitePool) {
+        time::mock::set(NaiveDate::from_ymd_opt(2020, 1, 10).unwrap());
+        let task_store = add_filtering_fixtures(&conn).await;
+
+        let query = TaskQuery {
+            search: Some("staub".into()),
+            ..Default::default()
+        };
+        assert_eq!(find_names(&task_store, query).await, vec!["Hoovering"]);
+
+        let query = TaskQuery {
+            search: Some("100%".into()),
+            ..Default::default()
+        };
+        assert_eq!(
+            find_names(&task_store, query).await,
+            vec!["Clean 100% of the oven"]
+        );
+
+        let query = TaskQuery {
+            search: Some("0%".into()),
+            ..Default::default()
+        };
+        assert_eq!(
+            find_names(&task_store, query).await,
+            vec!["Clean 100% of the oven"]
+        );
+
+        let query = TaskQuery {
+            search: Some("n_".into()),
+            ..Default::default()
+        };
+        assert_eq!(find_names(&task_store, query).await, Vec::<String>::new());
+    }
+
+    #[sqlx::test]
+    async fn sorts_tasks_by_query(conn: sqlx::SqlitePool) {
+        time::mock::set(NaiveDate::from_ymd_opt(2020, 1, 10).unwrap());
+        let task_store = add_filtering_fixtures(&conn).await;
+
+        let query = TaskQuery {
+            sort: Some(TaskSort::Deadline),
+            ..Default::default()
+        };
+        assert_eq!(
+            find_names(&task_store, query).await,
+            vec!["Bins", "Hoovering", "Clean 100% of the oven"]
+        );
+
+        let query = TaskQuery {
+            sort: Some(TaskSort::Name),
+            order: SortOrder::Descending,
+            ..Default::default()
+        };
+        assert_eq!(
+            find_names(&task_store, query).await,
+            vec!["Hoovering", "Clean 100% of the oven", "Bins"]
+        );
+
+        task_store
+            .mark_task_done(3.into(), "bob", &today(), None)
+            .await
+            .unwrap();
+        let query = TaskQuery {
+            sort: Some(TaskSort::LastCompleted),
+            order: SortOrder::Descending,
+            ..Default::default()
+        };
+        assert_eq!(
+            find_names(&task_store, query).await,
+            vec!["Clean 100% of the oven", "Hoovering", "Bins"]
+        );
+    }
+
+    #[sqlx::test]
+    async fn tags_have_translated_names(conn: sqlx::SqlitePool) {
+        let task_store = TaskStore::new(conn, HouseholdId::default());
+        let kitchen = task_store
+            .add_tag(NewTag {
+                names: names(&[("en", "Kitchen"), ("de", "Küche")]),
+            })
+            .await
+            .unwrap();
+        let bathroom = task_store
+            .add_tag(NewTag {
+                names: names(&[("de", "Bad")]),
+            })
+            .await
+            .unwrap();
+
+        let tags = task_store.tags(&"en".into()).await.unwrap();
+        assert_eq!(
+            tags,
+            vec![
+                Tag {
+                    id: bathroom,
+                    name: "Bad".into()
+                },
+                Tag {
+                    id: kitchen,
+                    name: "Kitchen".into()
+                },
+            ]
+        );
+
+        task_store
+            .update_tag(
+                bathroom,
+                NewTag {
+                    names: names(&[("en", "Bathroom")]),
+                },
+            )
+            .await
+            .unwrap();
+        let tag = task_store.tag(bathroom, &"de".into()).await.unwrap();
+        assert_eq!(tag.name, "Bathroom");
+
+        task_store.delete_tag(kitchen).await.unwrap();
+        let result = task_store.tag(kitchen, &"en".into()).await.unwrap_err();
+        assert!(matches!(result, TaskStoreError::UnknownTagId(_)));
+        let result = task_store.delete_tag(kitchen).await.unwrap_err();
+        assert!(matches!(result, TaskStoreError::UnknownTagId(_)));
+
+        let result = task_store.add_tag(NewTag::default()).await.unwrap_err();
+        assert!(matches!(result, TaskStoreError::NoTagNames));
+    }
+
+    #[sqlx::test]
+    async fn filters_tasks_by_tag(conn: sqlx::SqlitePool) {
+        time::mock::set(NaiveDate::from_ymd_opt(2020, 1, 10).unwrap());
+        let task_store = add_filtering_fixtures(&conn).await;
+        let kitchen = task_store
+            .add_tag(NewTag {
+                names: names(&[("en", "Kitchen")]),
+            })
+            .await
+            .unwrap();
+        let cleaning = task_store
+            .add_tag(NewTag {
+                names: names(&[("en", "Cleaning")]),
+            })
+            .await
+            .unwrap();
+
+        task_store.tag_task(3.into(), kitchen).await.unwrap();
+        task_store.tag_task(3.into(), cleaning).await.unwrap();
+        task_store.tag_task(1.into(), cleaning).await.unwrap();
+
+        let query = TaskQuery {
+            tag: Some(kitchen),
+            ..Default::default()
+        };
+        assert_eq!(
+            find_names(&task_store, query).await,
+            vec!["Clean 100% of the oven"]
+        );
+
+        let task = task_store.task(3.into(), &"en".into()).await.unwrap();
+        let tag_names = task.tags.iter().map(|tag| &tag.name).collect::<Vec<_>>();
+        assert_eq!(tag_names, vec!["Cleaning", "Kitchen"]);
+
+        task_store.untag_task(3.into(), kitchen).await.unwrap();
+        let query = TaskQuery {
+            tag: Some(kitchen),
+            ..Default::default()
+        };
+        assert_eq!(find_names(&task_store, query).await, Vec::<String>::new());
+
+        // deleting a tag removes it from its tasks
+        task_store.delete_tag(cleaning).await.unwrap();
+        let task = task_store.task(1.into(), &"en".into()).await.unwrap();
+        assert_eq!(task.tags, vec![]);
+
+        let result = task_store.tag_task(1.into(), cleaning).await.unwrap_err();
+        assert!(matches!(result, TaskStoreError::UnknownTagId(_)));
+        let result = task_store.tag_task(9.into(), kitchen).await.unwrap_err();
+        assert!(matches!(result, TaskStoreError::UnknownTaskId(_)));
+    }
+
+    #[sqlx::test]
+    async fn tasks_have_translated_descriptions_and_checklists(conn: sqlx::SqlitePool) {
+        time::mock::set(NaiveDate::from_ymd_opt(2020, 1, 10).unwrap());
+        let task_store = TaskStore::new(conn.clone(), HouseholdId::default());
+        let auth_store = AuthStore::new(conn);
+        auth_store.create_test_user("arthur").await.unwrap();
+        task_store
+            .add_task(NewTask {
+                names: names(&[("en", "Clean the kitchen"), ("de", "Küche putzen")]),
+                descriptions: names(&[("en", "Leave it *sparkling*")]),
+                checklist: vec![
+                    names(&[("en", "Wipe counters"), ("de", "Arbeitsflächen abwischen")]),
+                    names(&[("en", "Empty bin"), ("de", "Müll rausbringen")]),
+                    names(&[("en", "Mop floor")]),
+                ],
+                starts_with: "arthur".into(),
+                routine: Routine::Interval,
+                duration: 7,
+                starts_on: NaiveDate::from_ymd_opt(2020, 1, 10).unwrap(),
+                participants: vec!["arthur".into()],
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        let task = task_store.task(1.into(), &"en".into()).await.unwrap();
+        assert_eq!(task.description.as_deref(), Some("Leave it *sparkling*"));
+        let steps = task.checklist.iter().map(|s| &s.name).collect::<Vec<_>>();
+        assert_eq!(steps, vec!["Wipe counters", "Empty bin", "Mop floor"]);
+        assert_eq!(task.last_steps_done, None);
+
+        let task = task_store.task(1.into(), &"de".into()).await.unwrap();
+        assert_eq!(task.description, None);
+        let steps = task.checklist.iter().map(|s| &s.name).collect::<Vec<_>>();
+        assert_eq!(
+            steps,
+            vec!["Arbeitsflächen abwischen", "Müll rausbringen", "Mop floor"]
+        );
+
+        let step_ids = task.checklist.iter().map(|s| s.id).collect::<Vec<_>>();
+        task_store
+            .mark_task_done(
+                1.into(),
+                "arthur",
+                &today(),
+                Some(&[step_ids[2], step_ids[0], step_ids[2]]),
+            )
+            .await
+            .unwrap();
+        let task = task_store.task(1.into(), &"en".into()).await.unwrap();
+        assert_eq!(task.last_steps_done, Some(vec![step_ids[0], step_ids[2]]));
+
+        task_store
+            .mark_task_done(1.into(), "arthur", &today(), None)
+            .await
+            .unwrap();
+        let task = task_store.task(1.into(), &"en".into()).await.unwrap();
+        assert_eq!(task.last_steps_done, None);
+    }
+
+    #[sqlx::test]
+    async fn only_steps_from_the_task_can_be_marked_done(conn: sqlx::SqlitePool) {
+        time::mock::set(NaiveDate::from_ymd_opt(2020, 1, 10).unwrap());
+        let task_store = TaskStore::new(conn.clone(), HouseholdId::default());
+        let auth_store = AuthStore::new(conn);
+        auth_store.create_test_user("arthur").await.unwrap();
+        for task_name in ["Task 1", "Task 2"] {
+            task_store
+                .add_task(NewTask {
+                    names: names(&[("en", task_name)]),
+                    checklist: vec![names(&[("en", "Step")])],
+                    starts_with: "arthur".into(),
+                    routine: Routine::Interval,
+                    duration: 7,
+                    starts_on: NaiveDate::from_ymd_opt(2020, 1, 10).unwrap(),
+                    participants: vec!["arthur".into()],
+                    ..Default::default()
+                })
+                .await
+                .unwrap();
+        }
+
+        let other_task = task_store.task(2.into(), &"en".into()).await.unwrap();
+        let other_step = other_task.checklist[0].id;
+        let result = task_store
+            .mark_task_done(1.into(), "arthur", &today(), Some(&[other_step]))
+            .await
+            .unwrap_err();
+        assert!(matches!(result, TaskStoreError::UnknownStep(step) if step == other_step));
+
+        let task = task_store.task(1.into(), &"en".into()).await.unwrap();
+        assert_eq!(
+            task.last_completed,
+            NaiveDate::from_ymd_opt(2020, 1, 3).unwrap()
+        );
+    }
+
+    #[sqlx::test]
+    async fn stats_score_completions_against_their_due_dates(conn: sqlx::SqlitePool) {
+        time::mock::set(NaiveDate::from_ymd_opt(2020, 1, 30).unwrap());
+        let task_store = TaskStore::new(conn.clone(), HouseholdId::default());
+        let auth_store = AuthStore::new(conn);
+        auth_store.create_test_user("arthur").await.unwrap();
+        auth_store.create_test_user("bob").await.unwrap();
+        task_store
+            .add_task(NewTask {
+                names: names(&[("en", "Interval")]),
+                starts_with: "arthur".into(),
+                routine: Routine::Interval,
+                duration: 7,
+                starts_on: NaiveDate::from_ymd_opt(2020, 1, 6).unwrap(),
+                participants: vec!["arthur".into(), "bob".into()],
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        task_store
+            .add_task(NewTask {
+                names: names(&[("en", "Schedule")]),
+                starts_with: "bob".into(),
+                routine: Routine::Schedule,
+                duration: 7,
+                starts_on: NaiveDate::from_ymd_opt(2020, 1, 6).unwrap(),
+                participants: vec!["bob".into()],
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        // interval: due on the 6th, then a week after each completion
+        for (person, day) in [("arthur", 6), ("bob", 16), ("arthur", 20)] {
+            let date = NaiveDate::from_ymd_opt(2020, 1, day).unwrap();
+            task_store
+                .mark_task_done(1.into(), person, &date, None)
+                .await
+                .unwrap();
+        }
+        // schedule: due on the 6th, 13th, 20th regardless of when it was done
+        for day in [8, 13, 22] {
+            let date = NaiveDate::from_ymd_opt(2020, 1, day).unwrap();
+            task_store
+                .mark_task_done(2.into(), "bob", &date, None)
+                .await
+                .unwrap();
+        }
+
+        let stats = task_store
+            .stats(StatsPeriod::Month, &"en".into())
+            .await
+            .unwrap();
+
+        let interval = &stats.tasks[0].punctuality;
+        assert_eq!((interval.on_time, interval.late), (2, 1));
+        assert_eq!(interval.average_days_late, Some(3.0));
+        let schedule = &stats.tasks[1].punctuality;
+        assert_eq!((schedule.on_time, schedule.late), (1, 2));
+        assert_eq!(schedule.average_days_late, Some(2.0));
+
+        let bob = &stats.people[1];
+        assert_eq!(bob.person, "bob");
+        assert_eq!(bob.completions[0].count, 4);
+        assert_eq!((bob.punctuality.on_time, bob.punctuality.late), (1, 3));
+        assert_eq!(bob.longest_streak, 1);
+        assert_eq!(bob.current_streak, 0);
+    }
+
+    #[sqlx::test]
+    async fn absent_people_are_skipped_when_the_task_is_due(conn: sqlx::SqlitePool) {
+        time::mock::set(NaiveDate::from_ymd_opt(2020, 1, 10).unwrap());
+        let task_store = TaskStore::new(conn.clone(), HouseholdId::default());
+        let auth_store = AuthStore::new(conn);
+        auth_store.create_test_user("arthur").await.unwrap();
+        auth_store.create_test_user("bob").await.unwrap();
+        auth_store.create_test_user("claire").await.unwrap();
+        task_store
+            .add_task(NewTask {
+                names: names(&[("en", "Task")]),
+                starts_with: "bob".into(),
+                routine: Routine::Interval,
+                duration: 7,
+                starts_on: NaiveDate::from_ymd_opt(2020, 1, 15).unwrap(),
+                participants: vec!["arthur".into(), "bob".into(), "claire".into()],
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        // bob is away on the 15th, when the task is due
+        let absence = task_store
+            .add_absence(
+                "Bob",
+                NaiveDate::from_ymd_opt(2020, 1, 12).unwrap(),
+                NaiveDate::from_ymd_opt(2020, 1, 15).unwrap(),
+            )
+            .await
+            .unwrap();
+        let task = task_store.task(1.into(), &"en".into()).await.unwrap();
+        assert_eq!(task.assigned_to.as_deref(), Some("claire"));
+
+        // claire is away too, and an absence ending before the due date doesn't matter
+        task_store
+            .add_absence(
+                "claire",
+                NaiveDate::from_ymd_opt(2020, 1, 15).unwrap(),
+                NaiveDate::from_ymd_opt(2020, 1, 30).unwrap(),
+            )
+            .await
+            .unwrap();
+        task_store
+            .add_absence(
+                "arthur",
+                NaiveDate::from_ymd_opt(2020, 1, 1).unwrap(),
+                NaiveDate::from_ymd_opt(2020, 1, 14).unwrap(),
+            )
+            .await
+            .unwrap();
+        let task = task_store.task(1.into(), &"en".into()).await.unwrap();
+        assert_eq!(task.assigned_to.as_deref(), Some("arthur"));
+
+        task_store.remove_absence(absence).await.unwrap();
+        let task = task_store.task(1.into(), &"en".into()).await.unwrap();
+        assert_eq!(task.assigned_to.as_deref(), Some("bob"));
+
+        assert_eq!(task_store.absences("CLAIRE").await.unwrap().len(), 1);
+    }
+
+    #[sqlx::test]
+    async fn absences_only_refresh_their_own_household(conn: sqlx::SqlitePool) {
+        time::mock::set(NaiveDate::from_ymd_opt(2020, 1, 10).unwrap());
+        let auth_store = AuthStore::new(conn.clone());
+        auth_store.create_test_user("arthur").await.unwrap();
+        let household = auth_store.create_household("Second").await.unwrap();
+        auth_store
+            .join_household("arthur", household)
+            .await
+            .unwrap();
+        auth_store
+            .create_user("bob", "password", household)
+            .await
+            .unwrap();
+        let other_store = TaskStore::new(conn.clone(), household);
+        let task_id = other_store
+            .add_task(NewTask {
+                names: names(&[("en", "Task")]),
+                starts_with: "arthur".into(),
+                routine: Routine::Interval,
+                duration: 7,
+                starts_on: NaiveDate::from_ymd_opt(2020, 1, 15).unwrap(),
+                participants: vec!["arthur".into(), "bob".into()],
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        let task_store = TaskStore::new(conn, HouseholdId::default());
+        task_store
+            .add_absence(
+                "arthur",
+                NaiveDate::from_ymd_opt(2020, 1, 12).unwrap(),
+                NaiveDate::from_ymd_opt(2020, 1, 15).unwrap(),
+            )
+            .await
+            .unwrap();
+        let task = other_store.task(task_id, &"en".into()).await.unwrap();
+        assert_eq!(task.assigned_to.as_deref(), Some("arthur"));
+    }
+
+    #[sqlx::test]
+    async fn tasks_are_unassigned_when_everyone_is_away(conn: sqlx::SqlitePool) {
+        time::mock::set(NaiveDate::from_ymd_opt(2020, 1, 10).unwrap());
+        let task_store = TaskStore::new(conn.clone(), HouseholdId::default());
+        let auth_store = AuthStore::new(conn);
+        auth_store.create_test_user("arthur").await.unwrap();
+        auth_store.create_test_user("bob").await.unwrap();
+        for person in ["arthur", "bob"] {
+            task_store
+                .add_absence(
+                    person,
+                    NaiveDate::from_ymd_opt(2020, 1, 1).unwrap(),
+                    NaiveDate::from_ymd_opt(2020, 1, 31).unwrap(),
+                )
+                .await
+                .unwrap();
+        }
+        task_store
+            .add_task(NewTask {
+                names: names(&[("en", "Task")]),
+                starts_with: "bob".into(),
+                routine: Routine::Interval,
+                duration: 7,
+                starts_on: NaiveDate::from_ymd_opt(2020, 1, 15).unwrap(),
+                participants: vec!["arthur".into(), "bob".into()],
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        let task = task_store.task(1.into(), &"en".into()).await.unwrap();
+        assert_eq!(task.assigned_to, None);
+        assert_eq!(task.deadline, Deadline::Upcoming(5));
+
+        // once the task has been done, the next due date falls after the absences
+        time::mock::set(NaiveDate::from_ymd_opt(2020, 1, 28).unwrap());
+        task_store
+            .mark_task_done(1.into(), "bob", &today(), None)
+            .await
+            .unwrap();
+        let task = task_store.task(1.into(), &"en".into()).await.unwrap();
+        assert_eq!(task.assigned_to.as_deref(), Some("arthur"));
+    }
+
+    #[sqlx::test]
+    async fn completions_must_be_valid(conn: sqlx::SqlitePool) {
+        time::mock::set(NaiveDate::from_ymd_opt(2020, 1, 20).unwrap());
+        let task_store = TaskStore::new(conn.clone(), HouseholdId::default());
+        let auth_store = AuthStore::new(conn);
+        for person in ["arthur", "bob", "claire"] {
+            auth_store.create_test_user(person).await.unwrap();
+        }
+        task_store
+            .add_task(NewTask {
+                names: names(&[("en", "Task")]),
+                starts_with: "arthur".into(),
+                routine: Routine::Interval,
+                duration: 7,
+                starts_on: NaiveDate::from_ymd_opt(2020, 1, 15).unwrap(),
+                participants: vec!["arthur".into(), "bob".into()],
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        let day = |d| NaiveDate::from_ymd_opt(2020, 1, d).unwrap();
+        let mark_done = |person: &'static str, date: NaiveDate| {
+            let task_store = task_store.clone();
+            async move {
+                task_store
+                    .mark_task_done(1.into(), person, &date, None)
+                    .await
+            }
+        };
+
+        let result = mark_done("claire", day(20)).await.unwrap_err();
+        assert!(matches!(result, TaskStoreError::PersonNotParticipant(_)));
+        let result = mark_done("nobody", day(20)).await.unwrap_err();
+        assert!(matches!(result, TaskStoreError::PersonDoesNotExist(_)));
+        let result = mark_done("arthur", day(21)).await.unwrap_err();
+        assert!(matches!(result, TaskStoreError::CompletionInFuture(_)));
+        // the task started a cycle before its first due date
+        let result = mark_done("arthur", day(7)).await.unwrap_err();
+        assert!(matches!(result, TaskStoreError::CompletionBeforeStart(_)));
+
+        assert_eq!(task_store.backdate_limit().await.unwrap(), None);
+        task_store.set_backdate_limit(Some(5)).await.unwrap();
+        assert_eq!(task_store.backdate_limit().await.unwrap(), Some(5));
+        let result = mark_done("arthur", day(14)).await.unwrap_err();
+        assert!(matches!(result, TaskStoreError::CompletionTooOld(5)));
+        assert!(task_store.history(1.into()).await.unwrap().is_empty());
+
+        // people who aren't participants can still help
+        task_store
+            .mark_task_done_with(
+                1.into(),
+                "Arthur",
+                &day(15),
+                None,
+                CompletionDetails {
+                    helpers: vec!["claire".into()],
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(task_store.history(1.into()).await.unwrap().len(), 1);
+    }
+
+    #[sqlx::test]
+    async fn absences_must_be_valid(conn: sqlx::SqlitePool) {
+        let task_store = TaskStore::new(conn.clone(), HouseholdId::default());
+        let auth_store = AuthStore::new(conn);
+        auth_store.create_test_user("arthur").await.unwrap();
+        let day = |d| NaiveDate::from_ymd_opt(2020, 1, d).unwrap();
+
+        let result = task_store
+            .add_absence("arthur", day(10), day(9))
+            .await
+            .unwrap_err();
+        assert!(matches!(result, TaskStoreError::AbsenceEndsBeforeStart));
+
+        let result = task_store
+            .add_absence("zoe", day(10), day(12))
+            .await
+            .unwrap_err();
+        assert!(matches!(result, TaskStoreError::PersonDoesNotExist(_)));
+
+        let result = task_store.remove_absence(5.into()).await.unwrap_err();
+        assert!(matches!(result, TaskStoreError::UnknownAbsenceId(_)));
+    }
+
+    #[sqlx::test]
+    async fn participants_can_be_added_removed_and_reordered(conn: sqlx::SqlitePool) {
+        time::mock::set(NaiveDate::from_ymd_opt(2020, 1, 10).unwrap());
+        let task_store = TaskStore::new(conn.clone(), HouseholdId::default());
+        let auth_store = AuthStore::new(conn);
+        auth_store.create_test_user("arthur").await.unwrap();
+        auth_store.create_test_user("bob").await.unwrap();
+        auth_store.create_test_user("claire").await.unwrap();
+        task_store
+            .add_task(NewTask {
+                names: names(&[("en", "Task")]),
+                starts_with: "arthur".into(),
+                routine: Routine::Interval,
+                duration: 7,
+                starts_on: NaiveDate::from_ymd_opt(2020, 1, 10).unwrap(),
+                participants: vec!["arthur".into(), "bob".into()],
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        task_store
+            .add_participant(1.into(), "Claire")
+            .await
+            .unwrap();
+        task_store.add_participant(1.into(), "bob").await.unwrap();
+        let task = task_store.task(1.into(), &"en".into()).await.unwrap();
+        assert_eq!(task.participants, vec!["arthur", "bob", "claire"]);
+
+        task_store
+            .reorder_participants(1.into(), &["claire".into(), "arthur".into(), "bob".into()])
+            .await
+            .unwrap();
+        let task = task_store.task(1.into(), &"en".into()).await.unwrap();
+        assert_eq!(task.participants, vec!["claire", "arthur", "bob"]);
+        // bob went last, so the turn wraps around to the new first person
+        assert_eq!(task.assigned_to.as_deref(), Some("claire"));
+
+        task_store
+            .mark_task_done(1.into(), "arthur", &today(), None)
+            .await
+            .unwrap();
+        let task = task_store.task(1.into(), &"en".into()).await.unwrap();
+        assert_eq!(task.assigned_to.as_deref(), Some("bob"));
+
+        // the last person to do the task leaving restarts the rotation from the top
+        task_store
+            .remove_participant(1.into(), "arthur")
+            .await
+            .unwrap();
+        let task = task_store.task(1.into(), &"en".into()).await.unwrap();
+        assert_eq!(task.participants, vec!["claire", "bob"]);
+        assert_eq!(task.assigned_to.as_deref(), Some("claire"));
+
+        // as does the current assignee leaving
+        task_store
+            .remove_participant(1.into(), "claire")
+            .await
+            .unwrap();
+        let task = task_store.task(1.into(), &"en".into()).await.unwrap();
+        assert_eq!(task.assigned_to.as_deref(), Some("bob"));
+    }
+
+    #[sqlx::test]
+    async fn participant_changes_must_be_valid(conn: sqlx::SqlitePool) {
+        time::mock::set(NaiveDate::from_ymd_opt(2020, 1, 10).unwrap());
+        let task_store = TaskStore::new(conn.clone(), HouseholdId::default());
+        let auth_store = AuthStore::new(conn);
+        auth_store.create_test_user("arthur").await.unwrap();
+        auth_store.create_test_user("bob").await.unwrap();
+        task_store
+            .add_task(NewTask {
+                names: names(&[("en", "Task")]),
+                starts_with: "arthur".into(),
+                routine: Routine::Interval,
+                duration: 7,
+                starts_on: NaiveDate::from_ymd_opt(2020, 1, 10).unwrap(),
+                participants: vec!["arthur".into()],
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        let result = task_store.add_participant(1.into(), "zoe").await;
+        assert!(matches!(result, Err(TaskStoreError::PersonDoesNotExist(_))));
+        let result = task_store.add_participant(2.into(), "bob").await;
+        assert!(matches!(result, Err(TaskStoreError::UnknownTaskId(_))));
+        let result = task_store.remove_participant(1.into(), "bob").await;
+        assert!(matches!(
+            result,
+            Err(TaskStoreError::PersonNotParticipant(_))
+        ));
+        let result = task_store.remove_participant(1.into(), "arthur").await;
+        assert!(matches!(result, Err(TaskStoreError::NoParticipants)));
+        let result = task_store
+            .reorder_participants(1.into(), &["arthur".into(), "bob".into()])
+            .await;
+        assert!(matches!(
+            result,
+            Err(TaskStoreError::ParticipantOrderMismatch)
+        ));
+    }
+
+    #[sqlx::test]
+    async fn removing_the_claimant_unclaims_a_one_off_task(conn: sqlx::SqlitePool) {
+        time::mock::set(NaiveDate::from_ymd_opt(2020, 1, 10).unwrap());
+        let task_store = TaskStore::new(conn.clone(), HouseholdId::default());
+        let auth_store = AuthStore::new(conn);
+        auth_store.create_test_user("arthur").await.unwrap();
+        auth_store.create_test_user("bob").await.unwrap();
+        task_store
+            .add_task(NewTask {
+                names: names(&[("en", "Fix the shelf")]),
+                starts_with: "bob".into(),
+                routine: Routine::OneOff,
+                starts_on: NaiveDate::from_ymd_opt(2020, 1, 10).unwrap(),
+                participants: vec!["arthur".into(), "bob".into()],
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        task_store
+            .remove_participant(1.into(), "bob")
+            .await
+            .unwrap();
+        let task = task_store.task(1.into(), &"en".into()).await.unwrap();
+        assert_eq!(task.assigned_to, None);
+    }
+
+    #[sqlx::test]
+    async fn completing_a_task_triggers_chained_tasks(conn: sqlx::SqlitePool) {
+        time::mock::set(NaiveDate::from_ymd_opt(2020, 1, 10).unwrap());
+        let task_store = TaskStore::new(conn.clone(), HouseholdId::default());
+        let auth_store = AuthStore::new(conn);
+        auth_store.create_test_user("arthur").await.unwrap();
+        auth_store.create_test_user("bob").await.unwrap();
+        auth_store.create_test_user("claire").await.unwrap();
+        task_store
+            .add_task(NewTask {
+                names: names(&[("en", "Wash the sheets")]),
+                starts_with: "arthur".into(),
+                routine: Routine::Interval,
+                duration: 7,
+                starts_on: NaiveDate::from_ymd_opt(2020, 1, 10).unwrap(),
+                participants: vec!["arthur".into(), "bob".into()],
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        for name in ["Put the sheets away", "Iron the sheets"] {
+            task_store
+                .add_task(NewTask {
+                    names: names(&[("en", name)]),
+                    starts_with: "bob".into(),
+                    routine: Routine::Triggered,
+                    starts_on: NaiveDate::from_ymd_opt(2020, 1, 10).unwrap(),
+                    participants: vec!["arthur".into(), "bob".into(), "claire".into()],
+                    ..Default::default()
+                })
+                .await
+                .unwrap();
+        }
+        task_store
+            .add_chain(1.into(), 2.into(), 1, ChainAssignee::SamePerson)
+            .await
+            .unwrap();
+        task_store
+            .add_chain(1.into(), 3.into(), 2, ChainAssignee::NextInRotation)
+            .await
+            .unwrap();
+
+        // triggered tasks are hidden until they are triggered
+        let tasks = task_store.tasks(&"en".into()).await.unwrap();
+        assert_eq!(tasks.len(), 1);
+        let task = task_store.task(2.into(), &"en".into()).await.unwrap();
+        assert_eq!(task.deadline, Deadline::Unscheduled);
+
+        task_store
+            .mark_task_done(1.into(), "arthur", &today(), None)
+            .await
+            .unwrap();
+        let tasks = task_store.tasks(&"en".into()).await.unwrap();
+        assert_eq!(tasks.len(), 3);
+        let task = task_store.task(2.into(), &"en".into()).await.unwrap();
+        assert_eq!(task.deadline, Deadline::Upcoming(1));
+        assert_eq!(task.assigned_to.as_deref(), Some("arthur"));
+        let task = task_store.task(3.into(), &"en".into()).await.unwrap();
+        assert_eq!(task.deadline, Deadline::Upcoming(2));
+        assert_eq!(task.assigned_to.as_deref(), Some("bob"));
+
+        // once done, the chained task waits to be triggered again
+        task_store
+            .mark_task_done(2.into(), "arthur", &today(), None)
+            .await
+            .unwrap();
+        let tasks = task_store.tasks(&"en".into()).await.unwrap();
+        assert_eq!(tasks.len(), 2);
+        let task = task_store.task(2.into(), &"en".into()).await.unwrap();
+        assert_eq!(task.deadline, Deadline::Unscheduled);
+        assert_eq!(task.assigned_to.as_deref(), Some("bob"));
+    }
+
+    #[sqlx::test]
+    async fn chains_must_be_valid(conn: sqlx::SqlitePool) {
+        time::mock::set(NaiveDate::from_ymd_opt(2020, 1, 10).unwrap());
+        let task_store = TaskStore::new(conn.clone(), HouseholdId::default());
+        let auth_store = AuthStore::new(conn);
+        auth_store.create_test_user("arthur").await.unwrap();
+        for routine in [Routine::Interval, Routine::Triggered] {
+            task_store
+                .add_task(NewTask {
+                    names: names(&[("en", "Task")]),
+                    starts_with: "arthur".into(),
+                    routine,
+                    duration: 7,
+                    starts_on: NaiveDate::from_ymd_opt(2020, 1, 10).unwrap(),
+                    participants: vec!["arthur".into()],
+                    ..Default::default()
+                })
+                .await
+                .unwrap();
+        }
+
+        let result = task_store
+            .add_chain(2.into(), 1.into(), 0, ChainAssignee::SamePerson)
+            .await;
+        assert!(matches!(
+            result,
+            Err(TaskStoreError::ChainTargetNotTriggered(_))
+        ));
+        let result = task_store
+            .add_chain(3.into(), 2.into(), 0, ChainAssignee::SamePerson)
+            .await;
+        assert!(matches!(result, Err(TaskStoreError::UnknownTaskId(_))));
+
+        let chain_id = task_store
+            .add_chain(1.into(), 2.into(), 3, ChainAssignee::SamePerson)
+            .await
+            .unwrap();
+        assert_eq!(task_store.chains().await.unwrap().len(), 1);
+        task_store.delete_chain(chain_id).await.unwrap();
+        let result = task_store.chain(chain_id).await;
+        assert!(matches!(result, Err(TaskStoreError::UnknownChainId(_))));
+        let result = task_store.delete_chain(chain_id).await;
+        assert!(matches!(result, Err(TaskStoreError::UnknownChainId(_))));
+    }
+
+    #[sqlx::test]
+    async fn households_only_see_their_own_tasks(conn: sqlx::SqlitePool) {
+        time::mock::set(NaiveDate::from_ymd_opt(2020, 1, 10).unwrap());
+        let home = TaskStore::new(conn.clone(), HouseholdId::default());
+        let auth_store = AuthStore::new(conn.clone());
+        auth_store.create_test_user("arthur").await.unwrap();
+        auth_store.create_test_user("bob").await.unwrap();
+        let flat = auth_store.create_household("Flat").await.unwrap();
+        auth_store.join_household("bob", flat).await.unwrap();
+        let flat = TaskStore::new(conn, flat);
+
+        home.add_task(NewTask {
+            names: names(&[("en", "Hoover")]),
+            starts_with: "arthur".into(),
+            routine: Routine::Interval,
+            duration: 7,
+            starts_on: NaiveDate::from_ymd_opt(2020, 1, 10).unwrap(),
+            participants: vec!["arthur".into()],
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+        let kitchen = home
+            .add_tag(NewTag {
+                names: names(&[("en", "Kitchen")]),
+            })
+            .await
+            .unwrap();
+        home.add_feed(None, &"en".into()).await.unwrap();
+
+        assert_eq!(flat.tasks(&"en".into()).await.unwrap(), vec![]);
+        assert!(matches!(
+            flat.task(1.into(), &"en".into()).await,
+            Err(TaskStoreError::UnknownTaskId(_))
+        ));
+        assert!(matches!(
+            flat.mark_task_done(1.into(), "bob", &today(), None).await,
+            Err(TaskStoreError::UnknownTaskId(_))
+        ));
+        assert_eq!(flat.tags(&"en".into()).await.unwrap(), vec![]);
+        assert!(matches!(
+            flat.delete_tag(kitchen).await,
+            Err(TaskStoreError::UnknownTagId(_))
+        ));
+        assert_eq!(flat.feeds().await.unwrap(), vec![]);
+
+        // people can only take part in the tasks of their own households
+        let result = flat
+            .add_task(NewTask {
+                names: names(&[("en", "Bins")]),
+                starts_with: "bob".into(),
+                routine: Routine::Interval,
+                duration: 7,
+                starts_on: NaiveDate::from_ymd_opt(2020, 1, 10).unwrap(),
+                participants: vec!["arthur".into(), "bob".into()],
+                ..Default::default()
+            })
+            .await;
+        assert!(matches!(result, Err(TaskStoreError::PersonDoesNotExist(_))));
+
+        // but can belong to several households at once
+        flat.add_task(NewTask {
+            names: names(&[("en", "Bins")]),
+            starts_with: "bob".into(),
+            routine: Routine::Interval,
+            duration: 7,
+            starts_on: NaiveDate::from_ymd_opt(2020, 1, 10).unwrap(),
+            participants: vec!["bob".into()],
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+        let tasks = flat.tasks(&"en".into()).await.unwrap();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].name, "Bins");
+        assert_eq!(home.tasks(&"en".into()).await.unwrap().len(), 1);
+    }
+
+    #[sqlx::test]
+    async fn completions_keep_notes_and_attached_images(conn: sqlx::SqlitePool) {
         time::mock::set(NaiveDate::from_ymd_opt(2020, 1, 10).unwrap());
-        let task_store = TaskStore::new(conn.clone());
+        let task_store = TaskStore::new(conn.clone(), HouseholdId::default());
         let auth_store = AuthStore::new(conn);
         auth_store.create_test_user("arthur").await.unwrap();
-        auth_store.create_test_user("claire").await.unwrap();
-        auth_store.create_test_user("bob").await.unwrap();
         task_store
             .add_task(NewTask {
-                names: names(&[("en", "Test Task 1")]),
-                starts_with: "bob".into(),
+                names: names(&[("en", "Clean the oven")]),
+                starts_with: "arthur".into(),
                 routine: Routine::Interval,
                 duration: 7,
-                starts_on: NaiveDate::from_ymd_opt(2020, 1, 15).unwrap(),
-                participants: vec!["arthur".into(), "bob".into(), "claire".into()],
+                starts_on: NaiveDate::from_ymd_opt(2020, 1, 1).unwrap(),
+                participants: vec!["arthur".into()],
+                ..Default::default()
             })
             .await
             .unwrap();
+        let photo = |content_type: &str, size: usize| NewAttachment {
+            content_type: content_type.into(),
+            data: vec![1; size],
+        };
+
         task_store
-            .add_task(NewTask {
-                names: names(&[("en", "Test Task 2")]),
-                starts_with: "claire".into(),
-                routine: Routine::Interval,
-                duration: 7,
-                starts_on: NaiveDate::from_ymd_opt(2020, 1, 15).unwrap(),
-                participants: vec!["claire".into(), "bob".into(), "arthur".into()],
-            })
+            .mark_task_done(
+                1.into(),
+                "arthur",
+                &NaiveDate::from_ymd_opt(2020, 1, 2).unwrap(),
+                None,
+            )
             .await
             .unwrap();
         task_store
-            .add_task(NewTask {
-                names: names(&[("en", "Test Task 3")]),
-                starts_with: "bob".into(),
-                routine: Routine::Interval,
-                duration: 7,
-                starts_on: NaiveDate::from_ymd_opt(2020, 1, 15).unwrap(),
-                participants: vec!["claire".into(), "bob".into(), "arthur".into()],
-            })
+            .mark_task_done_with(
+                1.into(),
+                "arthur",
+                &today(),
+                None,
+                CompletionDetails {
+                    note: Some(" Sparkling now ".into()),
+                    attachments: vec![photo("image/jpeg", 10), photo("image/PNG", 20)],
+                    ..Default::default()
+                },
+            )
             .await
             .unwrap();
 
-        let bobs_tasks = task_store.tasks_for("bob", &"en".into()).await.unwrap();
-        assert_eq!(bobs_tasks.len(), 2);
-        assert_eq!(bobs_tasks[0].name, "Test Task 1");
-        assert_eq!(bobs_tasks[1].name, "Test Task 3");
+        let history = task_store.history(1.into()).await.unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].completed_on, today());
+        assert_eq!(history[0].note.as_deref(), Some("Sparkling now"));
+        assert_eq!(history[0].attachments.len(), 2);
+        assert_eq!(history[0].attachments[1].content_type, "image/png");
+        assert_eq!(history[0].attachments[1].size, 20);
+        assert_eq!(history[1].note, None);
+        assert_eq!(history[1].attachments, vec![]);
 
-        let claires_tasks = task_store.tasks_for("claire", &"en".into()).await.unwrap();
-        assert_eq!(claires_tasks.len(), 1);
-        assert_eq!(claires_tasks[0].name, "Test Task 2");
+        let (attachment, data) = task_store
+            .attachment(history[0].attachments[0].id)
+            .await
+            .unwrap();
+        assert_eq!(attachment, history[0].attachments[0]);
+        assert_eq!(data, vec![1; 10]);
 
-        let arthurs_tasks = task_store.tasks_for("arthur", &"en".into()).await.unwrap();
-        assert_eq!(arthurs_tasks.len(), 0);
+        let mut errors = Vec::new();
+        for attachments in [
+            vec![photo("image/svg+xml", 10)],
+            vec![photo("image/jpeg", MAX_ATTACHMENT_SIZE + 1)],
+            vec![photo("image/jpeg", 10); 5],
+        ] {
+            let details = CompletionDetails {
+                attachments,
+                ..Default::default()
+            };
+            errors.push(
+                task_store
+                    .mark_task_done_with(1.into(), "arthur", &today(), None, details)
+                    .await
+                    .unwrap_err(),
+            );
+        }
+        assert!(matches!(
+            errors.as_slice(),
+            [
+                TaskStoreError::NotAnImage(_),
+                TaskStoreError::AttachmentTooLarge,
+                TaskStoreError::TooManyAttachments
+            ]
+        ));
+        assert_eq!(task_store.history(1.into()).await.unwrap().len(), 2);
 
         assert_eq!(
-            bobs_tasks[0].participants,
-            vec!["arthur".to_owned(), "bob".to_owned(), "claire".to_owned()]
+            task_store
+                .prune_attachments(NaiveDate::from_ymd_opt(2020, 1, 10).unwrap())
+                .await
+                .unwrap(),
+            0
         );
         assert_eq!(
-            bobs_tasks[1].participants,
-            vec!["claire".to_owned(), "bob".to_owned(), "arthur".to_owned()]
+            task_store
+                .prune_attachments(NaiveDate::from_ymd_opt(2020, 1, 11).unwrap())
+                .await
+                .unwrap(),
+            2
         );
+        let history = task_store.history(1.into()).await.unwrap();
+        assert_eq!(history[0].note.as_deref(), Some("Sparkling now"));
+        assert_eq!(history[0].attachments, vec![]);
+        assert!(matches!(
+            task_store.attachment(attachment.id).await,
+            Err(TaskStoreError::UnknownAttachmentId(_))
+        ));
     }
 
     #[sqlx::test]
-    async fn returns_created_task(conn: sqlx::SqlitePool) {
+    async fn joint_completions_credit_everyone_and_skip_past_them(conn: sqlx::SqlitePool) {
         time::mock::set(NaiveDate::from_ymd_opt(2020, 1, 10).unwrap());
-        let task_store = TaskStore::new(conn.clone());
+        let task_store = TaskStore::new(conn.clone(), HouseholdId::default());
         let auth_store = AuthStore::new(conn);
-        auth_store.create_test_user("arthur").await.unwrap();
-        auth_store.create_test_user("bob").await.unwrap();
+        for person in ["arthur", "bob", "claire", "dan"] {
+            auth_store.create_test_user(person).await.unwrap();
+        }
         task_store
             .add_task(NewTask {
-                names: names(&[("en", "Test Task 1")]),
-                starts_with: "bob".into(),
+                names: names(&[("en", "Clear out the garage")]),
+                starts_with: "arthur".into(),
                 routine: Routine::Interval,
                 duration: 7,
                 starts_on: NaiveDate::from_ymd_opt(2020, 1, 10).unwrap(),
-                participants: vec!["arthur".into(), "bob".into()],
+                participants: vec!["arthur".into(), "bob".into(), "claire".into(), "dan".into()],
+                ..Default::default()
             })
             .await
             .unwrap();
+        let together = |helpers: &[&str]| CompletionDetails {
+            helpers: helpers.iter().map(|helper| helper.to_string()).collect(),
+            ..Default::default()
+        };
+
+        // it was arthur's turn, but bob is further along, so claire is next
         task_store
-            .add_task(NewTask {
-                names: names(&[("en", "Test Task 2")]),
-                starts_with: "bob".into(),
-                routine: Routine::Interval,
-                duration: 7,
-                starts_on: NaiveDate::from_ymd_opt(2020, 1, 15).unwrap(),
-                participants: vec!["arthur".into(), "bob".into()],
-            })
+            .mark_task_done_with(1.into(), "arthur", &today(), None, together(&["Bob"]))
             .await
             .unwrap();
-
         let task = task_store.task(1.into(), &"en".into()).await.unwrap();
-        assert_eq!(task.name, "Test Task 1".to_owned());
-        assert_eq!(
-            task.last_completed,
-            NaiveDate::from_ymd_opt(2020, 1, 3).unwrap()
-        );
+        assert_eq!(task.assigned_to.as_deref(), Some("claire"));
 
-        let task = task_store.task(2.into(), &"en".into()).await.unwrap();
-        assert_eq!(task.name, "Test Task 2".to_owned());
-        assert_eq!(
-            task.last_completed,
-            NaiveDate::from_ymd_opt(2020, 1, 8).unwrap()
-        );
+        let history = task_store.history(1.into()).await.unwrap();
+        assert_eq!(history[0].completed_by, "bob");
+        assert_eq!(history[0].helpers, vec!["arthur"]);
+
+        let stats = task_store
+            .stats(StatsPeriod::Week, &"en".into())
+            .await
+            .unwrap();
+        let people = stats
+            .people
+            .iter()
+            .map(|person| person.person.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(people, vec!["arthur", "bob"]);
+
+        let result = task_store
+            .mark_task_done_with(1.into(), "claire", &today(), None, together(&["eve"]))
+            .await;
+        assert!(matches!(result, Err(TaskStoreError::PersonDoesNotExist(_))));
+        assert_eq!(task_store.history(1.into()).await.unwrap().len(), 1);
     }
 
     #[sqlx::test]
-    async fn completing_interval_task_returns_updated_task(conn: sqlx::SqlitePool) {
+    async fn completions_earn_points_that_can_be_spent(conn: sqlx::SqlitePool) {
         time::mock::set(NaiveDate::from_ymd_opt(2020, 1, 10).unwrap());
-        let task_store = TaskStore::new(conn.clone());
+        let task_store = TaskStore::new(conn.clone(), HouseholdId::default());
         let auth_store = AuthStore::new(conn);
-        auth_store.create_test_user("arthur").await.unwrap();
-        auth_store.create_test_user("bob").await.unwrap();
+        for person in ["arthur", "bob", "claire"] {
+            auth_store.create_test_user(person).await.unwrap();
+        }
         task_store
             .add_task(NewTask {
-                names: names(&[("en", "Task")]),
+                names: names(&[("en", "Wash the car")]),
                 starts_with: "arthur".into(),
                 routine: Routine::Interval,
                 duration: 7,
-                starts_on: NaiveDate::from_ymd_opt(2020, 1, 12).unwrap(),
+                starts_on: NaiveDate::from_ymd_opt(2020, 1, 10).unwrap(),
                 participants: vec!["arthur".into(), "bob".into()],
+                points: 5,
+                ..Default::default()
             })
             .await
             .unwrap();
 
         task_store
-            .mark_task_done(1.into(), "arthur", &today())
+            .mark_task_done_with(
+                1.into(),
+                "arthur",
+                &today(),
+                None,
+                CompletionDetails {
+                    helpers: vec!["bob".into()],
+                    ..Default::default()
+                },
+            )
             .await
             .unwrap();
-        let task = task_store.task(1.into(), &"en".into()).await.unwrap();
-        assert_eq!(task.name, "Task".to_owned());
-        assert_eq!(task.assigned_to, "bob".to_owned());
+        task_store.set_points(1.into(), 3).await.unwrap();
         assert_eq!(
-            task.last_completed,
-            NaiveDate::from_ymd_opt(2020, 1, 10).unwrap()
+            task_store
+                .task(1.into(), &"en".into())
+                .await
+                .unwrap()
+                .points,
+            3
         );
-        assert_eq!(task.deadline, Deadline::Upcoming(7));
+        task_store
+            .mark_task_done(1.into(), "bob", &today(), None)
+            .await
+            .unwrap();
+
+        let balances = task_store.balances(None).await.unwrap();
+        let balances = balances
+            .iter()
+            .map(|balance| (balance.person.as_str(), balance.points))
+            .collect::<Vec<_>>();
+        assert_eq!(balances, vec![("arthur", 5), ("bob", 8), ("claire", 0)]);
+
+        task_store
+            .adjust_points("claire", 2, Some("Helped with the shopping"))
+            .await
+            .unwrap();
+        let result = task_store.redeem_points("claire", 3, None).await;
+        assert!(matches!(result, Err(TaskStoreError::NotEnoughPoints(_))));
+        let result = task_store.redeem_points("claire", i32::MIN, None).await;
+        assert!(matches!(result, Err(TaskStoreError::InvalidRedemption(_))));
+        let result = task_store.redeem_points("eve", 1, None).await;
+        assert!(matches!(result, Err(TaskStoreError::PersonDoesNotExist(_))));
+
+        let entry_id = task_store
+            .redeem_points("bob", 6, Some("Cinema trip"))
+            .await
+            .unwrap();
+        let entry = task_store.ledger_entry(entry_id).await.unwrap();
+        assert_eq!(entry.kind, LedgerKind::Redemption);
+        assert_eq!(entry.points, -6);
+
+        let ledger = task_store.ledger(Some("bob")).await.unwrap();
+        let ledger = ledger
+            .iter()
+            .map(|entry| (entry.kind, entry.points))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            ledger,
+            vec![
+                (LedgerKind::Completion, 5),
+                (LedgerKind::Completion, 3),
+                (LedgerKind::Redemption, -6),
+            ]
+        );
+        assert_eq!(task_store.ledger(None).await.unwrap().len(), 5);
+
+        let result = task_store.adjust_points("eve", 1, None).await;
+        assert!(matches!(result, Err(TaskStoreError::PersonDoesNotExist(_))));
     }
 
     #[sqlx::test]
-    async fn completing_schedule_task_returns_updated_task(conn: sqlx::SqlitePool) {
-        time::mock::set(NaiveDate::from_ymd_opt(2020, 1, 10).unwrap());
-        let task_store = TaskStore::new(conn.clone());
+    async fn seasonal_tasks_wait_until_their_window_opens(conn: sqlx::SqlitePool) {
+        time::mock::set(NaiveDate::from_ymd_opt(2020, 10, 20).unwrap());
+        let task_store = TaskStore::new(conn.clone(), HouseholdId::default());
         let auth_store = AuthStore::new(conn);
         auth_store.create_test_user("arthur").await.unwrap();
         auth_store.create_test_user("bob").await.unwrap();
         task_store
             .add_task(NewTask {
-                names: names(&[("en", "Task")]),
+                names: names(&[("en", "Mow the lawn")]),
                 starts_with: "arthur".into(),
-                routine: Routine::Schedule,
-                duration: 7,
-                starts_on: NaiveDate::from_ymd_opt(2020, 1, 12).unwrap(),
+                routine: Routine::Interval,
+                duration: 14,
+                starts_on: NaiveDate::from_ymd_opt(2020, 10, 20).unwrap(),
                 participants: vec!["arthur".into(), "bob".into()],
+                active_window: Some("04-01/10-31".parse().unwrap()),
+                ..Default::default()
             })
             .await
             .unwrap();
+        let task = task_store.task(1.into(), &"en".into()).await.unwrap();
+        assert!(task.active);
+        assert_eq!(task.deadline, Deadline::DueToday);
 
+        // the next mow would be in November, so it waits until April
         task_store
-            .mark_task_done(1.into(), "arthur", &today())
+            .mark_task_done(1.into(), "arthur", &today(), None)
             .await
             .unwrap();
+        time::mock::set(NaiveDate::from_ymd_opt(2020, 11, 15).unwrap());
         let task = task_store.task(1.into(), &"en".into()).await.unwrap();
-        assert_eq!(task.name, "Task".to_owned());
-        assert_eq!(task.assigned_to, "bob".to_owned());
-        assert_eq!(
-            task.last_completed,
-            NaiveDate::from_ymd_opt(2020, 1, 12).unwrap()
-        );
-        assert_eq!(task.deadline, Deadline::Upcoming(9));
+        assert!(!task.active);
+        assert_eq!(task.deadline, Deadline::Upcoming(137));
+        assert_eq!(task.assigned_to.as_deref(), Some("bob"));
+
+        task_store.set_active_window(1.into(), None).await.unwrap();
+        let task = task_store.task(1.into(), &"en".into()).await.unwrap();
+        assert!(task.active);
+        assert_eq!(task.deadline, Deadline::Overdue(12));
     }
 
     #[sqlx::test]
-    async fn completing_period_schedule_tasks_rolls_the_date_forward(conn: sqlx::SqlitePool) {
-        time::mock::set(NaiveDate::from_ymd_opt(2020, 1, 14).unwrap());
-        let task_store = TaskStore::new(conn.clone());
+    async fn seasonal_tasks_are_not_overdue_out_of_season(conn: sqlx::SqlitePool) {
+        time::mock::set(NaiveDate::from_ymd_opt(2020, 10, 20).unwrap());
+        let task_store = TaskStore::new(conn.clone(), HouseholdId::default());
         let auth_store = AuthStore::new(conn);
         auth_store.create_test_user("arthur").await.unwrap();
-        auth_store.create_test_user("bob").await.unwrap();
         task_store
             .add_task(NewTask {
-                names: names(&[("en", "Task")]),
+                names: names(&[("en", "Mow the lawn")]),
                 starts_with: "arthur".into(),
-                routine: Routine::Schedule,
-                duration: 7,
-                starts_on: NaiveDate::from_ymd_opt(2020, 1, 1).unwrap(),
-                participants: vec!["arthur".into(), "bob".into()],
+                routine: Routine::Interval,
+                duration: 14,
+                starts_on: NaiveDate::from_ymd_opt(2020, 10, 20).unwrap(),
+                participants: vec!["arthur".into()],
+                active_window: Some("04-01/10-31".parse().unwrap()),
+                ..Default::default()
             })
             .await
             .unwrap();
 
-        // complete for period until 1st
-        task_store
-            .mark_task_done(1.into(), "arthur", &today())
-            .await
-            .unwrap();
+        time::mock::set(NaiveDate::from_ymd_opt(2020, 10, 31).unwrap());
         let task = task_store.task(1.into(), &"en".into()).await.unwrap();
-        assert_eq!(task.assigned_to, "bob".to_owned());
-        assert_eq!(task.deadline, Deadline::Overdue(6));
+        assert_eq!(task.deadline, Deadline::Overdue(11));
 
-        // complete for period 8th - 14th
-        task_store
-            .mark_task_done(1.into(), "bob", &today())
-            .await
-            .unwrap();
+        // the season ends with the task still overdue
+        time::mock::set(NaiveDate::from_ymd_opt(2020, 11, 15).unwrap());
         let task = task_store.task(1.into(), &"en".into()).await.unwrap();
-        assert_eq!(task.assigned_to, "arthur".to_owned()); // assignee updated
-        assert_eq!(task.deadline, Deadline::Upcoming(1)); // next period starts on 8th and continues for 7 days
+        assert!(!task.active);
+        assert_eq!(task.deadline, Deadline::Upcoming(137));
+
+        time::mock::set(NaiveDate::from_ymd_opt(2021, 4, 3).unwrap());
+        let task = task_store.task(1.into(), &"en".into()).await.unwrap();
+        assert!(task.active);
+        assert_eq!(task.deadline, Deadline::Overdue(2));
     }
 
     #[sqlx::test]
-    async fn completing_schedule_task_multiple_times(conn: sqlx::SqlitePool) {
-        time::mock::set(NaiveDate::from_ymd_opt(2020, 1, 14).unwrap());
-        let task_store = TaskStore::new(conn.clone());
+    async fn only_recurring_tasks_can_be_seasonal(conn: sqlx::SqlitePool) {
+        time::mock::set(NaiveDate::from_ymd_opt(2020, 1, 10).unwrap());
+        let task_store = TaskStore::new(conn.clone(), HouseholdId::default());
+        let auth_store = AuthStore::new(conn);
+        auth_store.create_test_user("arthur").await.unwrap();
+        let new_task = || NewTask {
+            names: names(&[("en", "Clear the gutters")]),
+            starts_with: "arthur".into(),
+            routine: Routine::OneOff,
+            starts_on: NaiveDate::from_ymd_opt(2020, 1, 10).unwrap(),
+            participants: vec!["arthur".into()],
+            ..Default::default()
+        };
+
+        let result = task_store
+            .add_task(NewTask {
+                active_window: Some("11-01/11-30".parse().unwrap()),
+                ..new_task()
+            })
+            .await;
+        assert!(matches!(result, Err(TaskStoreError::ActiveWindowMismatch)));
+
+        task_store.add_task(new_task()).await.unwrap();
+        let result = task_store
+            .set_active_window(1.into(), Some("11-01/11-30".parse().unwrap()))
+            .await;
+        assert!(matches!(result, Err(TaskStoreError::ActiveWindowMismatch)));
+        let result = task_store.set_active_window(2.into(), None).await;
+        assert!(matches!(result, Err(TaskStoreError::UnknownTaskId(_))));
+    }
+
+    #[sqlx::test]
+    async fn deadlines_use_each_tasks_urgency(conn: sqlx::SqlitePool) {
+        time::mock::set(NaiveDate::from_ymd_opt(2020, 1, 10).unwrap());
+        let task_store = TaskStore::new(conn.clone(), HouseholdId::default());
         let auth_store = AuthStore::new(conn);
         auth_store.create_test_user("arthur").await.unwrap();
-        auth_store.create_test_user("bob").await.unwrap();
         task_store
             .add_task(NewTask {
-                names: names(&[("en", "Task")]),
+                names: names(&[("en", "Water the plants")]),
                 starts_with: "arthur".into(),
-                routine: Routine::Schedule,
+                routine: Routine::Interval,
                 duration: 7,
-                starts_on: NaiveDate::from_ymd_opt(2020, 1, 10).unwrap(),
-                participants: vec!["arthur".into(), "bob".into()],
+                starts_on: NaiveDate::from_ymd_opt(2020, 1, 12).unwrap(),
+                participants: vec!["arthur".into()],
+                urgency: Urgency {
+                    due_soon_days: 3,
+                    grace_days: Some(2),
+                },
+                ..Default::default()
             })
             .await
             .unwrap();
-
         let task = task_store.task(1.into(), &"en".into()).await.unwrap();
-        assert_eq!(task.assigned_to, "arthur".to_owned());
-        assert_eq!(task.deadline, Deadline::Overdue(4));
+        assert_eq!(task.deadline, Deadline::DueSoon(2));
 
-        task_store
-            .mark_task_done(1.into(), "arthur", &today())
-            .await
-            .unwrap();
+        time::mock::set(NaiveDate::from_ymd_opt(2020, 1, 14).unwrap());
         let task = task_store.task(1.into(), &"en".into()).await.unwrap();
-        assert_eq!(task.assigned_to, "bob".to_owned());
-        assert_eq!(task.deadline, Deadline::Upcoming(3));
+        assert_eq!(task.deadline, Deadline::Overdue(2));
 
-        task_store
-            .mark_task_done(1.into(), "bob", &today())
-            .await
-            .unwrap();
+        time::mock::set(NaiveDate::from_ymd_opt(2020, 1, 15).unwrap());
         let task = task_store.task(1.into(), &"en".into()).await.unwrap();
-        assert_eq!(task.assigned_to, "arthur".to_owned());
-        assert_eq!(task.deadline, Deadline::Upcoming(10));
+        assert_eq!(task.deadline, Deadline::BadlyOverdue(3));
 
-        // the same person does the task multiple times in a row
         task_store
-            .mark_task_done(1.into(), "bob", &today())
+            .set_urgency(1.into(), Urgency::default())
             .await
             .unwrap();
         let task = task_store.task(1.into(), &"en".into()).await.unwrap();
-        assert_eq!(task.assigned_to, "arthur".to_owned());
-        assert_eq!(task.deadline, Deadline::Upcoming(17));
+        assert_eq!(task.urgency, Urgency::default());
+        assert_eq!(task.deadline, Deadline::Overdue(3));
+
+        assert!(matches!(
+            task_store.set_urgency(2.into(), Urgency::default()).await,
+            Err(TaskStoreError::UnknownTaskId(_))
+        ));
     }
 
     #[sqlx::test]
-    async fn completing_interval_task_multiple_times(conn: sqlx::SqlitePool) {
-        time::mock::set(NaiveDate::from_ymd_opt(2020, 1, 14).unwrap());
-        let task_store = TaskStore::new(conn.clone());
+    async fn today_depends_on_the_household_and_personal_timezones(conn: sqlx::SqlitePool) {
+        use chrono::{TimeZone, Utc};
+
+        time::mock::set(NaiveDate::from_ymd_opt(2023, 3, 20).unwrap());
+        let task_store = TaskStore::new(conn.clone(), HouseholdId::default());
         let auth_store = AuthStore::new(conn);
         auth_store.create_test_user("arthur").await.unwrap();
         auth_store.create_test_user("bob").await.unwrap();
         task_store
             .add_task(NewTask {
-                names: names(&[("en", "Task")]),
+                names: names(&[("en", "Water the plants")]),
                 starts_with: "arthur".into(),
-                routine: Routine::Interval,
-                duration: 7,
-                starts_on: NaiveDate::from_ymd_opt(2020, 1, 10).unwrap(),
+                routine: Routine::OneOff,
+                starts_on: NaiveDate::from_ymd_opt(2023, 3, 20).unwrap(),
+                due_on: NaiveDate::from_ymd_opt(2023, 3, 27),
                 participants: vec!["arthur".into(), "bob".into()],
+                ..Default::default()
             })
             .await
             .unwrap();
-
-        let task = task_store.task(1.into(), &"en".into()).await.unwrap();
-        assert_eq!(task.assigned_to, "arthur".to_owned());
-        assert_eq!(task.deadline, Deadline::Overdue(4));
-
         task_store
-            .mark_task_done(1.into(), "arthur", &today())
+            .set_household_timezone(Some("Europe/Berlin".parse().unwrap()))
             .await
             .unwrap();
-        let task = task_store.task(1.into(), &"en".into()).await.unwrap();
-        assert_eq!(task.assigned_to, "bob".to_owned());
-        assert_eq!(task.deadline, Deadline::Upcoming(7));
-
         task_store
-            .mark_task_done(1.into(), "bob", &today())
+            .set_person_timezone("Arthur", Some("America/New_York".parse().unwrap()))
             .await
             .unwrap();
+
+        // Berlin has just switched to summer time (UTC+2)
+        time::mock::set_now(Utc.with_ymd_and_hms(2023, 3, 26, 21, 59, 0).unwrap());
         let task = task_store.task(1.into(), &"en".into()).await.unwrap();
-        assert_eq!(task.assigned_to, "arthur".to_owned());
-        assert_eq!(task.deadline, Deadline::Upcoming(7));
+        assert_eq!(task.deadline, Deadline::Upcoming(1));
+
+        time::mock::set_now(Utc.with_ymd_and_hms(2023, 3, 26, 22, 0, 0).unwrap());
+        let task = task_store.task(1.into(), &"en".into()).await.unwrap();
+        assert_eq!(task.deadline, Deadline::DueToday);
+
+        // it is still the afternoon in New York
+        let tasks = task_store.tasks_for("arthur", &"en".into()).await.unwrap();
+        assert_eq!(tasks[0].deadline, Deadline::Upcoming(1));
+        assert_eq!(
+            task_store.today_for(Some("arthur")).await.unwrap(),
+            NaiveDate::from_ymd_opt(2023, 3, 26).unwrap()
+        );
+        assert_eq!(
+            task_store.today_for(Some("bob")).await.unwrap(),
+            NaiveDate::from_ymd_opt(2023, 3, 27).unwrap()
+        );
 
-        // the same person does the task multiple times in a row
         task_store
-            .mark_task_done(1.into(), "bob", &today())
+            .set_person_timezone("arthur", None)
             .await
             .unwrap();
-        let task = task_store.task(1.into(), &"en".into()).await.unwrap();
-        assert_eq!(task.assigned_to, "arthur".to_owned());
-        assert_eq!(task.deadline, Deadline::Upcoming(7));
+        assert_eq!(
+            task_store.today_for(Some("arthur")).await.unwrap(),
+            NaiveDate::from_ymd_opt(2023, 3, 27).unwrap()
+        );
+
+        assert!(matches!(
+            task_store.set_person_timezone("claire", None).await,
+            Err(TaskStoreError::PersonDoesNotExist(_))
+        ));
     }
 
     #[sqlx::test]
-    async fn handles_tasks_with_only_one_participant(conn: sqlx::SqlitePool) {
-        time::mock::set(NaiveDate::from_ymd_opt(2020, 1, 14).unwrap());
-        let task_store = TaskStore::new(conn.clone());
+    async fn forecasts_future_deadlines_and_assignees(conn: sqlx::SqlitePool) {
+        time::mock::set(NaiveDate::from_ymd_opt(2020, 1, 10).unwrap());
+        let task_store = TaskStore::new(conn.clone(), HouseholdId::default());
         let auth_store = AuthStore::new(conn);
         auth_store.create_test_user("arthur").await.unwrap();
+        auth_store.create_test_user("bob").await.unwrap();
         task_store
             .add_task(NewTask {
-                names: names(&[("en", "Interval Task")]),
+                names: names(&[("en", "Hoover")]),
                 starts_with: "arthur".into(),
                 routine: Routine::Interval,
                 duration: 7,
+                starts_on: NaiveDate::from_ymd_opt(2020, 1, 8).unwrap(),
+                participants: vec!["arthur".into(), "bob".into()],
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        task_store
+            .add_task(NewTask {
+                names: names(&[("en", "Fix the shelf")]),
+                starts_with: "bob".into(),
+                routine: Routine::OneOff,
                 starts_on: NaiveDate::from_ymd_opt(2020, 1, 10).unwrap(),
-                participants: vec!["arthur".into()],
+                due_on: NaiveDate::from_ymd_opt(2020, 1, 20),
+                participants: vec!["arthur".into(), "bob".into()],
+                ..Default::default()
             })
             .await
             .unwrap();
+        task_store
+            .add_absence(
+                "bob",
+                NaiveDate::from_ymd_opt(2020, 1, 15).unwrap(),
+                NaiveDate::from_ymd_opt(2020, 1, 18).unwrap(),
+            )
+            .await
+            .unwrap();
+
+        let forecast = task_store.forecast(3, &"en".into()).await.unwrap();
+        let forecast = forecast
+            .iter()
+            .map(|entry| {
+                (
+                    entry.name.as_str(),
+                    entry.due.to_string(),
+                    entry.assigned_to.as_deref(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            forecast,
+            vec![
+                // overdue, so assumed to be done today
+                ("Hoover", "2020-01-08".to_owned(), Some("arthur")),
+                // bob is away, so arthur goes again
+                ("Hoover", "2020-01-17".to_owned(), Some("arthur")),
+                ("Fix the shelf", "2020-01-20".to_owned(), Some("bob")),
+                ("Hoover", "2020-01-24".to_owned(), Some("bob")),
+                ("Hoover", "2020-01-31".to_owned(), Some("arthur")),
+            ]
+        );
+
+        assert!(matches!(
+            task_store.forecast(53, &"en".into()).await,
+            Err(TaskStoreError::ForecastTooLong)
+        ));
+    }
+
+    #[sqlx::test]
+    async fn calendar_feeds_show_upcoming_tasks(conn: sqlx::SqlitePool) {
+        time::mock::set(NaiveDate::from_ymd_opt(2020, 1, 10).unwrap());
+        let task_store = TaskStore::new(conn.clone(), HouseholdId::default());
+        let auth_store = AuthStore::new(conn);
+        auth_store.create_test_user("arthur").await.unwrap();
+        auth_store.create_test_user("bob").await.unwrap();
         task_store
             .add_task(NewTask {
-                names: names(&[("en", "Schedule Task")]),
+                names: names(&[("en", "Hoover"), ("de", "Staubsaugen")]),
                 starts_with: "arthur".into(),
-                routine: Routine::Schedule,
+                routine: Routine::Interval,
                 duration: 7,
                 starts_on: NaiveDate::from_ymd_opt(2020, 1, 10).unwrap(),
-                participants: vec!["arthur".into()],
+                participants: vec!["arthur".into(), "bob".into()],
+                ..Default::default()
             })
             .await
             .unwrap();
 
-        let task = task_store.task(1.into(), &"en".into()).await.unwrap();
-        assert_eq!(task.assigned_to, "arthur".to_owned());
-        assert_eq!(task.deadline, Deadline::Overdue(4));
+        let household = task_store.add_feed(None, &"de".into()).await.unwrap();
+        let household = task_store.feed(household).await.unwrap();
+        let calendar = TaskStore::calendar(&task_store.conn, household.secret)
+            .await
+            .unwrap();
+        assert_eq!(calendar.matches("BEGIN:VEVENT").count(), 9);
+        assert!(calendar.contains("SUMMARY:Staubsaugen (arthur)\r\n"));
+        assert!(calendar.contains("SUMMARY:Staubsaugen (bob)\r\n"));
 
-        task_store
-            .mark_task_done(2.into(), "arthur", &today())
+        let arthur = task_store
+            .add_feed(Some("Arthur"), &"en".into())
             .await
             .unwrap();
-        let task = task_store.task(2.into(), &"en".into()).await.unwrap();
-        assert_eq!(task.assigned_to, "arthur".to_owned());
-        assert_eq!(task.deadline, Deadline::Upcoming(3));
+        let arthur = task_store.feed(arthur).await.unwrap();
+        assert_eq!(arthur.person.as_deref(), Some("arthur"));
+        let calendar = TaskStore::calendar(&task_store.conn, arthur.secret)
+            .await
+            .unwrap();
+        assert_eq!(calendar.matches("BEGIN:VEVENT").count(), 5);
+        assert!(calendar.contains("DTSTART;VALUE=DATE:20200110\r\n"));
+        assert!(calendar.contains("SUMMARY:Hoover\r\n"));
+
+        task_store.delete_feed(arthur.id).await.unwrap();
+        assert!(matches!(
+            TaskStore::calendar(&task_store.conn, arthur.secret).await,
+            Err(TaskStoreError::UnknownFeedSecret)
+        ));
+        assert_eq!(task_store.feeds().await.unwrap(), vec![household]);
+
+        assert!(matches!(
+            task_store.add_feed(Some("claire"), &"en".into()).await,
+            Err(TaskStoreError::PersonDoesNotExist(_))
+        ));
     }
 
     #[sqlx::test]
     async fn returns_error_if_fetched_task_does_not_exist(conn: sqlx::SqlitePool) {
         time::mock::set(NaiveDate::from_ymd_opt(2020, 1, 14).unwrap());
-        let task_store = TaskStore::new(conn);
+        let task_store = TaskStore::new(conn, HouseholdId::default());
         let result = task_store.task(4.into(), &"en".into()).await.unwrap_err();
 
         match result {
@@ -0,0 +1,86 @@
+// SPDX-FileCopyrightText: 2023 Jonathan Frere
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! A built-in catalogue of common chores, so that setting up a household
+//! doesn't mean typing out every task's names by hand.  The catalogue itself
+//! lives in `templates.toml`.
+
+use std::collections::HashMap;
+
+use super::{store::NewTask, types::Routine};
+
+/// A common chore, with its names and how often it is usually done.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct Template {
+    pub id: String,
+    /// Names, keyed by language
+    pub names: HashMap<String, String>,
+    pub routine: Routine,
+    /// Suggested length of each cycle in days
+    pub duration: u16,
+}
+
+impl Template {
+    /// A new task with this template's names, routine and duration.  The
+    /// participants and start still need to be filled in.
+    pub fn new_task(&self) -> NewTask {
+        NewTask {
+            names: self.names.clone(),
+            routine: self.routine,
+            duration: self.duration,
+            ..Default::default()
+        }
+    }
+
+    /// Every template, in the order they appear in the catalogue.
+    pub fn catalogue() -> Vec<Template> {
+        toml::from_str::<Catalogue>(include_str!("./templates.toml"))
+            .expect("the template catalogue is valid")
+            .template
+    }
+
+    pub fn find(id: &str) -> Option<Template> {
+        Self::catalogue()
+            .into_iter()
+            .find(|template| template.id == id)
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct Catalogue {
+    template: Vec<Template>,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn every_template_has_a_unique_id_and_both_names() {
+        let templates = Template::catalogue();
+        assert!(!templates.is_empty());
+
+        let ids = templates
+            .iter()
+            .map(|template| template.id.as_str())
+            .collect::<HashSet<_>>();
+        assert_eq!(ids.len(), templates.len());
+
+        for template in &templates {
+            assert!(template.names.contains_key("en"), "{}", template.id);
+            assert!(template.names.contains_key("de"), "{}", template.id);
+            assert!(template.duration > 0, "{}", template.id);
+        }
+    }
+
+    #[test]
+    fn finds_templates_by_id() {
+        let template = Template::find("hoover").unwrap();
+        assert_eq!(template.names["de"], "Staubsaugen");
+        assert_eq!(template.routine, Routine::Interval);
+        assert!(Template::find("juggle").is_none());
+    }
+}
@@ -2,20 +2,109 @@
 //
 // SPDX-License-Identifier: MPL-2.0
 
+use std::{fmt::Display, str::FromStr};
+
+use chrono::{DateTime, Local, NaiveDate, Utc};
+use chrono_tz::Tz;
+use sqlx::Sqlite;
+
+#[derive(thiserror::Error, Debug, PartialEq, Eq)]
+#[error("{0} is not a known timezone")]
+pub struct TimezoneError(String);
+
+/// An IANA timezone, e.g. `Europe/Berlin`, which decides when one day ends and
+/// the next begins.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timezone(Tz);
+
+/// Today's date in the given timezone, or in the server's own timezone if none
+/// has been configured.
+pub fn today_in(timezone: Option<Timezone>) -> NaiveDate {
+    date_in(now(), timezone)
+}
+
+/// The date at the given moment in the given timezone, or in the server's own
+/// timezone if none has been configured.
+pub fn date_in(time: DateTime<Utc>, timezone: Option<Timezone>) -> NaiveDate {
+    match timezone {
+        Some(Timezone(tz)) => time.with_timezone(&tz).date_naive(),
+        None => time.with_timezone(&Local).date_naive(),
+    }
+}
+
 #[cfg(not(test))]
-pub fn today() -> chrono::NaiveDate {
-    chrono::Local::now().date_naive()
+pub fn now() -> DateTime<Utc> {
+    Utc::now()
+}
+
+#[cfg(test)]
+pub use mock::now;
+
+impl FromStr for Timezone {
+    type Err = TimezoneError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        name.trim()
+            .parse()
+            .map(Self)
+            .map_err(|_| TimezoneError(name.to_owned()))
+    }
+}
+
+impl Display for Timezone {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.0.name())
+    }
+}
+
+impl serde::Serialize for Timezone {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for Timezone {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+impl sqlx::Type<Sqlite> for Timezone {
+    fn type_info() -> <Sqlite as sqlx::Database>::TypeInfo {
+        <String as sqlx::Type<Sqlite>>::type_info()
+    }
+}
+
+impl<'q> sqlx::Encode<'q, Sqlite> for Timezone {
+    fn encode_by_ref(
+        &self,
+        buf: &mut <Sqlite as sqlx::database::HasArguments<'q>>::ArgumentBuffer,
+    ) -> sqlx::encode::IsNull {
+        <String as sqlx::Encode<Sqlite>>::encode(self.to_string(), buf)
+    }
+}
+
+impl<'r> sqlx::Decode<'r, Sqlite> for Timezone {
+    fn decode(
+        value: <Sqlite as sqlx::database::HasValueRef<'r>>::ValueRef,
+    ) -> Result<Self, sqlx::error::BoxDynError> {
+        Ok(<&str as sqlx::Decode<Sqlite>>::decode(value)?.parse()?)
+    }
 }
 
 #[cfg(test)]
 pub mod mock {
     use std::cell::RefCell;
 
+    use chrono::{DateTime, NaiveDate, Utc};
+
     thread_local! {
-        static MOCK_TIME: RefCell<Option<chrono::NaiveDate>> = RefCell::new(None);
+        static MOCK_TIME: RefCell<Option<DateTime<Utc>>> = const { RefCell::new(None) };
     }
 
-    pub fn today() -> chrono::NaiveDate {
+    pub fn now() -> DateTime<Utc> {
         MOCK_TIME.with(|cell| {
             cell.borrow()
                 .as_ref()
@@ -24,10 +113,72 @@ pub mod mock {
         })
     }
 
-    pub fn set(time: chrono::NaiveDate) {
+    /// Pretends that it is midday (UTC) on the given date, which is the same
+    /// date in most timezones.
+    pub fn set(date: NaiveDate) {
+        set_now(date.and_hms_opt(12, 0, 0).unwrap().and_utc());
+    }
+
+    pub fn set_now(time: DateTime<Utc>) {
         MOCK_TIME.with(|cell| *cell.borrow_mut() = Some(time));
     }
 }
 
 #[cfg(test)]
-pub use mock::today;
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn timezone(name: &str) -> Option<Timezone> {
+        Some(name.parse().unwrap())
+    }
+
+    #[test]
+    fn parses_and_formats_timezones() {
+        assert_eq!(
+            timezone("Europe/Berlin").unwrap().to_string(),
+            "Europe/Berlin"
+        );
+        assert_eq!(
+            "Europe/Atlantis".parse::<Timezone>(),
+            Err(TimezoneError("Europe/Atlantis".to_owned()))
+        );
+    }
+
+    #[test]
+    fn today_depends_on_the_timezone() {
+        mock::set_now(Utc.with_ymd_and_hms(2023, 1, 10, 23, 30, 0).unwrap());
+        assert_eq!(today_in(timezone("UTC")), date(2023, 1, 10));
+        assert_eq!(today_in(timezone("Europe/Berlin")), date(2023, 1, 11));
+        assert_eq!(today_in(timezone("America/New_York")), date(2023, 1, 10));
+    }
+
+    #[test]
+    fn today_follows_daylight_saving_time() {
+        // Berlin is UTC+1 in winter and UTC+2 in summer, switching on the
+        // last Sundays of March and October
+        let berlin = timezone("Europe/Berlin");
+
+        mock::set_now(Utc.with_ymd_and_hms(2023, 3, 25, 22, 30, 0).unwrap());
+        assert_eq!(today_in(berlin), date(2023, 3, 25));
+        mock::set_now(Utc.with_ymd_and_hms(2023, 3, 25, 23, 30, 0).unwrap());
+        assert_eq!(today_in(berlin), date(2023, 3, 26));
+
+        // after the clocks go forward, the day starts an hour earlier in UTC
+        mock::set_now(Utc.with_ymd_and_hms(2023, 3, 26, 21, 59, 0).unwrap());
+        assert_eq!(today_in(berlin), date(2023, 3, 26));
+        mock::set_now(Utc.with_ymd_and_hms(2023, 3, 26, 22, 0, 0).unwrap());
+        assert_eq!(today_in(berlin), date(2023, 3, 27));
+
+        // and an hour later again once they go back
+        mock::set_now(Utc.with_ymd_and_hms(2023, 10, 29, 22, 59, 0).unwrap());
+        assert_eq!(today_in(berlin), date(2023, 10, 29));
+        mock::set_now(Utc.with_ymd_and_hms(2023, 10, 29, 23, 0, 0).unwrap());
+        assert_eq!(today_in(berlin), date(2023, 10, 30));
+    }
+}
@@ -2,13 +2,59 @@
 //
 // SPDX-License-Identifier: MPL-2.0
 
+use std::fmt::Display;
+
 use chrono::{Duration, NaiveDate};
 use sqlx::Sqlite;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize, serde::Serialize, sqlx::Type)]
+use super::{recurrence::Recurrence, season::ActiveWindow};
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Default, serde::Deserialize, serde::Serialize, sqlx::Type,
+)]
 pub enum Routine {
     Schedule,
+    #[default]
     Interval,
+    /// Due on the dates given by the task's recurrence rule, e.g. every Monday.
+    Calendar,
+    /// Done once, then gone.
+    OneOff,
+    /// Only due once another task that it is chained to has been done.
+    Triggered,
+}
+
+/// What happens to the cycles of a `Schedule` task that went by without it
+/// being done.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Default, serde::Deserialize, serde::Serialize, sqlx::Type,
+)]
+pub enum CatchUp {
+    /// Every missed cycle still has to be done, one completion each.
+    #[default]
+    Accumulate,
+    /// The next completion makes up for every missed cycle at once.
+    Collapse,
+    /// Missed cycles are dropped as soon as they are over, so the task is only
+    /// ever due for the current cycle.
+    Skip,
+}
+
+/// How the next person is picked once a task has been completed.
+#[derive(Debug, Clone, PartialEq, Eq, Default, serde::Deserialize, serde::Serialize)]
+pub enum Rotation {
+    /// Hand the task to the participant after whoever did it last.
+    #[default]
+    RoundRobin,
+    /// Hand the task to whoever has gone the longest without doing it.
+    LeastRecentlyDone,
+    /// Hand the task to whoever has done it the fewest times in the `days`
+    /// leading up to when it is due.
+    FewestCompletions { days: u16 },
+    /// Always hand the task to the same participant.
+    FixedOwner(String),
+    /// Pick a participant at random, reproducibly for a given seed.
+    SeededRandom { seed: u32 },
 }
 
 #[derive(
@@ -17,6 +63,9 @@ pub enum Routine {
     Copy,
     PartialEq,
     Eq,
+    PartialOrd,
+    Ord,
+    Hash,
     serde::Deserialize,
     serde::Serialize,
     sqlx::Encode,
@@ -30,26 +79,237 @@ impl sqlx::Type<Sqlite> for TaskId {
     }
 }
 
-#[cfg(test)]
 impl From<i32> for TaskId {
     fn from(value: i32) -> Self {
         TaskId(value)
     }
 }
 
+impl Display for TaskId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    serde::Deserialize,
+    serde::Serialize,
+    sqlx::Encode,
+    sqlx::Decode,
+)]
+pub struct TagId(i32);
+
+impl sqlx::Type<Sqlite> for TagId {
+    fn type_info() -> <Sqlite as sqlx::Database>::TypeInfo {
+        <i32 as sqlx::Type<sqlx::Sqlite>>::type_info()
+    }
+}
+
+#[cfg(test)]
+impl From<i32> for TagId {
+    fn from(value: i32) -> Self {
+        TagId(value)
+    }
+}
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    serde::Deserialize,
+    serde::Serialize,
+    sqlx::Encode,
+    sqlx::Decode,
+)]
+pub struct StepId(i32);
+
+impl sqlx::Type<Sqlite> for StepId {
+    fn type_info() -> <Sqlite as sqlx::Database>::TypeInfo {
+        <i32 as sqlx::Type<sqlx::Sqlite>>::type_info()
+    }
+}
+
+impl From<i32> for StepId {
+    fn from(value: i32) -> Self {
+        StepId(value)
+    }
+}
+
+impl Display for StepId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// One of the things that needs doing to complete a task, e.g. "mop the floor".
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct ChecklistStep {
+    pub id: StepId,
+    pub name: String,
+}
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    serde::Deserialize,
+    serde::Serialize,
+    sqlx::Encode,
+    sqlx::Decode,
+)]
+pub struct AbsenceId(i32);
+
+impl sqlx::Type<Sqlite> for AbsenceId {
+    fn type_info() -> <Sqlite as sqlx::Database>::TypeInfo {
+        <i32 as sqlx::Type<sqlx::Sqlite>>::type_info()
+    }
+}
+
+impl From<i32> for AbsenceId {
+    fn from(value: i32) -> Self {
+        AbsenceId(value)
+    }
+}
+
+impl Display for AbsenceId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// A period when somebody is away, so shouldn't be given any tasks due then.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize, serde::Serialize, sqlx::FromRow)]
+pub struct Absence {
+    pub id: AbsenceId,
+    pub person: String,
+    pub starts_on: NaiveDate,
+    /// The last day of the absence
+    pub ends_on: NaiveDate,
+}
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    serde::Deserialize,
+    serde::Serialize,
+    sqlx::Encode,
+    sqlx::Decode,
+)]
+pub struct ChainId(i32);
+
+impl sqlx::Type<Sqlite> for ChainId {
+    fn type_info() -> <Sqlite as sqlx::Database>::TypeInfo {
+        <i32 as sqlx::Type<sqlx::Sqlite>>::type_info()
+    }
+}
+
+impl From<i32> for ChainId {
+    fn from(value: i32) -> Self {
+        ChainId(value)
+    }
+}
+
+impl Display for ChainId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// Who a chained task goes to when it is triggered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize, serde::Serialize, sqlx::Type)]
+pub enum ChainAssignee {
+    /// Whoever did the task that triggered it (if they take part in the chained task).
+    SamePerson,
+    /// Whoever is next in the chained task's own rotation.
+    NextInRotation,
+}
+
+/// A rule that completing one task makes another (triggered) task due.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize, serde::Serialize, sqlx::FromRow)]
+pub struct Chain {
+    pub id: ChainId,
+    pub from_task: TaskId,
+    pub to_task: TaskId,
+    /// Days after the first task was done that the chained task is due
+    pub offset_days: u16,
+    pub assignee: ChainAssignee,
+}
+
+/// A room or category that tasks can be grouped under.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize, serde::Serialize, sqlx::FromRow)]
+pub struct Tag {
+    pub id: TagId,
+    pub name: String,
+}
+
+/// How a task's deadline is classified: when it starts to count as due soon,
+/// and how long it can be overdue before it counts as badly overdue.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Deserialize, serde::Serialize)]
+pub struct Urgency {
+    /// Tasks due within this many days (but not today) are due soon
+    pub due_soon_days: u16,
+    /// Days a task can be overdue before it is badly overdue, or `None` if it
+    /// never is
+    pub grace_days: Option<u16>,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
 pub enum Deadline {
+    /// Due in more days than the task's due-soon threshold.
     Upcoming(u16),
+    /// Due within the task's due-soon threshold, but not today.
+    DueSoon(u16),
+    DueToday,
+    /// Past its due date, but still within the task's grace period.
     Overdue(u16),
+    /// Past its due date and the task's grace period.
+    BadlyOverdue(u16),
+    /// The task has no due date (e.g. a one-off task that can be done whenever).
+    Unscheduled,
 }
 
-impl From<Duration> for Deadline {
-    fn from(duration: Duration) -> Self {
-        let days = duration.num_days();
-        if days >= 0 {
-            Self::Upcoming(days as u16)
-        } else {
-            Self::Overdue((-days) as u16)
+impl Deadline {
+    /// Classifies a deadline that is `until_due` away (negative if it has
+    /// passed).  Anything further away than `u16::MAX` days is capped there.
+    pub fn new(until_due: Duration, urgency: Urgency) -> Self {
+        let days = until_due.num_days();
+        let abs_days = u16::try_from(days.unsigned_abs()).unwrap_or(u16::MAX);
+        match days {
+            0 => Self::DueToday,
+            1.. if abs_days <= urgency.due_soon_days => Self::DueSoon(abs_days),
+            1.. => Self::Upcoming(abs_days),
+            _ if urgency.grace_days.is_some_and(|grace| abs_days > grace) => {
+                Self::BadlyOverdue(abs_days)
+            }
+            _ => Self::Overdue(abs_days),
+        }
+    }
+
+    /// The date the task is due, given the date the deadline was worked out on.
+    pub fn due_date(&self, today: NaiveDate) -> Option<NaiveDate> {
+        match *self {
+            Self::Upcoming(days) | Self::DueSoon(days) => Some(today + Duration::days(days.into())),
+            Self::DueToday => Some(today),
+            Self::Overdue(days) | Self::BadlyOverdue(days) => {
+                Some(today - Duration::days(days.into()))
+            }
+            Self::Unscheduled => None,
         }
     }
 }
@@ -58,10 +318,345 @@ impl From<Duration> for Deadline {
 pub struct Task {
     pub id: TaskId,
     pub name: String,
+    /// Markdown explaining what the task involves
+    pub description: Option<String>,
     pub kind: Routine,
-    pub assigned_to: String,
+    pub rotation: Rotation,
+    pub catch_up: CatchUp,
+    pub recurrence: Option<Recurrence>,
+    /// The part of the year the task is done in, if it is seasonal
+    pub active_window: Option<ActiveWindow>,
+    /// Whether today falls inside the task's active window
+    pub active: bool,
+    pub assigned_to: Option<String>,
     pub deadline: Deadline,
+    pub urgency: Urgency,
+    /// Points earned by each person who does the task
+    pub points: u32,
     pub length_days: u16,
     pub last_completed: NaiveDate,
     pub participants: Vec<String>,
+    pub tags: Vec<Tag>,
+    pub checklist: Vec<ChecklistStep>,
+    /// Checklist steps ticked off the last time the task was done, if they were recorded
+    pub last_steps_done: Option<Vec<StepId>>,
+}
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    serde::Deserialize,
+    serde::Serialize,
+    sqlx::Encode,
+    sqlx::Decode,
+)]
+pub struct FeedId(i32);
+
+impl sqlx::Type<Sqlite> for FeedId {
+    fn type_info() -> <Sqlite as sqlx::Database>::TypeInfo {
+        <i32 as sqlx::Type<sqlx::Sqlite>>::type_info()
+    }
+}
+
+impl From<i32> for FeedId {
+    fn from(value: i32) -> Self {
+        FeedId(value)
+    }
+}
+
+impl Display for FeedId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// A read-only calendar of upcoming tasks that calendar apps can subscribe to
+/// using only the secret, without logging in.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize, serde::Serialize, sqlx::FromRow)]
+pub struct Feed {
+    pub id: FeedId,
+    /// Whose tasks are shown, or `None` for the whole household's
+    pub person: Option<String>,
+    /// Language that task names are shown in
+    pub language: String,
+    pub secret: uuid::Uuid,
+}
+
+/// A future date that a task is expected to be due on, assuming it is done on
+/// time, and who it should go to then.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct ForecastEntry {
+    pub task_id: TaskId,
+    pub name: String,
+    pub due: NaiveDate,
+    pub assigned_to: Option<String>,
+}
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    serde::Deserialize,
+    serde::Serialize,
+    sqlx::Encode,
+    sqlx::Decode,
+)]
+pub struct CompletionId(i32);
+
+impl sqlx::Type<Sqlite> for CompletionId {
+    fn type_info() -> <Sqlite as sqlx::Database>::TypeInfo {
+        <i32 as sqlx::Type<sqlx::Sqlite>>::type_info()
+    }
+}
+
+impl From<i32> for CompletionId {
+    fn from(value: i32) -> Self {
+        CompletionId(value)
+    }
+}
+
+impl Display for CompletionId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    serde::Deserialize,
+    serde::Serialize,
+    sqlx::Encode,
+    sqlx::Decode,
+)]
+pub struct AttachmentId(i32);
+
+impl sqlx::Type<Sqlite> for AttachmentId {
+    fn type_info() -> <Sqlite as sqlx::Database>::TypeInfo {
+        <i32 as sqlx::Type<sqlx::Sqlite>>::type_info()
+    }
+}
+
+impl From<i32> for AttachmentId {
+    fn from(value: i32) -> Self {
+        AttachmentId(value)
+    }
+}
+
+impl Display for AttachmentId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// An image uploaded when a task was done, e.g. a photo of the cleaned oven.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize, serde::Serialize, sqlx::FromRow)]
+pub struct Attachment {
+    pub id: AttachmentId,
+    pub content_type: String,
+    /// Size of the image in bytes
+    pub size: u32,
+}
+
+/// A time that a task was done, as shown in the task's history.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct HistoryEntry {
+    pub id: CompletionId,
+    pub completed_by: String,
+    /// Everyone else who did the task at the same time
+    pub helpers: Vec<String>,
+    pub completed_on: NaiveDate,
+    pub note: Option<String>,
+    pub attachments: Vec<Attachment>,
+}
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    serde::Deserialize,
+    serde::Serialize,
+    sqlx::Encode,
+    sqlx::Decode,
+)]
+pub struct LedgerEntryId(i32);
+
+impl sqlx::Type<Sqlite> for LedgerEntryId {
+    fn type_info() -> <Sqlite as sqlx::Database>::TypeInfo {
+        <i32 as sqlx::Type<sqlx::Sqlite>>::type_info()
+    }
+}
+
+impl From<i32> for LedgerEntryId {
+    fn from(value: i32) -> Self {
+        LedgerEntryId(value)
+    }
+}
+
+impl Display for LedgerEntryId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// Why somebody's points changed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize, serde::Serialize, sqlx::Type)]
+pub enum LedgerKind {
+    /// Points earned by doing a task
+    Completion,
+    /// Points given or taken away by hand
+    Adjustment,
+    /// Points spent on a reward
+    Redemption,
+}
+
+impl Display for LedgerKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Debug::fmt(self, f)
+    }
+}
+
+/// A single change to somebody's points.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize, serde::Serialize, sqlx::FromRow)]
+pub struct LedgerEntry {
+    pub id: LedgerEntryId,
+    pub person: String,
+    pub entry_on: NaiveDate,
+    pub kind: LedgerKind,
+    /// Negative when points were taken away or spent
+    pub points: i32,
+    /// The task that was done, for completions
+    pub task_id: Option<TaskId>,
+    pub description: Option<String>,
+}
+
+/// How many points somebody has to spend.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize, serde::Serialize, sqlx::FromRow)]
+pub struct Balance {
+    pub person: String,
+    pub points: i32,
+}
+
+/// A single time that a task was done, as used to work out who is next.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Completion {
+    pub completed_by: String,
+    /// Everyone else who did the task at the same time
+    pub helpers: Vec<String>,
+    pub completed_on: NaiveDate,
+}
+
+impl Completion {
+    /// Whether the person did the task, either on their own or with others.
+    pub fn was_done_by(&self, person: &str) -> bool {
+        self.completed_by == person || self.helpers.iter().any(|helper| helper == person)
+    }
+}
+
+/// Whether a task's due date has already passed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize, sqlx::Type)]
+pub enum DueStatus {
+    Overdue,
+    Upcoming,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize, sqlx::Type)]
+pub enum TaskSort {
+    /// Soonest due first, tasks without a due date last.
+    Deadline,
+    Name,
+    LastCompleted,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Deserialize, sqlx::Type)]
+pub enum SortOrder {
+    #[default]
+    Ascending,
+    Descending,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deadlines_are_classified_by_urgency() {
+        let urgency = Urgency {
+            due_soon_days: 2,
+            grace_days: Some(3),
+        };
+        let deadline = |days| Deadline::new(Duration::days(days), urgency);
+
+        assert_eq!(deadline(3), Deadline::Upcoming(3));
+        assert_eq!(deadline(2), Deadline::DueSoon(2));
+        assert_eq!(deadline(1), Deadline::DueSoon(1));
+        assert_eq!(deadline(0), Deadline::DueToday);
+        assert_eq!(deadline(-1), Deadline::Overdue(1));
+        assert_eq!(deadline(-3), Deadline::Overdue(3));
+        assert_eq!(deadline(-4), Deadline::BadlyOverdue(4));
+    }
+
+    #[test]
+    fn deadlines_without_a_grace_period_are_never_badly_overdue() {
+        let urgency = Urgency::default();
+        assert_eq!(
+            Deadline::new(Duration::days(1), urgency),
+            Deadline::Upcoming(1)
+        );
+        assert_eq!(
+            Deadline::new(Duration::days(-1000), urgency),
+            Deadline::Overdue(1000)
+        );
+    }
+
+    #[test]
+    fn distant_deadlines_saturate_instead_of_wrapping() {
+        let urgency = Urgency {
+            due_soon_days: 0,
+            grace_days: Some(0),
+        };
+        assert_eq!(
+            Deadline::new(Duration::days(70_000), urgency),
+            Deadline::Upcoming(u16::MAX)
+        );
+        assert_eq!(
+            Deadline::new(Duration::days(-70_000), urgency),
+            Deadline::BadlyOverdue(u16::MAX)
+        );
+        assert_eq!(
+            Deadline::new(Duration::days(-65_536), Urgency::default()),
+            Deadline::Overdue(u16::MAX)
+        );
+    }
+
+    #[test]
+    fn due_dates_round_trip_through_deadlines() {
+        let today = NaiveDate::from_ymd_opt(2023, 1, 10).unwrap();
+        let urgency = Urgency {
+            due_soon_days: 2,
+            grace_days: Some(3),
+        };
+        for days in [-10, -2, 0, 1, 5] {
+            let due = today + Duration::days(days);
+            assert_eq!(
+                Deadline::new(due - today, urgency).due_date(today),
+                Some(due)
+            );
+        }
+        assert_eq!(Deadline::Unscheduled.due_date(today), None);
+    }
 }
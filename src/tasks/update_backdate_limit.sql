@@ -0,0 +1,8 @@
+-- SPDX-FileCopyrightText: 2023 Jonathan Frere
+--
+-- SPDX-License-Identifier: MPL-2.0
+UPDATE households
+SET
+  backdate_days = ?
+WHERE
+  id = ?
\ No newline at end of file
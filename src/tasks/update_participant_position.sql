@@ -0,0 +1,16 @@
+-- SPDX-FileCopyrightText: 2023 Jonathan Frere
+--
+-- SPDX-License-Identifier: MPL-2.0
+UPDATE task_participant_link
+SET
+  position = ?
+WHERE
+  task_id = ?
+  AND user_id = (
+    SELECT
+      id
+    FROM
+      users
+    WHERE
+      username = ? COLLATE NOCASE
+  )
\ No newline at end of file
@@ -0,0 +1,16 @@
+-- SPDX-FileCopyrightText: 2023 Jonathan Frere
+--
+-- SPDX-License-Identifier: MPL-2.0
+UPDATE users
+SET
+  timezone = ?
+WHERE
+  username = ? COLLATE NOCASE
+  AND id IN (
+    SELECT
+      user_id
+    FROM
+      household_members
+    WHERE
+      household_id = ?
+  )
\ No newline at end of file
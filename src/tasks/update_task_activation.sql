@@ -0,0 +1,19 @@
+-- SPDX-FileCopyrightText: 2023 Jonathan Frere
+--
+-- SPDX-License-Identifier: MPL-2.0
+-- claimed_by is only set if the person is a participant in the task
+UPDATE tasks
+SET
+  due_on = ?1,
+  claimed_by = (
+    SELECT
+      task_participant_link.user_id
+    FROM
+      task_participant_link
+      INNER JOIN users ON users.id = task_participant_link.user_id
+    WHERE
+      task_participant_link.task_id = tasks.id
+      AND users.username = ?2 COLLATE NOCASE
+  )
+WHERE
+  id = ?3
\ No newline at end of file
@@ -0,0 +1,13 @@
+-- SPDX-FileCopyrightText: 2023 Jonathan Frere
+--
+-- SPDX-License-Identifier: MPL-2.0
+UPDATE tasks
+SET
+  claimed_by = task_participant_link.user_id
+FROM
+  task_participant_link
+  INNER JOIN users ON users.id = task_participant_link.user_id
+WHERE
+  task_participant_link.task_id = tasks.id
+  AND users.username = ? COLLATE NOCASE
+  AND tasks.id = ?
\ No newline at end of file
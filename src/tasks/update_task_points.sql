@@ -0,0 +1,9 @@
+-- SPDX-FileCopyrightText: 2023 Jonathan Frere
+--
+-- SPDX-License-Identifier: MPL-2.0
+UPDATE tasks
+SET
+  points = ?
+WHERE
+  id = ?
+  AND household_id = ?
\ No newline at end of file
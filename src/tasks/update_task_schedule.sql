@@ -0,0 +1,16 @@
+-- SPDX-FileCopyrightText: 2023 Jonathan Frere
+--
+-- SPDX-License-Identifier: MPL-2.0
+UPDATE tasks
+SET
+  next_due = ?,
+  assigned_to = (
+    SELECT
+      id
+    FROM
+      users
+    WHERE
+      username = ? COLLATE NOCASE
+  )
+WHERE
+  tasks.id = ?
\ No newline at end of file
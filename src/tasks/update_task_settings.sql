@@ -0,0 +1,17 @@
+-- SPDX-FileCopyrightText: 2023 Jonathan Frere
+--
+-- SPDX-License-Identifier: MPL-2.0
+UPDATE tasks
+SET
+  kind = ?,
+  duration = ?,
+  rotation = ?,
+  catch_up = ?,
+  recurrence = ?,
+  active_window = ?,
+  due_soon_days = ?,
+  grace_days = ?,
+  points = ?
+WHERE
+  id = ?
+  AND household_id = ?
\ No newline at end of file
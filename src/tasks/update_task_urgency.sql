@@ -0,0 +1,10 @@
+-- SPDX-FileCopyrightText: 2023 Jonathan Frere
+--
+-- SPDX-License-Identifier: MPL-2.0
+UPDATE tasks
+SET
+  due_soon_days = ?,
+  grace_days = ?
+WHERE
+  id = ?
+  AND household_id = ?
\ No newline at end of file
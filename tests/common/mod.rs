@@ -37,7 +37,9 @@ pub async fn harness_with_token() -> TestHarness {
     let mut harness = harness().await;
     let auth = harness.auth_store();
 
-    auth.create_user("__test_user", "").await.unwrap();
+    auth.create_user("__test_user", "", auth::HouseholdId::default())
+        .await
+        .unwrap();
     let token = auth.login("__test_user", "").await.unwrap();
     harness.token = Some(token);
     harness
@@ -57,16 +59,17 @@ impl TestHarness {
         auth::AuthStore::new(self.conn.clone())
     }
     pub fn task_store(&self) -> tasks::TaskStore {
-        tasks::TaskStore::new(self.conn.clone())
+        tasks::TaskStore::new(self.conn.clone(), auth::HouseholdId::default())
+    }
+    pub fn url(&self, path: impl AsRef<str>) -> String {
+        format!("http://{}{}", self.addr, path.as_ref())
     }
     pub fn request(
         &self,
         method: reqwest::Method,
         path: impl AsRef<str>,
     ) -> reqwest::RequestBuilder {
-        let builder = self
-            .client
-            .request(method, format!("http://{}{}", self.addr, path.as_ref()));
+        let builder = self.client.request(method, self.url(path));
         match &self.token {
             Some(token) => builder.header("token", token),
             None => builder,
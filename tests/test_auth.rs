@@ -2,6 +2,7 @@
 //
 // SPDX-License-Identifier: MPL-2.0
 
+use homie::{auth::HouseholdId, tasks::Task};
 use reqwest::Method;
 mod common;
 
@@ -11,7 +12,7 @@ async fn login_succeeds() {
 
     server
         .auth_store()
-        .create_user("hello", "password")
+        .create_user("hello", "password", HouseholdId::default())
         .await
         .unwrap();
 
@@ -27,3 +28,136 @@ async fn login_succeeds() {
 
     assert!(token.parse::<uuid::Uuid>().is_ok());
 }
+
+#[tokio::test]
+async fn logins_and_requests_choose_a_household() {
+    let server = common::harness().await;
+    let auth = server.auth_store();
+    auth.create_user("hello", "password", HouseholdId::default())
+        .await
+        .unwrap();
+    let flat = auth.create_household("Flat").await.unwrap();
+    auth.join_household("hello", flat).await.unwrap();
+    auth.create_household("Elsewhere").await.unwrap();
+    server
+        .task_store()
+        .add_task(homie::tasks::NewTask {
+            names: [("en".to_owned(), "Hoover".to_owned())].into(),
+            routine: homie::tasks::Routine::Interval,
+            duration: 7,
+            participants: vec!["hello".to_owned()],
+            starts_on: chrono::Local::now().date_naive(),
+            starts_with: "hello".to_owned(),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    let login = |household: &str| {
+        server
+            .request(Method::POST, "/api/auth/login")
+            .json(&serde_json::json!({
+                "username": "hello",
+                "password": "password",
+                "household": household,
+            }))
+    };
+    let tasks = |token: &str, household: Option<&str>| {
+        let request = server
+            .request(Method::GET, "/api/tasks")
+            .header("token", token);
+        match household {
+            Some(household) => request.header("household", household),
+            None => request,
+        }
+    };
+
+    let token = login("flat")
+        .send()
+        .await
+        .unwrap()
+        .json::<String>()
+        .await
+        .unwrap();
+    let response = tasks(&token, None).send().await.unwrap();
+    assert_eq!(response.json::<Vec<Task>>().await.unwrap(), vec![]);
+
+    // requests can switch to any other household the user belongs to
+    let response = tasks(&token, Some("Home")).send().await.unwrap();
+    assert_eq!(response.json::<Vec<Task>>().await.unwrap().len(), 1);
+    let response = tasks(&token, Some("Elsewhere")).send().await.unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+
+    let response = login("Elsewhere").send().await.unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+    let response = login("Nowhere").send().await.unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn only_admins_can_export_and_import_the_household() {
+    let server = common::harness_with_token().await;
+    server
+        .auth_store()
+        .create_user("hello", "password", HouseholdId::default())
+        .await
+        .unwrap();
+
+    let response = server
+        .request(Method::GET, "/api/admin/export")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::FORBIDDEN);
+
+    server
+        .auth_store()
+        .set_admin("__test_user", HouseholdId::default(), true)
+        .await
+        .unwrap();
+    let document = server
+        .request(Method::GET, "/api/admin/export?with_hashes=true")
+        .send()
+        .await
+        .unwrap()
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    assert_eq!(document["version"], 1);
+    assert_eq!(document["users"][1]["name"], "hello");
+    assert!(document["users"][1]["hash"].is_string());
+
+    let summary = server
+        .request(Method::POST, "/api/admin/import")
+        .json(&document)
+        .send()
+        .await
+        .unwrap()
+        .json::<homie::export::ImportSummary>()
+        .await
+        .unwrap();
+    assert_eq!(summary, homie::export::ImportSummary::default());
+
+    // users from other households can't be pulled in through the API
+    let other = server.auth_store().create_household("Other").await.unwrap();
+    server
+        .auth_store()
+        .create_user("stranger", "password", other)
+        .await
+        .unwrap();
+    let mut document = document;
+    document["users"][1]["name"] = "stranger".into();
+    let response = server
+        .request(Method::POST, "/api/admin/import")
+        .json(&document)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+    let members = server
+        .auth_store()
+        .members(HouseholdId::default())
+        .await
+        .unwrap();
+    assert!(!members.contains(&"stranger".to_owned()));
+}
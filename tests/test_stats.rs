@@ -0,0 +1,66 @@
+// SPDX-FileCopyrightText: 2023 Jonathan Frere
+//
+// SPDX-License-Identifier: MPL-2.0
+
+mod common;
+use std::collections::HashMap;
+
+use chrono::{Duration, Local};
+use homie::{auth::HouseholdId, tasks::Stats};
+use reqwest::{Method, StatusCode};
+
+#[tokio::test]
+async fn fetches_household_stats() {
+    let server = common::harness_with_token().await;
+    server
+        .auth_store()
+        .create_user("Kevin", "", HouseholdId::default())
+        .await
+        .unwrap();
+    server
+        .task_store()
+        .add_task(homie::tasks::NewTask {
+            names: HashMap::from([("en".to_owned(), "Task 1".to_owned())]),
+            routine: homie::tasks::Routine::Interval,
+            duration: 7,
+            participants: vec!["Kevin".to_owned()],
+            starts_on: (Local::now() - Duration::days(2)).date_naive(),
+            starts_with: "Kevin".to_owned(),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    server
+        .request(Method::POST, "/api/tasks/actions/mark_task_done/1?by=Kevin")
+        .send()
+        .await
+        .unwrap();
+
+    let stats = server
+        .request(Method::GET, "/api/stats?period=Month")
+        .send()
+        .await
+        .unwrap()
+        .json::<Stats>()
+        .await
+        .unwrap();
+
+    assert_eq!(stats.people.len(), 1);
+    assert_eq!(stats.people[0].person, "Kevin");
+    assert_eq!(stats.people[0].punctuality.late, 1);
+    assert_eq!(stats.tasks[0].name, "Task 1");
+    assert_eq!(stats.tasks[0].punctuality.average_days_late, Some(2.0));
+}
+
+#[tokio::test]
+async fn stats_need_a_login() {
+    let server = common::harness().await;
+
+    let response = server
+        .request(Method::GET, "/api/stats")
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
@@ -6,7 +6,13 @@ mod common;
 use std::collections::HashMap;
 
 use chrono::{Duration, Local};
-use homie::tasks::{Deadline, Task};
+use homie::{
+    auth::HouseholdId,
+    tasks::{
+        Absence, Balance, Chain, Deadline, Feed, ForecastEntry, HistoryEntry, LedgerEntry,
+        LedgerKind, Tag, Task, Template,
+    },
+};
 use proptest::{prelude::*, test_runner::TestRunner};
 use reqwest::Method;
 
@@ -36,8 +42,58 @@ async fn fetches_empty_list_of_tasks() {
 #[tokio::test]
 async fn fetches_tasks_that_exist() {
     let server = common::harness_with_token().await;
-    server.auth_store().create_user("Kevin", "").await.unwrap();
-    server.auth_store().create_user("Bob", "").await.unwrap();
+    server
+        .auth_store()
+        .create_user("Kevin", "", HouseholdId::default())
+        .await
+        .unwrap();
+    server
+        .auth_store()
+        .create_user("Bob", "", HouseholdId::default())
+        .await
+        .unwrap();
+    server
+        .task_store()
+        .add_task(homie::tasks::NewTask {
+            names: names(&[("en", "Task 1")]),
+            routine: homie::tasks::Routine::Interval,
+            duration: 7,
+            participants: vec!["Kevin".to_owned(), "Bob".to_owned()],
+            starts_on: (Local::now() - Duration::days(10)).date_naive(),
+            starts_with: "Kevin".to_owned(),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    let tasks = server
+        .request(Method::GET, "/api/tasks")
+        .send()
+        .await
+        .unwrap()
+        .json::<Vec<Task>>()
+        .await
+        .unwrap();
+
+    assert_eq!(tasks.len(), 1);
+    assert_eq!(tasks[0].name, "Task 1");
+    assert_eq!(tasks[0].assigned_to.as_deref(), Some("Kevin"));
+    assert_eq!(tasks[0].deadline, Deadline::Overdue(10));
+}
+
+#[tokio::test]
+async fn fetches_tasks_for_one_person() {
+    let server = common::harness_with_token().await;
+    server
+        .auth_store()
+        .create_user("Kevin", "", HouseholdId::default())
+        .await
+        .unwrap();
+    server
+        .auth_store()
+        .create_user("Bob", "", HouseholdId::default())
+        .await
+        .unwrap();
     server
         .task_store()
         .add_task(homie::tasks::NewTask {
@@ -47,81 +103,789 @@ async fn fetches_tasks_that_exist() {
             participants: vec!["Kevin".to_owned(), "Bob".to_owned()],
             starts_on: (Local::now() - Duration::days(10)).date_naive(),
             starts_with: "Kevin".to_owned(),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    let tasks = server
+        .request(Method::GET, "/api/tasks/people/Kevin")
+        .send()
+        .await
+        .unwrap()
+        .json::<Vec<Task>>()
+        .await
+        .unwrap();
+
+    assert_eq!(tasks.len(), 1);
+
+    let tasks = server
+        .request(Method::GET, "/api/tasks/people/Bob")
+        .send()
+        .await
+        .unwrap()
+        .json::<Vec<Task>>()
+        .await
+        .unwrap();
+
+    assert_eq!(tasks.len(), 0);
+
+    let tasks = server
+        .request(Method::GET, "/api/tasks/people/unknown")
+        .send()
+        .await
+        .unwrap()
+        .json::<Vec<Task>>()
+        .await
+        .unwrap();
+
+    assert_eq!(tasks.len(), 0);
+}
+
+#[tokio::test]
+async fn filters_and_sorts_tasks_with_query_parameters() {
+    let server = common::harness_with_token().await;
+    server
+        .auth_store()
+        .create_user("Kevin", "", HouseholdId::default())
+        .await
+        .unwrap();
+    server
+        .auth_store()
+        .create_user("Bob", "", HouseholdId::default())
+        .await
+        .unwrap();
+    for (name, routine, participant) in [
+        ("Bins", homie::tasks::Routine::Schedule, "Kevin"),
+        ("Hoovering", homie::tasks::Routine::Interval, "Kevin"),
+        ("Dusting", homie::tasks::Routine::Interval, "Bob"),
+    ] {
+        server
+            .task_store()
+            .add_task(homie::tasks::NewTask {
+                names: names(&[("en", name)]),
+                routine,
+                duration: 7,
+                participants: vec![participant.to_owned()],
+                starts_on: Local::now().date_naive(),
+                starts_with: participant.to_owned(),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+    }
+
+    let tasks = server
+        .request(
+            Method::GET,
+            "/api/tasks?participant=kevin&routine=Interval&search=oo",
+        )
+        .send()
+        .await
+        .unwrap()
+        .json::<Vec<Task>>()
+        .await
+        .unwrap();
+
+    assert_eq!(tasks.len(), 1);
+    assert_eq!(tasks[0].name, "Hoovering");
+
+    let tasks = server
+        .request(Method::GET, "/api/tasks?sort=Name&order=Descending")
+        .send()
+        .await
+        .unwrap()
+        .json::<Vec<Task>>()
+        .await
+        .unwrap();
+
+    let names = tasks.into_iter().map(|task| task.name).collect::<Vec<_>>();
+    assert_eq!(names, vec!["Hoovering", "Dusting", "Bins"]);
+
+    let response = server
+        .request(Method::GET, "/api/tasks?sort=Colour")
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn creates_tags_and_filters_tasks_by_them() {
+    let server = common::harness_with_token().await;
+    server
+        .auth_store()
+        .create_user("Kevin", "", HouseholdId::default())
+        .await
+        .unwrap();
+    for name in ["Washing up", "Hoovering"] {
+        server
+            .task_store()
+            .add_task(homie::tasks::NewTask {
+                names: names(&[("en", name)]),
+                routine: homie::tasks::Routine::Interval,
+                duration: 7,
+                participants: vec!["Kevin".to_owned()],
+                starts_on: Local::now().date_naive(),
+                starts_with: "Kevin".to_owned(),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+    }
+
+    let tag = server
+        .request(Method::POST, "/api/tasks/tags")
+        .json(&serde_json::json!({ "names": { "en": "Kitchen", "de": "Küche" } }))
+        .send()
+        .await
+        .unwrap()
+        .json::<Tag>()
+        .await
+        .unwrap();
+    assert_eq!(tag.name, "Kitchen");
+    let tag_id = serde_json::to_string(&tag.id).unwrap();
+
+    let task = server
+        .request(
+            Method::POST,
+            format!("/api/tasks/actions/tag_task/1?tag={tag_id}"),
+        )
+        .send()
+        .await
+        .unwrap()
+        .json::<Task>()
+        .await
+        .unwrap();
+    assert_eq!(task.tags, vec![tag.clone()]);
+
+    let tasks = server
+        .request(Method::GET, format!("/api/tasks?tag={tag_id}"))
+        .send()
+        .await
+        .unwrap()
+        .json::<Vec<Task>>()
+        .await
+        .unwrap();
+    assert_eq!(tasks.len(), 1);
+    assert_eq!(tasks[0].name, "Washing up");
+
+    let response = server
+        .request(Method::DELETE, format!("/api/tasks/tags/{tag_id}"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::NO_CONTENT);
+
+    let tags = server
+        .request(Method::GET, "/api/tasks/tags")
+        .send()
+        .await
+        .unwrap()
+        .json::<Vec<Tag>>()
+        .await
+        .unwrap();
+    assert_eq!(tags, vec![]);
+}
+
+#[tokio::test]
+async fn forecasts_upcoming_deadlines() {
+    let server = common::harness_with_token().await;
+    server
+        .auth_store()
+        .create_user("Kevin", "", HouseholdId::default())
+        .await
+        .unwrap();
+    server
+        .auth_store()
+        .create_user("Bob", "", HouseholdId::default())
+        .await
+        .unwrap();
+    server
+        .task_store()
+        .add_task(homie::tasks::NewTask {
+            names: names(&[("en", "Task 1")]),
+            routine: homie::tasks::Routine::Interval,
+            duration: 7,
+            participants: vec!["Kevin".to_owned(), "Bob".to_owned()],
+            starts_on: Local::now().date_naive(),
+            starts_with: "Kevin".to_owned(),
+            ..Default::default()
         })
         .await
         .unwrap();
 
-    let tasks = server
-        .request(Method::GET, "/api/tasks")
+    let forecast = server
+        .request(Method::GET, "/api/tasks/forecast?weeks=2")
+        .send()
+        .await
+        .unwrap()
+        .json::<Vec<ForecastEntry>>()
+        .await
+        .unwrap();
+    let assignees = forecast
+        .iter()
+        .map(|entry| entry.assigned_to.as_deref())
+        .collect::<Vec<_>>();
+    assert_eq!(assignees, vec![Some("Kevin"), Some("Bob"), Some("Kevin")]);
+
+    let response = server
+        .request(Method::GET, "/api/tasks/forecast?weeks=100")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn calendar_feeds_can_be_fetched_without_logging_in() {
+    let server = common::harness_with_token().await;
+    server
+        .auth_store()
+        .create_user("Kevin", "", HouseholdId::default())
+        .await
+        .unwrap();
+    server
+        .task_store()
+        .add_task(homie::tasks::NewTask {
+            names: names(&[("en", "Task 1")]),
+            routine: homie::tasks::Routine::Interval,
+            duration: 7,
+            participants: vec!["Kevin".to_owned()],
+            starts_on: Local::now().date_naive(),
+            starts_with: "Kevin".to_owned(),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    let feed = server
+        .request(Method::POST, "/api/tasks/feeds")
+        .json(&serde_json::json!({ "person": "kevin" }))
+        .send()
+        .await
+        .unwrap()
+        .json::<Feed>()
+        .await
+        .unwrap();
+    assert_eq!(feed.person.as_deref(), Some("Kevin"));
+
+    // calendar apps can't send the token header
+    let response = reqwest::get(server.url(format!("/api/calendar/{}.ics", feed.secret)))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+    assert_eq!(
+        response.headers()["content-type"],
+        "text/calendar; charset=utf-8"
+    );
+    let calendar = response.text().await.unwrap();
+    assert!(calendar.starts_with("BEGIN:VCALENDAR\r\n"));
+    assert!(calendar.contains("SUMMARY:Task 1\r\n"));
+
+    let response = reqwest::get(server.url("/api/calendar/unknown.ics"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn caldav_clients_can_list_and_complete_todos() {
+    let server = common::harness_with_token().await;
+    server
+        .auth_store()
+        .create_user("Kevin", "secret", HouseholdId::default())
+        .await
+        .unwrap();
+    server
+        .auth_store()
+        .create_user("Bob", "", HouseholdId::default())
+        .await
+        .unwrap();
+    let today = Local::now().date_naive();
+    server
+        .task_store()
+        .add_task(homie::tasks::NewTask {
+            names: names(&[("en", "Task 1")]),
+            routine: homie::tasks::Routine::Interval,
+            duration: 7,
+            participants: vec!["Kevin".to_owned()],
+            starts_on: today,
+            starts_with: "Kevin".to_owned(),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    let task_id = server
+        .request(Method::GET, "/api/tasks")
+        .send()
+        .await
+        .unwrap()
+        .json::<Vec<Task>>()
+        .await
+        .unwrap()[0]
+        .id;
+    let propfind = Method::from_bytes(b"PROPFIND").unwrap();
+    let report = Method::from_bytes(b"REPORT").unwrap();
+    let client = reqwest::Client::new();
+
+    let response = client
+        .request(propfind.clone(), server.url("/caldav/"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+    assert!(response.headers().contains_key("www-authenticate"));
+
+    let response = client
+        .request(propfind.clone(), server.url("/caldav/"))
+        .basic_auth("kevin", Some("secret"))
+        .header("depth", "0")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::MULTI_STATUS);
+    let body = response.text().await.unwrap();
+    assert!(body.contains(
+        "<D:current-user-principal><D:href>/caldav/kevin/</D:href></D:current-user-principal>"
+    ));
+
+    let response = client
+        .request(propfind.clone(), server.url("/caldav/kevin/tasks/"))
+        .basic_auth("kevin", Some("secret"))
+        .header("depth", "1")
+        .send()
+        .await
+        .unwrap();
+    let body = response.text().await.unwrap();
+    assert!(body.contains(r#"<C:comp name="VTODO"/>"#));
+    assert!(body.contains(&format!(
+        "<D:href>/caldav/kevin/tasks/{task_id}.ics</D:href>"
+    )));
+
+    let response = client
+        .request(report, server.url("/caldav/kevin/tasks/"))
+        .basic_auth("kevin", Some("secret"))
+        .body(r#"<C:calendar-query xmlns:C="urn:ietf:params:xml:ns:caldav"/>"#)
+        .send()
+        .await
+        .unwrap();
+    let body = response.text().await.unwrap();
+    assert!(body.contains("SUMMARY:Task 1"));
+    assert!(body.contains(&format!("DUE;VALUE=DATE:{}", today.format("%Y%m%d"))));
+
+    // people can't see each other's tasks
+    let response = client
+        .request(propfind, server.url("/caldav/kevin/tasks/"))
+        .basic_auth("Bob", Some(""))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::FORBIDDEN);
+
+    let todo_url = server.url(format!("/caldav/kevin/tasks/{task_id}.ics"));
+    let todo = client
+        .get(&todo_url)
+        .basic_auth("kevin", Some("secret"))
+        .send()
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+    let completed = todo.replace(
+        "STATUS:NEEDS-ACTION",
+        &format!(
+            "STATUS:COMPLETED\r\nCOMPLETED;VALUE=DATE:{}",
+            today.format("%Y%m%d")
+        ),
+    );
+    let response = client
+        .put(&todo_url)
+        .basic_auth("kevin", Some("secret"))
+        .body(completed)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::NO_CONTENT);
+
+    let task = server
+        .request(Method::GET, "/api/tasks/people/kevin")
+        .send()
+        .await
+        .unwrap()
+        .json::<Vec<Task>>()
+        .await
+        .unwrap()
+        .remove(0);
+    assert_eq!(task.last_completed, today);
+    assert_eq!(task.deadline, Deadline::Upcoming(7));
+}
+
+#[tokio::test]
+async fn absences_can_be_added_and_removed() {
+    let server = common::harness_with_token().await;
+    server
+        .auth_store()
+        .create_user("Kevin", "", HouseholdId::default())
+        .await
+        .unwrap();
+    server
+        .auth_store()
+        .create_user("Bob", "", HouseholdId::default())
+        .await
+        .unwrap();
+    server
+        .task_store()
+        .add_task(homie::tasks::NewTask {
+            names: names(&[("en", "Task 1")]),
+            routine: homie::tasks::Routine::Interval,
+            duration: 7,
+            participants: vec!["Kevin".to_owned(), "Bob".to_owned()],
+            starts_on: Local::now().date_naive(),
+            starts_with: "Kevin".to_owned(),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    let today = Local::now().date_naive();
+    let absence = server
+        .request(Method::POST, "/api/tasks/people/kevin/absences")
+        .json(&serde_json::json!({ "starts_on": today, "ends_on": today + Duration::days(3) }))
+        .send()
+        .await
+        .unwrap()
+        .json::<Absence>()
+        .await
+        .unwrap();
+    assert_eq!(absence.person, "Kevin");
+
+    let tasks = server
+        .request(Method::GET, "/api/tasks")
+        .send()
+        .await
+        .unwrap()
+        .json::<Vec<Task>>()
+        .await
+        .unwrap();
+    assert_eq!(tasks[0].assigned_to.as_deref(), Some("Bob"));
+
+    let response = server
+        .request(
+            Method::DELETE,
+            format!("/api/tasks/absences/{}", absence.id),
+        )
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::NO_CONTENT);
+
+    let absences = server
+        .request(Method::GET, "/api/tasks/people/Kevin/absences")
+        .send()
+        .await
+        .unwrap()
+        .json::<Vec<Absence>>()
+        .await
+        .unwrap();
+    assert_eq!(absences, vec![]);
+}
+
+#[tokio::test]
+async fn timezones_can_be_set_for_the_household_and_people() {
+    let server = common::harness_with_token().await;
+    server
+        .auth_store()
+        .create_user("Kevin", "", HouseholdId::default())
+        .await
+        .unwrap();
+
+    let timezone = server
+        .request(Method::PUT, "/api/tasks/timezone")
+        .json(&serde_json::json!({ "timezone": "Europe/Berlin" }))
+        .send()
+        .await
+        .unwrap()
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    assert_eq!(timezone, serde_json::json!({ "timezone": "Europe/Berlin" }));
+
+    // people use the household's timezone until they set their own
+    let timezone = server
+        .request(Method::GET, "/api/tasks/people/Kevin/timezone")
+        .send()
+        .await
+        .unwrap()
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    assert_eq!(timezone, serde_json::json!({ "timezone": "Europe/Berlin" }));
+
+    let timezone = server
+        .request(Method::PUT, "/api/tasks/people/kevin/timezone")
+        .json(&serde_json::json!({ "timezone": "Pacific/Auckland" }))
         .send()
         .await
         .unwrap()
-        .json::<Vec<Task>>()
+        .json::<serde_json::Value>()
         .await
         .unwrap();
+    assert_eq!(
+        timezone,
+        serde_json::json!({ "timezone": "Pacific/Auckland" })
+    );
 
-    assert_eq!(tasks.len(), 1);
-    assert_eq!(tasks[0].name, "Task 1");
-    assert_eq!(tasks[0].assigned_to, "Kevin");
-    assert_eq!(tasks[0].deadline, Deadline::Overdue(10));
+    let response = server
+        .request(Method::PUT, "/api/tasks/timezone")
+        .json(&serde_json::json!({ "timezone": "Europe/Atlantis" }))
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_client_error());
+
+    let response = server
+        .request(Method::PUT, "/api/tasks/people/nobody/timezone")
+        .json(&serde_json::json!({ "timezone": null }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
 }
 
 #[tokio::test]
-async fn fetches_tasks_for_one_person() {
+async fn tasks_can_be_created_from_templates() {
+    let server = common::harness_with_token().await;
+    server
+        .auth_store()
+        .create_user("Kevin", "", HouseholdId::default())
+        .await
+        .unwrap();
+    server
+        .auth_store()
+        .create_user("Bob", "", HouseholdId::default())
+        .await
+        .unwrap();
+
+    let templates = server
+        .request(Method::GET, "/api/tasks/templates")
+        .send()
+        .await
+        .unwrap()
+        .json::<Vec<Template>>()
+        .await
+        .unwrap();
+    assert!(templates.iter().any(|template| template.id == "hoover"));
+
+    let task = server
+        .request(Method::POST, "/api/tasks/templates/hoover")
+        .header("Accept-Language", "de")
+        .json(&serde_json::json!({ "participants": ["Kevin", "Bob"] }))
+        .send()
+        .await
+        .unwrap()
+        .json::<Task>()
+        .await
+        .unwrap();
+    assert_eq!(task.name, "Staubsaugen");
+    assert_eq!(task.length_days, 7);
+    assert_eq!(task.assigned_to.as_deref(), Some("Kevin"));
+    assert_eq!(task.participants, vec!["Kevin", "Bob"]);
+
+    let response = server
+        .request(Method::POST, "/api/tasks/templates/juggle")
+        .json(&serde_json::json!({ "participants": ["Kevin"] }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn points_are_earned_spent_and_exported() {
     let server = common::harness_with_token().await;
-    server.auth_store().create_user("Kevin", "").await.unwrap();
-    server.auth_store().create_user("Bob", "").await.unwrap();
+    server
+        .auth_store()
+        .create_user("Kevin", "", HouseholdId::default())
+        .await
+        .unwrap();
     server
         .task_store()
         .add_task(homie::tasks::NewTask {
             names: names(&[("en", "Task 1")]),
             routine: homie::tasks::Routine::Interval,
             duration: 7,
-            participants: vec!["Kevin".to_owned(), "Bob".to_owned()],
-            starts_on: (Local::now() - Duration::days(10)).date_naive(),
+            participants: vec!["Kevin".to_owned()],
+            starts_on: Local::now().date_naive(),
             starts_with: "Kevin".to_owned(),
+            ..Default::default()
         })
         .await
         .unwrap();
 
-    let tasks = server
-        .request(Method::GET, "/api/tasks/people/Kevin")
+    let task = server
+        .request(Method::POST, "/api/tasks/actions/set_points/1")
+        .json(&serde_json::json!({ "points": 4 }))
         .send()
         .await
         .unwrap()
-        .json::<Vec<Task>>()
+        .json::<Task>()
         .await
         .unwrap();
+    assert_eq!(task.points, 4);
 
-    assert_eq!(tasks.len(), 1);
+    server
+        .request(Method::POST, "/api/tasks/actions/mark_task_done/1?by=Kevin")
+        .send()
+        .await
+        .unwrap();
+    let entry = server
+        .request(Method::POST, "/api/tasks/people/kevin/points/redemptions")
+        .json(&serde_json::json!({ "points": 3, "description": "Ice cream" }))
+        .send()
+        .await
+        .unwrap()
+        .json::<LedgerEntry>()
+        .await
+        .unwrap();
+    assert_eq!(entry.kind, LedgerKind::Redemption);
+    assert_eq!(entry.points, -3);
 
-    let tasks = server
-        .request(Method::GET, "/api/tasks/people/Bob")
+    let response = server
+        .request(Method::POST, "/api/tasks/people/kevin/points/redemptions")
+        .json(&serde_json::json!({ "points": 3 }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+
+    let balances = server
+        .request(Method::GET, "/api/tasks/points?person=kevin")
         .send()
         .await
         .unwrap()
-        .json::<Vec<Task>>()
+        .json::<Vec<Balance>>()
         .await
         .unwrap();
+    assert_eq!(
+        balances,
+        vec![Balance {
+            person: "Kevin".to_owned(),
+            points: 1
+        }]
+    );
 
-    assert_eq!(tasks.len(), 0);
+    let response = server
+        .request(Method::GET, "/api/tasks/points/ledger.csv")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(
+        response.headers()[reqwest::header::CONTENT_TYPE],
+        "text/csv; charset=utf-8"
+    );
+    let csv = response.text().await.unwrap();
+    let lines = csv.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 3);
+    assert!(lines[1].contains(",Kevin,Completion,4,1,"));
+    assert!(lines[2].ends_with(",Kevin,Redemption,-3,,Ice cream"));
+}
+
+#[tokio::test]
+async fn chained_tasks_are_triggered_by_completions() {
+    let server = common::harness_with_token().await;
+    server
+        .auth_store()
+        .create_user("Kevin", "", HouseholdId::default())
+        .await
+        .unwrap();
+    server
+        .auth_store()
+        .create_user("Bob", "", HouseholdId::default())
+        .await
+        .unwrap();
+    for (name, routine) in [
+        ("Wash the sheets", homie::tasks::Routine::Interval),
+        ("Put the sheets away", homie::tasks::Routine::Triggered),
+    ] {
+        server
+            .task_store()
+            .add_task(homie::tasks::NewTask {
+                names: names(&[("en", name)]),
+                routine,
+                duration: 7,
+                participants: vec!["Kevin".to_owned(), "Bob".to_owned()],
+                starts_on: Local::now().date_naive(),
+                starts_with: "Kevin".to_owned(),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+    }
+
+    let chain = server
+        .request(Method::POST, "/api/tasks/chains")
+        .json(&serde_json::json!({
+            "from_task": 1,
+            "to_task": 2,
+            "offset_days": 1,
+            "assignee": "SamePerson",
+        }))
+        .send()
+        .await
+        .unwrap()
+        .json::<Chain>()
+        .await
+        .unwrap();
+    assert_eq!(chain.offset_days, 1);
+
+    server
+        .request(Method::POST, "/api/tasks/actions/mark_task_done/1?by=Bob")
+        .send()
+        .await
+        .unwrap();
 
     let tasks = server
-        .request(Method::GET, "/api/tasks/people/unknown")
+        .request(Method::GET, "/api/tasks")
         .send()
         .await
         .unwrap()
         .json::<Vec<Task>>()
         .await
         .unwrap();
+    assert_eq!(tasks.len(), 2);
+    assert_eq!(tasks[1].name, "Put the sheets away");
+    assert_eq!(tasks[1].deadline, Deadline::Upcoming(1));
+    assert_eq!(tasks[1].assigned_to.as_deref(), Some("Bob"));
 
-    assert_eq!(tasks.len(), 0);
+    let response = server
+        .request(Method::DELETE, format!("/api/tasks/chains/{}", chain.id))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::NO_CONTENT);
+
+    let chains = server
+        .request(Method::GET, "/api/tasks/chains")
+        .send()
+        .await
+        .unwrap()
+        .json::<Vec<Chain>>()
+        .await
+        .unwrap();
+    assert_eq!(chains, vec![]);
 }
 
 #[tokio::test]
 async fn fetches_tasks_for_people_case_insensitive() {
     let server = common::harness_with_token().await;
-    server.auth_store().create_user("Kevin", "").await.unwrap();
+    server
+        .auth_store()
+        .create_user("Kevin", "", HouseholdId::default())
+        .await
+        .unwrap();
     server
         .task_store()
         .add_task(homie::tasks::NewTask {
@@ -131,6 +895,7 @@ async fn fetches_tasks_for_people_case_insensitive() {
             participants: vec!["Kevin".to_owned()],
             starts_on: (Local::now() - Duration::days(10)).date_naive(),
             starts_with: "Kevin".to_owned(),
+            ..Default::default()
         })
         .await
         .unwrap();
@@ -172,8 +937,16 @@ async fn fetches_tasks_for_people_case_insensitive() {
 #[tokio::test]
 async fn updates_tasks_for_current_day() {
     let server = common::harness_with_token().await;
-    server.auth_store().create_user("Kevin", "").await.unwrap();
-    server.auth_store().create_user("Bob", "").await.unwrap();
+    server
+        .auth_store()
+        .create_user("Kevin", "", HouseholdId::default())
+        .await
+        .unwrap();
+    server
+        .auth_store()
+        .create_user("Bob", "", HouseholdId::default())
+        .await
+        .unwrap();
     server
         .task_store()
         .add_task(homie::tasks::NewTask {
@@ -183,6 +956,7 @@ async fn updates_tasks_for_current_day() {
             participants: vec!["Kevin".to_owned(), "Bob".to_owned()],
             starts_on: (Local::now() - Duration::days(10)).date_naive(),
             starts_with: "Kevin".to_owned(),
+            ..Default::default()
         })
         .await
         .unwrap();
@@ -200,11 +974,186 @@ async fn updates_tasks_for_current_day() {
     assert_eq!(updated.last_completed, Local::now().date_naive());
 }
 
+#[tokio::test]
+async fn task_update_can_record_checklist_steps() {
+    let server = common::harness_with_token().await;
+    server
+        .auth_store()
+        .create_user("Kevin", "", HouseholdId::default())
+        .await
+        .unwrap();
+    server
+        .task_store()
+        .add_task(homie::tasks::NewTask {
+            names: names(&[("en", "Bathroom")]),
+            checklist: vec![names(&[("en", "Scrub bath")]), names(&[("en", "Mop")])],
+            routine: homie::tasks::Routine::Interval,
+            duration: 7,
+            participants: vec!["Kevin".to_owned()],
+            starts_on: Local::now().date_naive(),
+            starts_with: "Kevin".to_owned(),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    let task = server.task_store().tasks(&"en".into()).await.unwrap()[0].clone();
+
+    let updated = server
+        .request(Method::POST, "/api/tasks/actions/mark_task_done/1?by=Kevin")
+        .json(&serde_json::json!({ "steps_done": [task.checklist[1].id] }))
+        .send()
+        .await
+        .unwrap()
+        .json::<Task>()
+        .await
+        .unwrap();
+
+    assert_eq!(updated.last_steps_done, Some(vec![task.checklist[1].id]));
+}
+
+#[tokio::test]
+async fn task_update_can_upload_a_note_and_photos() {
+    let server = common::harness_with_token().await;
+    server
+        .auth_store()
+        .create_user("Kevin", "", HouseholdId::default())
+        .await
+        .unwrap();
+    server
+        .task_store()
+        .add_task(homie::tasks::NewTask {
+            names: names(&[("en", "Oven")]),
+            checklist: vec![names(&[("en", "Racks")]), names(&[("en", "Door")])],
+            routine: homie::tasks::Routine::Interval,
+            duration: 7,
+            participants: vec!["Kevin".to_owned()],
+            starts_on: Local::now().date_naive(),
+            starts_with: "Kevin".to_owned(),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    let task = server.task_store().tasks(&"en".into()).await.unwrap()[0].clone();
+    let photo = || {
+        reqwest::multipart::Part::bytes(vec![0xff, 0xd8, 0xff])
+            .file_name("oven.jpg")
+            .mime_str("image/jpeg")
+            .unwrap()
+    };
+
+    let form = reqwest::multipart::Form::new()
+        .text("steps_done", task.checklist[0].id.to_string())
+        .text("note", "Sparkling")
+        .part("attachment", photo());
+    let updated = server
+        .request(Method::POST, "/api/tasks/actions/mark_task_done/1?by=Kevin")
+        .multipart(form)
+        .send()
+        .await
+        .unwrap()
+        .json::<Task>()
+        .await
+        .unwrap();
+    assert_eq!(updated.last_steps_done, Some(vec![task.checklist[0].id]));
+
+    let history = server
+        .request(Method::GET, "/api/tasks/history/1")
+        .send()
+        .await
+        .unwrap()
+        .json::<Vec<HistoryEntry>>()
+        .await
+        .unwrap();
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].completed_by, "Kevin");
+    assert_eq!(history[0].note.as_deref(), Some("Sparkling"));
+    assert_eq!(history[0].attachments.len(), 1);
+
+    let path = format!("/api/tasks/attachments/{}", history[0].attachments[0].id);
+    let response = server.request(Method::GET, &path).send().await.unwrap();
+    assert_eq!(response.headers()["content-type"], "image/jpeg");
+    assert_eq!(response.bytes().await.unwrap().as_ref(), [0xff, 0xd8, 0xff]);
+
+    // attachments are only for people who are logged in
+    let response = reqwest::get(server.url(&path)).await.unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+
+    let form = reqwest::multipart::Form::new().part(
+        "attachment",
+        reqwest::multipart::Part::text("<svg/>")
+            .file_name("oven.svg")
+            .mime_str("image/svg+xml")
+            .unwrap(),
+    );
+    let response = server
+        .request(Method::POST, "/api/tasks/actions/mark_task_done/1?by=Kevin")
+        .multipart(form)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn task_update_can_name_helpers_in_a_form() {
+    let server = common::harness_with_token().await;
+    for person in ["Kevin", "Bob", "Eve"] {
+        server
+            .auth_store()
+            .create_user(person, "", HouseholdId::default())
+            .await
+            .unwrap();
+    }
+    server
+        .task_store()
+        .add_task(homie::tasks::NewTask {
+            names: names(&[("en", "Move the sofa")]),
+            routine: homie::tasks::Routine::Interval,
+            duration: 7,
+            participants: vec!["Kevin".to_owned()],
+            starts_on: Local::now().date_naive(),
+            starts_with: "Kevin".to_owned(),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    let form = reqwest::multipart::Form::new()
+        .text("helpers", "Bob")
+        .text("helpers", "Eve");
+    let response = server
+        .request(Method::POST, "/api/tasks/actions/mark_task_done/1?by=Kevin")
+        .multipart(form)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+
+    let history = server
+        .request(Method::GET, "/api/tasks/history/1")
+        .send()
+        .await
+        .unwrap()
+        .json::<Vec<HistoryEntry>>()
+        .await
+        .unwrap();
+    assert_eq!(history[0].completed_by, "Kevin");
+    assert_eq!(history[0].helpers, vec!["Bob", "Eve"]);
+}
+
 #[tokio::test]
 async fn task_update_can_set_date_explicitly() {
     let server = common::harness_with_token().await;
-    server.auth_store().create_user("Kevin", "").await.unwrap();
-    server.auth_store().create_user("Bob", "").await.unwrap();
+    server
+        .auth_store()
+        .create_user("Kevin", "", HouseholdId::default())
+        .await
+        .unwrap();
+    server
+        .auth_store()
+        .create_user("Bob", "", HouseholdId::default())
+        .await
+        .unwrap();
     server
         .task_store()
         .add_task(homie::tasks::NewTask {
@@ -214,6 +1163,7 @@ async fn task_update_can_set_date_explicitly() {
             participants: vec!["Kevin".to_owned(), "Bob".to_owned()],
             starts_on: (Local::now() - Duration::days(10)).date_naive(),
             starts_with: "Kevin".to_owned(),
+            ..Default::default()
         })
         .await
         .unwrap();
@@ -252,26 +1202,36 @@ async fn task_update_can_set_date_explicitly() {
     assert_eq!(updated.deadline, Deadline::Upcoming(7));
     assert_eq!(updated.last_completed, today);
 
-    let updated = server
+    // tasks can't be done in the future
+    let response = server
         .request(
             Method::POST,
             format!("/api/tasks/actions/mark_task_done/1?by=Bob&on={after_tomorrow}"),
         )
         .send()
         .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+
+    let tasks = server
+        .request(Method::GET, "/api/tasks")
+        .send()
+        .await
         .unwrap()
-        .json::<Task>()
+        .json::<Vec<Task>>()
         .await
         .unwrap();
-
-    assert_eq!(updated.deadline, Deadline::Upcoming(9));
-    assert_eq!(updated.last_completed, after_tomorrow);
+    assert_eq!(tasks[0].last_completed, today);
 }
 
 #[tokio::test]
 async fn fetches_correct_translation_when_accept_lang_header_is_set() {
     let server = common::harness_with_token().await;
-    server.auth_store().create_user("Kevin", "").await.unwrap();
+    server
+        .auth_store()
+        .create_user("Kevin", "", HouseholdId::default())
+        .await
+        .unwrap();
     server
         .task_store()
         .add_task(homie::tasks::NewTask {
@@ -285,6 +1245,7 @@ async fn fetches_correct_translation_when_accept_lang_header_is_set() {
             participants: vec!["Kevin".to_owned()],
             starts_on: (Local::now() - Duration::days(10)).date_naive(),
             starts_with: "Kevin".to_owned(),
+            ..Default::default()
         })
         .await
         .unwrap();
@@ -363,8 +1324,12 @@ fn does_not_crash_on_arbitrary_values_for_accept_language() {
         .unwrap();
 
     let server = rt.block_on(common::harness_with_token());
-    rt.block_on(server.auth_store().create_user("Kevin", ""))
-        .unwrap();
+    rt.block_on(
+        server
+            .auth_store()
+            .create_user("Kevin", "", HouseholdId::default()),
+    )
+    .unwrap();
     rt.block_on(server.task_store().add_task(homie::tasks::NewTask {
         names: names(&[
             ("en", "ENGLISH_NAME"),
@@ -376,6 +1341,7 @@ fn does_not_crash_on_arbitrary_values_for_accept_language() {
         participants: vec!["Kevin".to_owned()],
         starts_on: (Local::now() - Duration::days(10)).date_naive(),
         starts_with: "Kevin".to_owned(),
+        ..Default::default()
     }))
     .unwrap();
 
@@ -400,3 +1366,66 @@ fn does_not_crash_on_arbitrary_values_for_accept_language() {
         })
         .unwrap();
 }
+
+#[tokio::test]
+async fn invalid_completions_are_rejected() {
+    let server = common::harness_with_token().await;
+    for person in ["Kevin", "Bob"] {
+        server
+            .auth_store()
+            .create_user(person, "", HouseholdId::default())
+            .await
+            .unwrap();
+    }
+    let today = Local::now().date_naive();
+    server
+        .task_store()
+        .add_task(homie::tasks::NewTask {
+            names: names(&[("en", "Task 1")]),
+            routine: homie::tasks::Routine::Interval,
+            duration: 7,
+            participants: vec!["Kevin".to_owned()],
+            starts_on: today,
+            starts_with: "Kevin".to_owned(),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    let mark_done = |query: String| {
+        server.request(
+            Method::POST,
+            format!("/api/tasks/actions/mark_task_done/1?{query}"),
+        )
+    };
+    let response = mark_done("by=Bob".to_owned()).send().await.unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+    let response = mark_done("by=Nobody".to_owned()).send().await.unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+    let tomorrow = today + Duration::days(1);
+    let response = mark_done(format!("by=Kevin&on={tomorrow}"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+
+    let limit = server
+        .request(Method::PUT, "/api/tasks/backdate_limit")
+        .json(&serde_json::json!({ "days": 1 }))
+        .send()
+        .await
+        .unwrap()
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    assert_eq!(limit["days"], 1);
+    let two_days_ago = today - Duration::days(2);
+    let response = mark_done(format!("by=Kevin&on={two_days_ago}"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+
+    let response = mark_done("by=Kevin".to_owned()).send().await.unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+}